@@ -23,6 +23,7 @@ pub struct Span {
 pub enum TypX {
     Bool,
     Int,
+    BitVec(u32),
     Named(Ident),
 }
 
@@ -30,11 +31,13 @@ pub enum TypX {
 pub enum Constant {
     Bool(bool),
     Nat(Arc<String>),
+    BitVec(Arc<String>, u32), // value, width
 }
 
 #[derive(Copy, Clone, Debug)]
 pub enum UnaryOp {
     Not,
+    BitNot,
 }
 
 #[derive(Copy, Clone, Debug)]
@@ -47,6 +50,21 @@ pub enum BinaryOp {
     Gt,
     EuclideanDiv,
     EuclideanMod,
+    // Bit-vector operations (operands must have the same width; division and comparison are unsigned)
+    BitAnd,
+    BitOr,
+    BitXor,
+    BitAdd,
+    BitSub,
+    BitMul,
+    BitUDiv,
+    BitURem,
+    BitShl,
+    BitLShr,
+    BitULt,
+    BitULe,
+    BitUGt,
+    BitUGe,
 }
 
 #[derive(Copy, Clone, Debug)]
@@ -16,6 +16,7 @@ fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> Result<(), std::fmt::Error> {
         match self {
             Constant::Bool(b) => write!(f, "{}", b),
             Constant::Nat(n) => write!(f, "{}", n),
+            Constant::BitVec(n, width) => write!(f, "{}bv{}", n, width),
         }
     }
 }
@@ -5,7 +5,13 @@
 use crate::def::SWITCH_LABEL;
 use std::sync::Arc;
 
-fn stmt_to_expr(label_n: &mut u64, locals: &mut Vec<Decl>, stmt: &Stmt, pred: Expr) -> Expr {
+struct State {
+    switch_label: u64,
+    locals: Vec<Decl>,
+    multiple_errors: bool,
+}
+
+fn stmt_to_expr(state: &mut State, stmt: &Stmt, pred: Expr) -> Expr {
     match &**stmt {
         StmtX::Assume(expr) => {
             // wp((assume Q), P) = Q ==> P
@@ -14,6 +20,14 @@ fn stmt_to_expr(label_n: &mut u64, locals: &mut Vec<Decl>, stmt: &Stmt, pred: Ex
         StmtX::Assert(span, expr) => {
             // wp((assert Q), P) = Q /\ P
             let assertion = Arc::new(ExprX::LabeledAssertion(span.clone(), expr.clone()));
+            let pred = if state.multiple_errors {
+                // wp((assert Q), P) = Q /\ (Q ==> P)
+                // This form duplicates Q, but when the labeled assertion Q is disabled
+                // (see Context::check_valid_again), Q is still assumed for the rest of the query
+                Arc::new(ExprX::Binary(BinaryOp::Implies, expr.clone(), pred))
+            } else {
+                pred
+            };
             Arc::new(ExprX::Multi(MultiOp::And, Arc::new(vec![assertion, pred])))
         }
         StmtX::Havoc(_) => panic!("internal error: Havoc in block_to_assert"),
@@ -23,7 +37,7 @@ fn stmt_to_expr(label_n: &mut u64, locals: &mut Vec<Decl>, stmt: &Stmt, pred: Ex
             // wp((s1; s2), P) = wp(s1, wp(s2, P))
             let mut p = pred;
             for stmt in stmts.iter().rev() {
-                p = stmt_to_expr(label_n, locals, stmt, p);
+                p = stmt_to_expr(state, stmt, p);
             }
             p
         }
@@ -32,13 +46,13 @@ fn stmt_to_expr(label_n: &mut u64, locals: &mut Vec<Decl>, stmt: &Stmt, pred: Ex
             // To avoid duplicating P, we use:
             // wp((s1 or s2), P) = (P ==> label) ==> wp(s1, label) /\ wp(s2, label)
             //                   = (wp(s1, label) /\ wp(s2, label)) \/ (!label /\ P)
-            let label = Arc::new(format!("{}{}", SWITCH_LABEL, label_n));
-            *label_n += 1;
-            locals.push(Arc::new(DeclX::Const(label.clone(), bool_typ())));
+            let label = Arc::new(format!("{}{}", SWITCH_LABEL, state.switch_label));
+            state.switch_label += 1;
+            state.locals.push(Arc::new(DeclX::Const(label.clone(), bool_typ())));
             let exp_label = Arc::new(ExprX::Var(label));
             let mut exprs: Vec<Expr> = Vec::new();
             for stmt in stmts.iter() {
-                exprs.push(stmt_to_expr(label_n, locals, stmt, exp_label.clone()));
+                exprs.push(stmt_to_expr(state, stmt, exp_label.clone()));
             }
             let neg_label = Arc::new(ExprX::Unary(UnaryOp::Not, exp_label));
             let and1 = Arc::new(ExprX::Multi(MultiOp::And, Arc::new(exprs)));
@@ -48,11 +62,11 @@ fn stmt_to_expr(label_n: &mut u64, locals: &mut Vec<Decl>, stmt: &Stmt, pred: Ex
     }
 }
 
-pub(crate) fn lower_query(query: &Query) -> Query {
-    let mut locals: Vec<Decl> = (*query.local).clone();
-    let mut switch_label: u64 = 0;
+pub(crate) fn lower_query(query: &Query, multiple_errors: bool) -> Query {
+    let locals: Vec<Decl> = (*query.local).clone();
+    let mut state = State { switch_label: 0, locals, multiple_errors };
     let tru = Arc::new(ExprX::Const(Constant::Bool(true)));
-    let expr = stmt_to_expr(&mut switch_label, &mut locals, &query.assertion, tru);
+    let expr = stmt_to_expr(&mut state, &query.assertion, tru);
     let assertion = Arc::new(StmtX::Assert(Arc::new(None), expr));
-    Arc::new(QueryX { local: Arc::new(locals), assertion })
+    Arc::new(QueryX { local: Arc::new(state.locals), assertion })
 }
@@ -1,9 +1,13 @@
 use crate::ast::{Command, CommandX, Decl, Ident, Query, SpanOption, TypeError};
 use crate::emitter::Emitter;
 use crate::model::Model;
+use crate::printer::{decl_to_node, hash_node_without_spans, query_to_node, str_to_node};
 use crate::scope_map::ScopeMap;
 use crate::smt_manager::SmtManager;
+use crate::smt_verify::QueryContext;
 use crate::typecheck::Typing;
+use sha2::{Digest, Sha256};
+use sise::Node;
 use std::collections::HashSet;
 use std::sync::Arc;
 
@@ -18,6 +22,9 @@ pub(crate) struct AssertionInfo {
 pub enum ValidityResult {
     Valid,
     Invalid(Model, SpanOption, SpanOption),
+    /// The SMT solver gave up before finding a proof or a counterexample (e.g. rlimit exceeded).
+    /// Carries the reason reported by the solver.
+    Canceled(String),
     TypeError(TypeError),
 }
 
@@ -28,10 +35,16 @@ pub struct Context {
     pub(crate) typing: Typing,
     pub(crate) debug: bool,
     pub(crate) rlimit: u32,
+    // If enabled, a failed assertion disabled by check_valid_again is still assumed afterwards
+    pub(crate) multiple_errors: bool,
     pub(crate) air_initial_log: Emitter,
     pub(crate) air_middle_log: Emitter,
     pub(crate) air_final_log: Emitter,
     pub(crate) smt_log: Emitter,
+    // If enabled, a hash of the options and declarations for each push/pop scope
+    pub(crate) fingerprints: Option<Vec<Sha256>>,
+    // The most recent query, if it failed and can be checked again
+    pub(crate) query_context: Option<QueryContext>,
 }
 
 impl Context {
@@ -43,10 +56,13 @@ pub fn new(smt_manager: SmtManager) -> Context {
             typing: Typing { decls: crate::scope_map::ScopeMap::new(), snapshots: HashSet::new() },
             debug: false,
             rlimit: 0,
+            multiple_errors: false,
             air_initial_log: Emitter::new(false, None),
             air_middle_log: Emitter::new(false, None),
             air_final_log: Emitter::new(false, None),
             smt_log: Emitter::new(true, None),
+            fingerprints: None,
+            query_context: None,
         };
         context.assert_infos.push_scope(false);
         context.typing.decls.push_scope(false);
@@ -77,8 +93,44 @@ pub fn get_debug(&self) -> bool {
         self.debug
     }
 
+    /// Encode queries so that check_valid_again can report failures that depend on
+    /// earlier failed assertions holding (at the cost of slightly larger queries).
+    /// Call this if check_valid_again will be used.
+    pub fn set_multiple_errors(&mut self, multiple_errors: bool) {
+        self.multiple_errors = multiple_errors;
+    }
+
+    /// Start hashing all options and declarations, so that query_fingerprint can identify queries
+    /// across runs (e.g. to cache query results).
+    /// This should be called before setting any options.
+    pub fn enable_fingerprints(&mut self) {
+        self.fingerprints = Some(vec![Sha256::new()]);
+    }
+
+    fn add_fingerprint(&mut self, node: &Node) {
+        if let Some(fingerprints) = &mut self.fingerprints {
+            hash_node_without_spans(node, fingerprints.last_mut().expect("fingerprint scope"));
+        }
+    }
+
+    fn add_option_fingerprint(&mut self, option: &str, value: &str) {
+        let nodes = vec![str_to_node("set-option"), str_to_node(option), str_to_node(value)];
+        self.add_fingerprint(&Node::List(nodes));
+    }
+
+    /// If enable_fingerprints was called, return a SHA-256 hash (as 64 hex digits)
+    /// of the query and everything in scope.
+    /// The hash does not depend on the source locations in the query and declarations.
+    pub fn query_fingerprint(&self, query: &Query) -> Option<String> {
+        let fingerprints = self.fingerprints.as_ref()?;
+        let mut state = fingerprints.last().expect("fingerprint scope").clone();
+        hash_node_without_spans(&query_to_node(query), &mut state);
+        Some(state.finalize().iter().map(|b| format!("{:02x}", b)).collect())
+    }
+
     pub fn set_rlimit(&mut self, rlimit: u32) {
         self.rlimit = rlimit;
+        self.add_option_fingerprint("rlimit", &rlimit.to_string());
         self.air_initial_log.log_set_option("rlimit", &rlimit.to_string());
         self.air_middle_log.log_set_option("rlimit", &rlimit.to_string());
         self.air_final_log.log_set_option("rlimit", &rlimit.to_string());
@@ -101,6 +153,7 @@ pub fn comment(&mut self, s: &str) {
     }
 
     fn log_set_z3_param(&mut self, option: &str, value: &str) {
+        self.add_option_fingerprint(option, value);
         self.air_initial_log.log_set_option(option, value);
         self.air_middle_log.log_set_option(option, value);
         self.air_final_log.log_set_option(option, value);
@@ -144,6 +197,7 @@ pub(crate) fn set_z3_param_f64(&mut self, option: &str, value: f64, write_to_log
     }
 
     pub fn set_z3_param(&mut self, option: &str, value: &str) {
+        crate::smt_verify::smt_close_query(self);
         if value == "true" {
             self.set_z3_param_bool(option, true, true);
         } else if value == "false" {
@@ -168,39 +222,51 @@ pub(crate) fn pop_name_scope(&mut self) {
     }
 
     pub fn push(&mut self) {
+        crate::smt_verify::smt_close_query(self);
         self.air_initial_log.log_push();
         self.air_middle_log.log_push();
         self.air_final_log.log_push();
         self.smt_log.log_push();
         self.push_name_scope();
+        if let Some(fingerprints) = &mut self.fingerprints {
+            let state = fingerprints.last().expect("fingerprint scope").clone();
+            fingerprints.push(state);
+        }
     }
 
     pub fn pop(&mut self) {
+        crate::smt_verify::smt_close_query(self);
         self.air_initial_log.log_pop();
         self.air_middle_log.log_pop();
         self.air_final_log.log_pop();
         self.smt_log.log_pop();
         self.pop_name_scope();
+        if let Some(fingerprints) = &mut self.fingerprints {
+            fingerprints.pop();
+        }
     }
 
     pub fn global(&mut self, decl: &Decl) -> Result<(), TypeError> {
+        crate::smt_verify::smt_close_query(self);
         self.air_initial_log.log_decl(decl);
         self.air_middle_log.log_decl(decl);
         self.air_final_log.log_decl(decl);
         crate::typecheck::check_decl(&mut self.typing, decl)?;
         crate::typecheck::add_decl(self, decl, true)?;
         crate::smt_verify::smt_add_decl(self, decl);
+        self.add_fingerprint(&decl_to_node(decl));
         Ok(())
     }
 
     pub fn check_valid(&mut self, query: &Query) -> ValidityResult {
+        crate::smt_verify::smt_close_query(self);
         self.air_initial_log.log_query(query);
         if let Err(err) = crate::typecheck::check_query(self, query) {
             return ValidityResult::TypeError(err);
         }
         let (query, snapshots, local_vars) = crate::var_to_const::lower_query(query);
         self.air_middle_log.log_query(&query);
-        let query = crate::block_to_assert::lower_query(&query);
+        let query = crate::block_to_assert::lower_query(&query, self.multiple_errors);
         self.air_final_log.log_query(&query);
 
         let validity = crate::smt_verify::smt_check_query(self, &query, snapshots, local_vars);
@@ -208,6 +274,13 @@ pub fn check_valid(&mut self, query: &Query) -> ValidityResult {
         validity
     }
 
+    /// After check_valid returns Invalid, disable the failed assertion and check the query again,
+    /// to find other failing assertions.
+    /// Returns Valid if there are no other failures, or if the failed assertion is unknown.
+    pub fn check_valid_again(&mut self) -> ValidityResult {
+        crate::smt_verify::smt_check_query_again(self)
+    }
+
     pub fn command(&mut self, command: &Command) -> ValidityResult {
         match &**command {
             CommandX::Push => {
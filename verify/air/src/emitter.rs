@@ -134,6 +134,12 @@ pub fn log_query(&mut self, query: &Query) {
         }
     }
 
+    pub fn log_get_info(&mut self, key: &str) {
+        if !self.is_none() {
+            self.log_node(&nodes!(get-info {Node::Atom(":".to_owned() + key)}));
+        }
+    }
+
     pub fn log_eval(&mut self, expr: ModelExpr) {
         if !self.is_none() {
             self.log_node(&nodes!(eval {Node::Atom(expr.to_string())}));
@@ -144,6 +144,10 @@ pub fn main() {
                     println!("Model: {}", m);
                 }
             }
+            ValidityResult::Canceled(reason) => {
+                count_errors += 1;
+                println!("Error: SMT solver gave up ({})", reason);
+            }
         }
     }
     println!("Verification results:: verified: {} errors: {}", count_verified, count_errors);
@@ -47,6 +47,15 @@ pub(crate) fn node_to_typ(node: &Node) -> Result<Typ, String> {
         Node::Atom(s) if s.to_string() == "Bool" => Ok(Arc::new(TypX::Bool)),
         Node::Atom(s) if s.to_string() == "Int" => Ok(Arc::new(TypX::Int)),
         Node::Atom(s) if is_symbol(s) => Ok(Arc::new(TypX::Named(Arc::new(s.clone())))),
+        Node::List(nodes) => match &nodes[..] {
+            [Node::Atom(u), Node::Atom(bv), Node::Atom(width)] if u == "_" && bv == "BitVec" => {
+                match width.parse::<u32>() {
+                    Ok(width) => Ok(Arc::new(TypX::BitVec(width))),
+                    Err(_) => Err(format!("expected bit-vector width, found: {}", width)),
+                }
+            }
+            _ => Err(format!("expected type, found: {}", node_to_string(node))),
+        },
         _ => Err(format!("expected type, found: {}", node_to_string(node))),
     }
 }
@@ -81,6 +90,20 @@ pub(crate) fn node_to_expr(node: &Node) -> Result<Expr, String> {
                 {
                     return Ok(Arc::new(ExprX::Old(Arc::new(snap.clone()), Arc::new(x.clone()))));
                 }
+                [Node::Atom(u), Node::Atom(n), Node::Atom(width)]
+                    if u == "_"
+                        && n.starts_with("bv")
+                        && n.len() > 2
+                        && n[2..].chars().all(|c| c.is_ascii_digit()) =>
+                {
+                    return match width.parse::<u32>() {
+                        Ok(width) => {
+                            let n = Arc::new(n[2..].to_string());
+                            Ok(Arc::new(ExprX::Const(Constant::BitVec(n, width))))
+                        }
+                        Err(_) => Err(format!("expected bit-vector width, found: {}", width)),
+                    };
+                }
                 [Node::Atom(s), Node::List(binders), e] if s.to_string() == "let" => {
                     return node_to_let_expr(binders, e);
                 }
@@ -95,6 +118,7 @@ pub(crate) fn node_to_expr(node: &Node) -> Result<Expr, String> {
             let args = nodes_to_exprs(&nodes[1..])?;
             let uop = match &nodes[0] {
                 Node::Atom(s) if s.to_string() == "not" => Some(UnaryOp::Not),
+                Node::Atom(s) if s.to_string() == "bvnot" => Some(UnaryOp::BitNot),
                 _ => None,
             };
             let bop = match &nodes[0] {
@@ -106,6 +130,20 @@ pub(crate) fn node_to_expr(node: &Node) -> Result<Expr, String> {
                 Node::Atom(s) if s.to_string() == ">" => Some(BinaryOp::Gt),
                 Node::Atom(s) if s.to_string() == "div" => Some(BinaryOp::EuclideanDiv),
                 Node::Atom(s) if s.to_string() == "mod" => Some(BinaryOp::EuclideanMod),
+                Node::Atom(s) if s.to_string() == "bvand" => Some(BinaryOp::BitAnd),
+                Node::Atom(s) if s.to_string() == "bvor" => Some(BinaryOp::BitOr),
+                Node::Atom(s) if s.to_string() == "bvxor" => Some(BinaryOp::BitXor),
+                Node::Atom(s) if s.to_string() == "bvadd" => Some(BinaryOp::BitAdd),
+                Node::Atom(s) if s.to_string() == "bvsub" => Some(BinaryOp::BitSub),
+                Node::Atom(s) if s.to_string() == "bvmul" => Some(BinaryOp::BitMul),
+                Node::Atom(s) if s.to_string() == "bvudiv" => Some(BinaryOp::BitUDiv),
+                Node::Atom(s) if s.to_string() == "bvurem" => Some(BinaryOp::BitURem),
+                Node::Atom(s) if s.to_string() == "bvshl" => Some(BinaryOp::BitShl),
+                Node::Atom(s) if s.to_string() == "bvlshr" => Some(BinaryOp::BitLShr),
+                Node::Atom(s) if s.to_string() == "bvult" => Some(BinaryOp::BitULt),
+                Node::Atom(s) if s.to_string() == "bvule" => Some(BinaryOp::BitULe),
+                Node::Atom(s) if s.to_string() == "bvugt" => Some(BinaryOp::BitUGt),
+                Node::Atom(s) if s.to_string() == "bvuge" => Some(BinaryOp::BitUGe),
                 _ => None,
             };
             let lop = match &nodes[0] {
@@ -76,6 +76,7 @@ pub(crate) fn typ_to_node(typ: &Typ) -> Node {
     match &**typ {
         TypX::Bool => str_to_node("Bool"),
         TypX::Int => str_to_node("Int"),
+        TypX::BitVec(width) => nodes!(_ BitVec {str_to_node(&width.to_string())}),
         TypX::Named(name) => str_to_node(&name.clone()),
     }
 }
@@ -88,6 +89,9 @@ pub(crate) fn expr_to_node(expr: &Expr) -> Node {
     match &**expr {
         ExprX::Const(Constant::Bool(b)) => Node::Atom(b.to_string()),
         ExprX::Const(Constant::Nat(n)) => Node::Atom((**n).clone()),
+        ExprX::Const(Constant::BitVec(n, width)) => {
+            nodes!(_ {str_to_node(&format!("bv{}", n))} {str_to_node(&width.to_string())})
+        }
         ExprX::Var(x) => Node::Atom(x.to_string()),
         ExprX::Old(snap, x) => {
             nodes!(old {str_to_node(&snap.to_string())} {str_to_node(&x.to_string())})
@@ -103,6 +107,7 @@ pub(crate) fn expr_to_node(expr: &Expr) -> Node {
         ExprX::Unary(op, expr) => {
             let sop = match op {
                 UnaryOp::Not => "not",
+                UnaryOp::BitNot => "bvnot",
             };
             Node::List(vec![str_to_node(sop), expr_to_node(expr)])
         }
@@ -116,6 +121,20 @@ pub(crate) fn expr_to_node(expr: &Expr) -> Node {
                 BinaryOp::Gt => ">",
                 BinaryOp::EuclideanDiv => "div",
                 BinaryOp::EuclideanMod => "mod",
+                BinaryOp::BitAnd => "bvand",
+                BinaryOp::BitOr => "bvor",
+                BinaryOp::BitXor => "bvxor",
+                BinaryOp::BitAdd => "bvadd",
+                BinaryOp::BitSub => "bvsub",
+                BinaryOp::BitMul => "bvmul",
+                BinaryOp::BitUDiv => "bvudiv",
+                BinaryOp::BitURem => "bvurem",
+                BinaryOp::BitShl => "bvshl",
+                BinaryOp::BitLShr => "bvlshr",
+                BinaryOp::BitULt => "bvult",
+                BinaryOp::BitULe => "bvule",
+                BinaryOp::BitUGt => "bvugt",
+                BinaryOp::BitUGe => "bvuge",
             };
             Node::List(vec![str_to_node(sop), expr_to_node(lhs), expr_to_node(rhs)])
         }
@@ -364,3 +383,28 @@ pub(crate) fn node_to_string_indent(indent: &String, node: &Node) -> String {
 pub(crate) fn node_to_string(node: &Node) -> String {
     node_to_string_indent(&"".to_string(), node)
 }
+
+/// Hash a node, skipping the quoted source locations in "location" and "assert" nodes,
+/// so that moving code around doesn't change the hash.
+/// Each atom and list is prefixed with a tag and its length, so the encoding is unambiguous.
+pub(crate) fn hash_node_without_spans<D: sha2::Digest>(node: &Node, state: &mut D) {
+    match node {
+        Node::Atom(s) => {
+            state.update(&[0u8]);
+            state.update(&(s.len() as u64).to_le_bytes());
+            state.update(s.as_bytes());
+        }
+        Node::List(nodes) => {
+            state.update(&[1u8]);
+            state.update(&(nodes.len() as u64).to_le_bytes());
+            for (i, n) in nodes.iter().enumerate() {
+                if let (1, Node::Atom(head), Node::Atom(s)) = (i, &nodes[0], n) {
+                    if (head == "location" || head == "assert") && s.starts_with("\"") {
+                        continue;
+                    }
+                }
+                hash_node_without_spans(n, state);
+            }
+        }
+    }
+}
@@ -89,17 +89,26 @@ pub(crate) fn smt_add_decl<'ctx>(context: &mut Context, decl: &Decl) {
     }
 }
 
+/// A query whose assertion failed, kept open so that it can be checked again
+/// with the failed assertions disabled (see Context::check_valid_again)
+pub(crate) struct QueryContext {
+    infos: Vec<AssertionInfo>,
+    snapshots: Snapshots,
+    local_vars: Vec<Decl>,
+    // Label of the most recently discovered failing assertion
+    failed_label: Option<Ident>,
+}
+
+// Returns the result, along with the label of the failing assertion, if found
 fn smt_check_assertion<'ctx>(
     context: &mut Context,
     infos: &Vec<AssertionInfo>,
     snapshots: Snapshots,
     local_vars: Vec<Decl>, // Expected to be entirely DeclX::Const
-    expr: &Expr,
-) -> ValidityResult {
+) -> (ValidityResult, Option<Ident>) {
     let mut discovered_span = Arc::new(None);
     let mut discovered_global_span = Arc::new(None);
-    let not_expr = Arc::new(ExprX::Unary(UnaryOp::Not, expr.clone()));
-    context.smt_log.log_assert(&not_expr);
+    let mut discovered_label = None;
 
     context.smt_log.log_set_option("rlimit", &context.rlimit.to_string());
     context.set_z3_param_u32("rlimit", context.rlimit, false);
@@ -109,6 +118,7 @@ fn smt_check_assertion<'ctx>(
     let smt_output =
         context.smt_manager.get_smt_process().send_commands(context.smt_log.take_pipe_data());
     let mut unsat = None;
+    let mut unknown = false;
     for line in smt_output {
         if line == "unsat" {
             assert!(unsat == None);
@@ -116,20 +126,40 @@ fn smt_check_assertion<'ctx>(
         } else if line == "sat" || line == "unknown" {
             assert!(unsat == None);
             unsat = Some(false);
+            unknown = line == "unknown";
         } else {
             println!("warning: unexpected SMT output: {}", line);
         }
     }
 
+    // With mbqi disabled, a failed proof usually ends in "unknown" (incomplete quantifiers),
+    // and the model is still useful for finding the failing assertion.
+    // But if the solver was canceled, the model is meaningless, so don't report it.
+    let mut canceled = None;
+    if unknown {
+        context.smt_log.log_get_info("reason-unknown");
+        let smt_output =
+            context.smt_manager.get_smt_process().send_commands(context.smt_log.take_pipe_data());
+        let reason = smt_output.join(" ");
+        let reason = match (reason.find('"'), reason.rfind('"')) {
+            (Some(i), Some(j)) if i < j => reason[i + 1..j].to_string(),
+            _ => reason,
+        };
+        if ["canceled", "resource", "timeout", "memout"].iter().any(|r| reason.contains(r)) {
+            canceled = Some(reason);
+        }
+    }
+
     context.smt_log.log_set_option("rlimit", "0");
     context.set_z3_param_u32("rlimit", 0, false);
 
-    match unsat {
-        None => {
+    match (unsat, canceled) {
+        (None, _) => {
             panic!("expected sat/unsat/unknown from SMT solver");
         }
-        Some(true) => ValidityResult::Valid,
-        Some(false) => {
+        (Some(true), _) => (ValidityResult::Valid, None),
+        (Some(false), Some(reason)) => (ValidityResult::Canceled(reason), None),
+        (Some(false), None) => {
             context.smt_log.log_word("get-model");
             let smt_output = context
                 .smt_manager
@@ -144,6 +174,7 @@ fn smt_check_assertion<'ctx>(
                 if let Some(def) = model_defs.get(&info.label) {
                     if *def.body == "true" {
                         discovered_span = info.span.clone();
+                        discovered_label = Some(info.label.clone());
                         break;
                     }
                 }
@@ -164,7 +195,9 @@ fn smt_check_assertion<'ctx>(
             if context.debug {
                 air_model.build(context, local_vars);
             }
-            ValidityResult::Invalid(air_model, discovered_span, discovered_global_span)
+            let result =
+                ValidityResult::Invalid(air_model, discovered_span, discovered_global_span);
+            (result, discovered_label)
         }
     }
 }
@@ -207,10 +240,50 @@ pub(crate) fn smt_check_query<'ctx>(
     }
 
     // check assertion
-    let result = smt_check_assertion(context, &infos, snapshots, local_vars, &labeled_assertion);
+    let not_expr = Arc::new(ExprX::Unary(UnaryOp::Not, labeled_assertion));
+    context.smt_log.log_assert(&not_expr);
+    let (result, failed_label) =
+        smt_check_assertion(context, &infos, snapshots.clone(), local_vars.clone());
+    finish_check(context, result, QueryContext { infos, snapshots, local_vars, failed_label })
+}
 
-    // clean up
-    context.pop_name_scope();
-    context.smt_log.log_pop();
+// Keep the query open after a failure, so that the query can be checked again; otherwise, clean up
+fn finish_check(
+    context: &mut Context,
+    result: ValidityResult,
+    query_context: QueryContext,
+) -> ValidityResult {
+    if let ValidityResult::Invalid(..) = result {
+        context.query_context = Some(query_context);
+    } else {
+        context.pop_name_scope();
+        context.smt_log.log_pop();
+    }
     result
 }
+
+pub(crate) fn smt_close_query(context: &mut Context) {
+    if let Some(_) = context.query_context.take() {
+        context.pop_name_scope();
+        context.smt_log.log_pop();
+    }
+}
+
+pub(crate) fn smt_check_query_again(context: &mut Context) -> ValidityResult {
+    let label = match &context.query_context {
+        Some(QueryContext { failed_label: Some(label), .. }) => label.clone(),
+        _ => {
+            smt_close_query(context);
+            return ValidityResult::Valid;
+        }
+    };
+    let QueryContext { infos, snapshots, local_vars, .. } =
+        context.query_context.take().expect("query_context");
+
+    // disable the failed assertion
+    let not_label = Arc::new(ExprX::Unary(UnaryOp::Not, Arc::new(ExprX::Var(label))));
+    context.smt_log.log_assert(&not_label);
+    let (result, failed_label) =
+        smt_check_assertion(context, &infos, snapshots.clone(), local_vars.clone());
+    finish_check(context, result, QueryContext { infos, snapshots, local_vars, failed_label })
+}
@@ -1034,3 +1034,191 @@ fn yes_datatype2() {
         )
     )
 }
+
+#[test]
+fn yes_bit_vector() {
+    yes!(
+        (check-valid
+            (declare-const x (_ BitVec 32))
+            (declare-const y (_ BitVec 32))
+            (block
+                (assert (= (bvand x y) (bvand y x)))
+                (assert (= (bvxor x x) (_ bv0 32)))
+                (assert (= (bvshl (_ bv1 32) (_ bv3 32)) (_ bv8 32)))
+                (assert (bvule (bvand x (_ bv255 32)) (_ bv255 32)))
+            )
+        )
+    )
+}
+
+#[test]
+fn no_bit_vector() {
+    no!(
+        (check-valid
+            (declare-const x (_ BitVec 8))
+            (assert (bvult x (bvadd x (_ bv1 8))))
+        )
+    )
+}
+
+#[test]
+fn untyped_bit_vector_width() {
+    untyped!(
+        (check-valid
+            (declare-const x (_ BitVec 8))
+            (declare-const y (_ BitVec 16))
+            (assert (= (bvor x y) (bvor y x)))
+        )
+    )
+}
+
+#[test]
+fn untyped_bit_vector_int() {
+    untyped!(
+        (check-valid
+            (declare-const x (_ BitVec 8))
+            (assert (= (bvnot x) 0))
+        )
+    )
+}
+
+#[allow(dead_code)]
+fn query_fingerprint_of_nodes(nodes: &[Node]) -> String {
+    let mut air_context = crate::context::Context::new(SmtManager::new());
+    air_context.enable_fingerprints();
+    air_context.set_z3_param("air_recommended_options", "true");
+    let commands = nodes_to_commands(&nodes).expect("commands");
+    let (query, globals) = commands.split_last().expect("query");
+    for command in globals.iter() {
+        assert!(matches!(air_context.command(&command), ValidityResult::Valid));
+    }
+    match &**query {
+        CommandX::CheckValid(query) => air_context.query_fingerprint(query).expect("fingerprint"),
+        _ => panic!("expected check-valid"),
+    }
+}
+
+#[allow(unused_macros)]
+macro_rules! fingerprint {
+    ( $( $x:tt )* ) => {
+       {
+           let mut v = Vec::new();
+           $(macro_push_node(&mut v, node!($x));)*
+           query_fingerprint_of_nodes(&v)
+       }
+    };
+}
+
+#[test]
+fn fingerprint_ignores_locations() {
+    let f1 = fingerprint!(
+        (declare-const x Int)
+        (axiom (location "\"a.rs:1:1\"" (> x 0)))
+        (check-valid
+            (assert "\"a.rs:2:1\"" (> x 0))
+        )
+    );
+    let f2 = fingerprint!(
+        (declare-const x Int)
+        (axiom (location "\"a.rs:5:1\"" (> x 0)))
+        (check-valid
+            (assert "\"a.rs:6:1\"" (> x 0))
+        )
+    );
+    assert_eq!(f1, f2);
+    assert_eq!(f1.len(), 64);
+}
+
+#[test]
+fn fingerprint_depends_on_decls() {
+    let f1 = fingerprint!(
+        (declare-const x Int)
+        (axiom (> x 0))
+        (check-valid
+            (assert (> x 0))
+        )
+    );
+    let f2 = fingerprint!(
+        (declare-const x Int)
+        (axiom (> x 1))
+        (check-valid
+            (assert (> x 0))
+        )
+    );
+    let f3 = fingerprint!(
+        (declare-const x Int)
+        (push)
+        (axiom (> x 0))
+        (pop)
+        (check-valid
+            (assert (> x 0))
+        )
+    );
+    assert_ne!(f1, f2);
+    assert_ne!(f1, f3);
+}
+
+#[test]
+fn multiple_errors() {
+    let mut air_context = crate::context::Context::new(SmtManager::new());
+    air_context.set_multiple_errors(true);
+    air_context.set_z3_param("air_recommended_options", "true");
+    let mut v = Vec::new();
+    macro_push_node(
+        &mut v,
+        node!(
+            (check-valid
+                (declare-const x Int)
+                (declare-const y Int)
+                (block
+                    (assume (> x 0))
+                    (assume (> y 0))
+                    (assert "\"a\"" (> x 1))
+                    (assert "\"b\"" (>= x 0))
+                    (assert "\"c\"" (> y 1))
+                )
+            )
+        ),
+    );
+    let commands = nodes_to_commands(&v).expect("commands");
+    let span_string = |result: ValidityResult| match result {
+        ValidityResult::Invalid(_, span, _) => (*span).as_ref().expect("span").as_string.clone(),
+        _ => panic!("expected invalid"),
+    };
+    let mut failures = vec![
+        span_string(air_context.command(&commands[0])),
+        span_string(air_context.check_valid_again()),
+    ];
+    failures.sort();
+    assert_eq!(failures, vec!["a".to_string(), "c".to_string()]);
+    assert!(matches!(air_context.check_valid_again(), ValidityResult::Valid));
+}
+
+#[test]
+fn multiple_errors_assume_failed() {
+    // After a failed assertion is disabled, it is still assumed by later assertions
+    let mut air_context = crate::context::Context::new(SmtManager::new());
+    air_context.set_multiple_errors(true);
+    air_context.set_z3_param("air_recommended_options", "true");
+    let mut v = Vec::new();
+    macro_push_node(
+        &mut v,
+        node!(
+            (check-valid
+                (declare-const x Int)
+                (block
+                    (assert "\"a\"" (= x 5))
+                    (assert "\"b\"" (= (+ x 1) 6))
+                )
+            )
+        ),
+    );
+    let commands = nodes_to_commands(&v).expect("commands");
+    match air_context.command(&commands[0]) {
+        ValidityResult::Invalid(_, span, _) => {
+            assert_eq!((*span).as_ref().expect("span").as_string, "a".to_string());
+        }
+        _ => panic!("expected invalid"),
+    }
+    assert!(matches!(air_context.check_valid_again(), ValidityResult::Valid));
+}
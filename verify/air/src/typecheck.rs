@@ -63,6 +63,7 @@ fn typ_name(typ: &Typ) -> String {
     match &**typ {
         TypX::Bool => "Bool".to_string(),
         TypX::Int => "Int".to_string(),
+        TypX::BitVec(width) => format!("(_ BitVec {})", width),
         TypX::Named(x) => x.to_string(),
     }
 }
@@ -71,6 +72,7 @@ fn typ_eq(typ1: &Typ, typ2: &Typ) -> bool {
     match (&**typ1, &**typ2) {
         (TypX::Bool, TypX::Bool) => true,
         (TypX::Int, TypX::Int) => true,
+        (TypX::BitVec(w1), TypX::BitVec(w2)) => w1 == w2,
         (TypX::Named(x1), TypX::Named(x2)) => x1 == x2,
         _ => false,
     }
@@ -84,6 +86,8 @@ pub(crate) fn check_typ(typing: &Typing, typ: &Typ) -> Result<(), TypeError> {
     match &**typ {
         TypX::Bool => Ok(()),
         TypX::Int => Ok(()),
+        TypX::BitVec(width) if *width > 0 => Ok(()),
+        TypX::BitVec(_) => Err("bit-vector width must be positive".to_string()),
         TypX::Named(x) => match typing.get(x) {
             Some(DeclaredX::Type) => Ok(()),
             _ => Err(format!("use of undeclared type {}", x)),
@@ -131,10 +135,23 @@ fn check_exprs(
     Ok(f_typ.clone())
 }
 
+fn check_bit_vector(typing: &mut Typing, f_name: &str, expr: &Expr) -> Result<Typ, TypeError> {
+    let t = check_expr(typing, expr)?;
+    match &*t {
+        TypX::BitVec(_) => Ok(t),
+        _ => Err(format!(
+            "in call to {}, argument #1 has type {} when it should have a bit-vector type",
+            f_name,
+            typ_name(&t)
+        )),
+    }
+}
+
 pub(crate) fn check_expr(typing: &mut Typing, expr: &Expr) -> Result<Typ, TypeError> {
     let result = match &**expr {
         ExprX::Const(Constant::Bool(_)) => Ok(Arc::new(TypX::Bool)),
         ExprX::Const(Constant::Nat(_)) => Ok(Arc::new(TypX::Int)),
+        ExprX::Const(Constant::BitVec(_, width)) => Ok(Arc::new(TypX::BitVec(*width))),
         ExprX::Var(x) => match typing.get(x) {
             Some(DeclaredX::Var { typ, .. }) => Ok(typ.clone()),
             _ => Err(format!("use of undeclared variable {}", x)),
@@ -149,6 +166,7 @@ pub(crate) fn check_expr(typing: &mut Typing, expr: &Expr) -> Result<Typ, TypeEr
             _ => Err(format!("use of undeclared function {}", x)),
         },
         ExprX::Unary(UnaryOp::Not, e1) => check_exprs(typing, "not", &[bt()], &bt(), &[e1.clone()]),
+        ExprX::Unary(UnaryOp::BitNot, e1) => check_bit_vector(typing, "bvnot", e1),
         ExprX::Binary(BinaryOp::Implies, e1, e2) => {
             check_exprs(typing, "=>", &[bt(), bt()], &bt(), &[e1.clone(), e2.clone()])
         }
@@ -183,6 +201,28 @@ pub(crate) fn check_expr(typing: &mut Typing, expr: &Expr) -> Result<Typ, TypeEr
         ExprX::Binary(BinaryOp::EuclideanMod, e1, e2) => {
             check_exprs(typing, "mod", &[it(), it()], &it(), &[e1.clone(), e2.clone()])
         }
+        ExprX::Binary(op @ BinaryOp::BitAnd, e1, e2)
+        | ExprX::Binary(op @ BinaryOp::BitOr, e1, e2)
+        | ExprX::Binary(op @ BinaryOp::BitXor, e1, e2)
+        | ExprX::Binary(op @ BinaryOp::BitAdd, e1, e2)
+        | ExprX::Binary(op @ BinaryOp::BitSub, e1, e2)
+        | ExprX::Binary(op @ BinaryOp::BitMul, e1, e2)
+        | ExprX::Binary(op @ BinaryOp::BitUDiv, e1, e2)
+        | ExprX::Binary(op @ BinaryOp::BitURem, e1, e2)
+        | ExprX::Binary(op @ BinaryOp::BitShl, e1, e2)
+        | ExprX::Binary(op @ BinaryOp::BitLShr, e1, e2) => {
+            let x = format!("{:?}", op);
+            let t1 = check_bit_vector(typing, &x, e1)?;
+            check_exprs(typing, &x, &[t1.clone()], &t1, &[e2.clone()])
+        }
+        ExprX::Binary(op @ BinaryOp::BitULt, e1, e2)
+        | ExprX::Binary(op @ BinaryOp::BitULe, e1, e2)
+        | ExprX::Binary(op @ BinaryOp::BitUGt, e1, e2)
+        | ExprX::Binary(op @ BinaryOp::BitUGe, e1, e2) => {
+            let x = format!("{:?}", op);
+            let t1 = check_bit_vector(typing, &x, e1)?;
+            check_exprs(typing, &x, &[t1], &bt(), &[e2.clone()])
+        }
         ExprX::Multi(op, exprs) => {
             let (x, t) = match op {
                 MultiOp::And => ("and", bt()),
@@ -4,6 +4,29 @@ pub fn admit() {
     unimplemented!();
 }
 
+// Prove a boolean expression over bool and unsigned integers using bit-vector reasoning
+pub fn assert_bit_vector(_b: bool) {
+    unimplemented!();
+}
+
+// Prove b using the proof block, in a query of its own that doesn't see the surrounding code;
+// facts established inside the block stay local to it
+pub fn assert_by(_b: bool, _proof: ()) {
+    unimplemented!();
+}
+
+// Prove forall vars. requires ==> ensures, in a query of its own,
+// given a closure |vars| { requires(...); ensures(...); proof }
+pub fn assert_forall_by<A>(_a: A) {
+    unimplemented!();
+}
+
+// Prove ensures from requires with nonlinear arithmetic, in a query of its own,
+// given a block { requires(...); ensures(...); proof }
+pub fn assert_nonlinear_by(_a: ()) {
+    unimplemented!();
+}
+
 // Can only appear at beginning of function body
 pub fn requires<A>(_a: A) {
     unimplemented!();
@@ -14,6 +37,11 @@ pub fn ensures<A>(_a: A) {
     unimplemented!();
 }
 
+// Value of a parameter at function entry; the argument must be a parameter
+pub fn old<A>(_a: A) -> A {
+    unimplemented!();
+}
+
 // Can only appear at beginning of loop body
 pub fn invariant<A>(_a: A) {
     unimplemented!();
@@ -24,6 +52,11 @@ pub fn decreases<A>(_a: A) {
     unimplemented!();
 }
 
+// Can only appear at the end of a trait method declaration, after requires/ensures
+pub fn no_method_body<A>() -> A {
+    unimplemented!();
+}
+
 // Can only appear at beginning of function body
 pub fn hide<F>(_f: F) {
     unimplemented!();
@@ -49,10 +82,25 @@ pub fn exists<A>(_a: A) -> bool {
     unimplemented!();
 }
 
+// Some value satisfying the predicate, if one exists; an arbitrary value otherwise
+pub fn choose<A, F: Fn(A) -> bool>(_f: F) -> A {
+    unimplemented!();
+}
+
 pub fn equal<A>(_: A, _: A) -> bool {
     unimplemented!();
 }
 
+// Cast between integer types, wrapping like `as` if the value doesn't fit in the result type
+pub fn truncate<A, B>(_a: A) -> B {
+    unimplemented!();
+}
+
+// Equality of sequences, sets, and maps proven element by element; ordinary equality otherwise
+pub fn ext_equal<A>(_: A, _: A) -> bool {
+    unimplemented!();
+}
+
 #[allow(non_camel_case_types)]
 pub struct int;
 
@@ -176,6 +224,129 @@ fn cmp(&self, _other: &Self) -> std::cmp::Ordering {
     }
 }
 
+// Mathematical sequence, for specifications
+pub struct Seq<A> {
+    _phantom: std::marker::PhantomData<A>,
+}
+
+impl<A> Clone for Seq<A> {
+    fn clone(&self) -> Self {
+        unimplemented!()
+    }
+}
+
+impl<A> Copy for Seq<A> {}
+
+impl<A> Seq<A> {
+    pub fn empty() -> Seq<A> {
+        unimplemented!()
+    }
+
+    pub fn len(self) -> nat {
+        unimplemented!()
+    }
+
+    pub fn index(self, _i: int) -> A {
+        unimplemented!()
+    }
+
+    pub fn push(self, _a: A) -> Seq<A> {
+        unimplemented!()
+    }
+
+    pub fn update(self, _i: int, _a: A) -> Seq<A> {
+        unimplemented!()
+    }
+
+    // Elements from index start up to, but not including, index end
+    pub fn subrange(self, _start: int, _end: int) -> Seq<A> {
+        unimplemented!()
+    }
+
+    pub fn ext_equal(self, _s2: Seq<A>) -> bool {
+        unimplemented!()
+    }
+}
+
+// Mathematical set, for specifications
+pub struct Set<A> {
+    _phantom: std::marker::PhantomData<A>,
+}
+
+impl<A> Clone for Set<A> {
+    fn clone(&self) -> Self {
+        unimplemented!()
+    }
+}
+
+impl<A> Copy for Set<A> {}
+
+impl<A> Set<A> {
+    pub fn empty() -> Set<A> {
+        unimplemented!()
+    }
+
+    pub fn contains(self, _a: A) -> bool {
+        unimplemented!()
+    }
+
+    pub fn insert(self, _a: A) -> Set<A> {
+        unimplemented!()
+    }
+
+    pub fn remove(self, _a: A) -> Set<A> {
+        unimplemented!()
+    }
+
+    pub fn ext_equal(self, _s2: Set<A>) -> bool {
+        unimplemented!()
+    }
+}
+
+// Mathematical map, for specifications
+pub struct Map<K, V> {
+    _phantom: std::marker::PhantomData<(K, V)>,
+}
+
+impl<K, V> Clone for Map<K, V> {
+    fn clone(&self) -> Self {
+        unimplemented!()
+    }
+}
+
+impl<K, V> Copy for Map<K, V> {}
+
+impl<K, V> Map<K, V> {
+    pub fn empty() -> Map<K, V> {
+        unimplemented!()
+    }
+
+    pub fn dom(self) -> Set<K> {
+        unimplemented!()
+    }
+
+    pub fn index(self, _k: K) -> V {
+        unimplemented!()
+    }
+
+    pub fn insert(self, _k: K, _v: V) -> Map<K, V> {
+        unimplemented!()
+    }
+
+    pub fn remove(self, _k: K) -> Map<K, V> {
+        unimplemented!()
+    }
+
+    pub fn ext_equal(self, _m2: Map<K, V>) -> bool {
+        unimplemented!()
+    }
+}
+
+// View the contents of an array or slice as a sequence, for specifications
+pub fn view<A>(_s: &[A]) -> Seq<A> {
+    unimplemented!();
+}
+
 // TODO(andreal) bake this into the compiler as a lang_item
 #[rustc_diagnostic_item = "builtin::Structural"]
 pub trait Structural {
@@ -0,0 +1,32 @@
+use std::path::PathBuf;
+
+/// Version of the fingerprint format, included in the cache file names,
+/// so that changes to the format never match files from older versions
+const CACHE_FORMAT: &str = "sha256-v1";
+
+/// On-disk record of the queries that have already been verified (see --cache-dir).
+/// Each successful query is stored as an empty file named after the query's fingerprint,
+/// which covers the query and all the declarations and options in scope for the query.
+pub struct QueryCache {
+    dir: PathBuf,
+}
+
+impl QueryCache {
+    pub fn new(dir: &String) -> Self {
+        std::fs::create_dir_all(dir).expect(&format!("could not create cache directory {}", dir));
+        QueryCache { dir: PathBuf::from(dir) }
+    }
+
+    fn path(&self, fingerprint: &str) -> PathBuf {
+        self.dir.join(format!("{}-{}.valid", CACHE_FORMAT, fingerprint))
+    }
+
+    pub fn contains(&self, fingerprint: &str) -> bool {
+        self.path(fingerprint).exists()
+    }
+
+    pub fn insert(&self, fingerprint: &str) {
+        let path = self.path(fingerprint);
+        std::fs::File::create(&path).expect(&format!("could not write cache file {:?}", path));
+    }
+}
@@ -6,6 +6,10 @@ pub struct Args {
     pub verify_module: Option<String>,
     pub no_verify: bool,
     pub rlimit: u32,
+    pub num_threads: usize,
+    pub cache_dir: Option<String>,
+    pub multiple_errors: u32,
+    pub arch_word_bits: Option<u32>,
     pub log_vir: Option<String>,
     pub log_air_initial: Option<String>,
     pub log_air_final: Option<String>,
@@ -21,6 +25,10 @@ pub fn parse_args(program: &String, args: impl Iterator<Item = String>) -> (Args
     const OPT_VERIFY_MODULE: &str = "verify-module";
     const OPT_NO_VERIFY: &str = "no-verify";
     const OPT_RLIMIT: &str = "rlimit";
+    const OPT_NUM_THREADS: &str = "num-threads";
+    const OPT_CACHE_DIR: &str = "cache-dir";
+    const OPT_MULTIPLE_ERRORS: &str = "multiple-errors";
+    const OPT_ARCH_WORD_BITS: &str = "arch-word-bits";
     const OPT_LOG_VIR: &str = "log-vir";
     const OPT_LOG_AIR_INITIAL: &str = "log-air";
     const OPT_LOG_AIR_FINAL: &str = "log-air-final";
@@ -40,6 +48,31 @@ pub fn parse_args(program: &String, args: impl Iterator<Item = String>) -> (Args
     );
     opts.optflag("", OPT_NO_VERIFY, "Do not run verification");
     opts.optopt("", OPT_RLIMIT, "Set SMT resource limit (roughly in seconds)", "INTEGER");
+    opts.optopt(
+        "",
+        OPT_NUM_THREADS,
+        "Verify modules in parallel, each thread with its own SMT process (default: 1)",
+        "INTEGER",
+    );
+    opts.optopt(
+        "",
+        OPT_CACHE_DIR,
+        "Record successfully verified queries in DIR, and skip them in later runs",
+        "DIR",
+    );
+    opts.optopt(
+        "",
+        OPT_MULTIPLE_ERRORS,
+        "Report up to N failing assertions per query, rather than just the first (default: 1); \
+         loop bodies and nonlinear blocks are checked in separate queries",
+        "N",
+    );
+    opts.optopt(
+        "",
+        OPT_ARCH_WORD_BITS,
+        "Set the width of usize/isize to 32 or 64 bits (default: the rustc target's pointer width)",
+        "BITS",
+    );
     opts.optopt("", OPT_LOG_VIR, "Log VIR", "FILENAME");
     opts.optopt("", OPT_LOG_AIR_INITIAL, "Log AIR queries in initial form", "FILENAME");
     opts.optopt("", OPT_LOG_AIR_FINAL, "Log AIR queries in final form", "FILENAME");
@@ -75,14 +108,47 @@ pub fn parse_args(program: &String, args: impl Iterator<Item = String>) -> (Args
         }
     };
 
+    let arch_word_bits =
+        matches.opt_get::<u32>(OPT_ARCH_WORD_BITS).expect("expected integer after arch-word-bits");
+    if let Some(bits) = arch_word_bits {
+        if bits != 32 && bits != 64 {
+            eprintln!("Error: arch-word-bits must be 32 or 64");
+            print_usage();
+            std::process::exit(-1);
+        }
+    }
+
+    let num_threads = matches
+        .opt_get::<usize>(OPT_NUM_THREADS)
+        .expect("expected integer after num-threads")
+        .unwrap_or(1);
+    if num_threads == 0 {
+        eprintln!("Error: num-threads must be at least 1");
+        print_usage();
+        std::process::exit(-1);
+    }
+
+    let rlimit =
+        matches.opt_get::<u32>(OPT_RLIMIT).expect("expected integer after rlimit").unwrap_or(0);
+    // the verifier passes rlimit * 1000000 to Z3
+    if rlimit.checked_mul(1000000).is_none() {
+        eprintln!("Error: rlimit is too large");
+        print_usage();
+        std::process::exit(-1);
+    }
+
     let args = Args {
         verify_root: matches.opt_present(OPT_VERIFY_ROOT),
         verify_module: matches.opt_str(OPT_VERIFY_MODULE),
         no_verify: matches.opt_present(OPT_NO_VERIFY),
-        rlimit: matches
-            .opt_get::<u32>(OPT_RLIMIT)
-            .expect("expected integer after rlimit")
-            .unwrap_or(0),
+        rlimit,
+        num_threads,
+        cache_dir: matches.opt_str(OPT_CACHE_DIR),
+        multiple_errors: matches
+            .opt_get::<u32>(OPT_MULTIPLE_ERRORS)
+            .expect("expected integer after multiple-errors")
+            .unwrap_or(1),
+        arch_word_bits,
         log_vir: matches.opt_str(OPT_LOG_VIR),
         log_air_initial: matches.opt_str(OPT_LOG_AIR_INITIAL),
         log_air_final: matches.opt_str(OPT_LOG_AIR_FINAL),
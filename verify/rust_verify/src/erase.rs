@@ -101,6 +101,10 @@ pub struct Ctxt {
 fn expr_to_call(ctxt: &Ctxt, expr: &Expr) -> ResolvedCall {
     match &expr.kind {
         ExprKind::Path(_, path) if path.segments.len() == 1 => ctxt.calls[&expr.span].clone(),
+        // an associated function, such as Seq::empty()
+        ExprKind::Path(None, _) if ctxt.calls.contains_key(&expr.span) => {
+            ctxt.calls[&expr.span].clone()
+        }
         _ => {
             unsupported!("complex function call", expr)
         }
@@ -186,6 +190,25 @@ fn erase_expr_opt(ctxt: &Ctxt, is_exec: bool, expr: &Expr) -> Option<Expr> {
                 return replace_with_exprs(expr, vec![e1, e2]);
             }
         }
+        ExprKind::Index(e1, e2) => {
+            if is_exec {
+                let e1 = erase_expr(ctxt, is_exec, e1);
+                let e2 = erase_expr(ctxt, is_exec, e2);
+                ExprKind::Index(P(e1), P(e2))
+            } else {
+                let e1 = erase_expr_opt(ctxt, is_exec, e1);
+                let e2 = erase_expr_opt(ctxt, is_exec, e2);
+                return replace_with_exprs(expr, vec![e1, e2]);
+            }
+        }
+        ExprKind::Array(es) => {
+            if is_exec {
+                ExprKind::Array(vec_map(es, |e| P(erase_expr(ctxt, is_exec, e))))
+            } else {
+                let es = es.iter().map(|e| erase_expr_opt(ctxt, is_exec, e)).collect();
+                return replace_with_exprs(expr, es);
+            }
+        }
         ExprKind::Assign(e1, e2, span) => {
             let is_exec1 = ctxt.var_modes[&e1.span] == Mode::Exec;
             if is_exec1 {
@@ -239,12 +262,24 @@ fn erase_expr_opt(ctxt: &Ctxt, is_exec: bool, expr: &Expr) -> Option<Expr> {
                 ExprKind::If(P(eb), P(e1), e2_opt)
             }
         },
-        ExprKind::While(eb, block, None) => {
+        ExprKind::While(eb, block, label) => {
             let eb = erase_expr(ctxt, true, eb);
             let block = erase_block(ctxt, true, block);
-            ExprKind::While(P(eb), P(block), None)
+            ExprKind::While(P(eb), P(block), *label)
+        }
+        ExprKind::Loop(block, label) => {
+            let block = erase_block(ctxt, true, block);
+            ExprKind::Loop(P(block), *label)
         }
+        ExprKind::Break(_, None) | ExprKind::Continue(_) => expr.kind.clone(),
         ExprKind::Block(block, None) => ExprKind::Block(P(erase_block(ctxt, is_exec, block)), None),
+        ExprKind::Ret(e1) => {
+            if is_exec {
+                ExprKind::Ret(e1.as_ref().and_then(|e| erase_expr_opt(ctxt, true, e)).map(P))
+            } else {
+                return None;
+            }
+        }
         _ => {
             unsupported!("unsupported expr", expr)
         }
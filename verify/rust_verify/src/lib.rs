@@ -15,6 +15,7 @@
 extern crate rustc_span;
 extern crate rustc_typeck;
 
+pub mod cache;
 pub mod config;
 pub mod context;
 pub mod erase;
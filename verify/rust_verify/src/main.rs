@@ -45,8 +45,9 @@ pub fn main() {
     let status = rustc_driver::RunCompiler::new(&rustc_args, &mut verifier).run();
     if !verifier.encountered_vir_error {
         println!(
-            "Verification results:: verified: {} errors: {}",
+            "Verification results:: verified: {} cached: {} errors: {}",
             verifier.count_verified,
+            verifier.count_cached,
             verifier.errors.len()
         );
     }
@@ -10,19 +10,116 @@
 use crate::rust_to_vir_adts::{check_item_enum, check_item_struct};
 use crate::rust_to_vir_base::{def_id_to_vir_path, hack_get_def_name, mk_visibility};
 use crate::rust_to_vir_func::{check_foreign_item_fn, check_item_fn};
-use crate::util::unsupported_err_span;
+use crate::util::{err_span_str, err_span_string, unsupported_err_span};
 use crate::{err_unless, unsupported_err, unsupported_err_unless, unsupported_unless};
 use rustc_ast::Attribute;
 use rustc_hir::{
-    AssocItemKind, Crate, ForeignItem, ForeignItemId, ForeignItemKind, HirId, ImplItemKind, Item,
-    ItemId, ItemKind, ModuleItems, QPath, TraitRef, TyKind,
+    AssocItemKind, Crate, ForeignItem, ForeignItemId, ForeignItemKind, HirId, Impl, ImplItemKind,
+    IsAuto, Item, ItemId, ItemKind, ModuleItems, QPath, TraitFn, TraitItemKind, TraitRef, TyKind,
+    Unsafety,
 };
-use rustc_middle::ty::TyCtxt;
+use rustc_middle::ty::{AssocKind, TyCtxt};
+use rustc_span::def_id::DefId;
 use std::collections::HashMap;
 use std::sync::Arc;
-use vir::ast::{Krate, KrateX, Path, VirErr};
+use vir::ast::{FunctionKind, Krate, KrateX, Path, VirErr, Visibility};
 use vir::ast_util::path_as_rust_name;
 
+fn check_impl_fns<'tcx>(
+    ctxt: &Context<'tcx>,
+    vir: &mut KrateX,
+    module_path: &Path,
+    item: &'tcx Item<'tcx>,
+    impll: &'tcx Impl<'tcx>,
+    trait_def_id: Option<DefId>,
+) -> Result<(), VirErr> {
+    let self_def_id = match impll.self_ty.kind {
+        TyKind::Path(QPath::Resolved(
+            None,
+            rustc_hir::Path { res: rustc_hir::def::Res::Def(_, self_def_id), .. },
+        )) => self_def_id,
+        _ => {
+            unsupported_err!(item.span, "unsupported impl of non-path type", item);
+        }
+    };
+    if let Some(trait_def_id) = trait_def_id {
+        // Trait method declarations have placeholder bodies, which Rust treats as default
+        // implementations, so check that the impl really implements each method
+        for trait_item in ctxt.tcx.associated_items(trait_def_id).in_definition_order() {
+            if !impll.items.iter().any(|impl_item_ref| impl_item_ref.ident == trait_item.ident) {
+                return err_span_string(
+                    item.span,
+                    format!("impl must implement trait method {}", trait_item.ident),
+                );
+            }
+        }
+    }
+    for impl_item_ref in impll.items {
+        match impl_item_ref.kind {
+            AssocItemKind::Fn { has_self } if has_self => {
+                let impl_item = ctxt.tcx.hir().impl_item(impl_item_ref.id);
+                let (kind, impl_item_visibility) = match trait_def_id {
+                    None => (
+                        FunctionKind::Static,
+                        mk_visibility(&Some(module_path.clone()), &impl_item.vis),
+                    ),
+                    Some(trait_def_id) => {
+                        // trait_method_impl_axiom and inherit_specs assume that the impl
+                        // method has the same type parameters as the trait method
+                        unsupported_err_unless!(
+                            impl_item.generics.params.len() == 0,
+                            impl_item.span,
+                            "trait method generics",
+                            impl_item
+                        );
+                        let method = ctxt
+                            .tcx
+                            .associated_items(trait_def_id)
+                            .find_by_name_and_kind(
+                                ctxt.tcx,
+                                impl_item.ident,
+                                AssocKind::Fn,
+                                trait_def_id,
+                            )
+                            .expect("trait method for impl method");
+                        let kind = FunctionKind::TraitMethodImpl {
+                            method: def_id_to_vir_path(ctxt.tcx, method.def_id),
+                            trait_path: def_id_to_vir_path(ctxt.tcx, trait_def_id),
+                        };
+                        // trait methods are visible wherever the trait and the type are
+                        let vis = Visibility {
+                            owning_module: Some(module_path.clone()),
+                            is_private: false,
+                        };
+                        (kind, vis)
+                    }
+                };
+                match &impl_item.kind {
+                    ImplItemKind::Fn(sig, body_id) => {
+                        let self_path = def_id_to_vir_path(ctxt.tcx, self_def_id);
+                        check_item_fn(
+                            ctxt,
+                            vir,
+                            Some(self_path),
+                            kind,
+                            impl_item.def_id.to_def_id(),
+                            impl_item_visibility,
+                            ctxt.tcx.hir().attrs(impl_item.hir_id()),
+                            sig,
+                            // TODO: make sure this is correct once supported
+                            &impll.generics,
+                            body_id,
+                        )?;
+                    }
+                    _ => unsupported_err!(item.span, "unsupported item in impl", impl_item_ref),
+                }
+            }
+            _ => unsupported_err!(item.span, "unsupported item in impl", impl_item_ref),
+        }
+    }
+    Ok(())
+}
+
 fn check_item<'tcx>(
     ctxt: &Context<'tcx>,
     vir: &mut KrateX,
@@ -37,6 +134,7 @@ fn check_item<'tcx>(
                 ctxt,
                 vir,
                 None,
+                FunctionKind::Static,
                 item.def_id.to_def_id(),
                 visibility,
                 ctxt.tcx.hir().attrs(item.hir_id()),
@@ -79,17 +177,28 @@ fn check_item<'tcx>(
         }
         ItemKind::Impl(impll) => {
             if let Some(TraitRef { path, hir_ref_id: _ }) = impll.of_trait {
-                let path_name = path_as_rust_name(&def_id_to_vir_path(ctxt.tcx, path.res.def_id()));
-                unsupported_err_unless!(
-                    path_name == "core::marker::StructuralEq"
-                        || path_name == "core::cmp::Eq"
-                        || path_name == "core::marker::StructuralPartialEq"
-                        || path_name == "core::cmp::PartialEq"
-                        || path_name == "builtin::Structural",
-                    item.span,
-                    "non_eq_trait_impl",
-                    path
-                );
+                let trait_def_id = path.res.def_id();
+                let path_name = path_as_rust_name(&def_id_to_vir_path(ctxt.tcx, trait_def_id));
+                let is_eq_trait = path_name == "core::marker::StructuralEq"
+                    || path_name == "core::cmp::Eq"
+                    || path_name == "core::marker::StructuralPartialEq"
+                    || path_name == "core::cmp::PartialEq"
+                    || path_name == "builtin::Structural";
+                if !is_eq_trait {
+                    unsupported_err_unless!(
+                        trait_def_id.is_local(),
+                        item.span,
+                        "non_eq_trait_impl",
+                        path
+                    );
+                    unsupported_err_unless!(
+                        impll.generics.params.len() == 0,
+                        item.span,
+                        "unsupported impl of trait with generics",
+                        item
+                    );
+                    check_impl_fns(ctxt, vir, module_path, item, impll, Some(trait_def_id))?;
+                }
                 if path_name == "builtin::Structural" {
                     let ty = {
                         // TODO extract to rust_to_vir_base, or use
@@ -131,64 +240,63 @@ fn check_item<'tcx>(
                     );
                 }
             } else {
-                unsupported_err_unless!(
-                    impll.of_trait.is_none(),
-                    item.span,
-                    "unsupported impl of trait",
-                    item
-                );
                 unsupported_err_unless!(
                     impll.generics.params.len() == 0,
                     item.span,
                     "unsupported impl of non-trait with generics",
                     item
                 );
-                match impll.self_ty.kind {
-                    TyKind::Path(QPath::Resolved(
-                        None,
-                        rustc_hir::Path { res: rustc_hir::def::Res::Def(_, self_def_id), .. },
-                    )) => {
-                        for impl_item_ref in impll.items {
-                            match impl_item_ref.kind {
-                                AssocItemKind::Fn { has_self } if has_self => {
-                                    let impl_item = ctxt.tcx.hir().impl_item(impl_item_ref.id);
-                                    let impl_item_visibility =
-                                        mk_visibility(&Some(module_path.clone()), &impl_item.vis);
-                                    match &impl_item.kind {
-                                        ImplItemKind::Fn(sig, body_id) => {
-                                            let self_path =
-                                                def_id_to_vir_path(ctxt.tcx, *self_def_id);
-                                            check_item_fn(
-                                                ctxt,
-                                                vir,
-                                                Some(self_path),
-                                                impl_item.def_id.to_def_id(),
-                                                impl_item_visibility,
-                                                ctxt.tcx.hir().attrs(impl_item.hir_id()),
-                                                sig,
-                                                // TODO: make sure this is correct once supported
-                                                &impll.generics,
-                                                body_id,
-                                            )?;
-                                        }
-                                        _ => unsupported_err!(
-                                            item.span,
-                                            "unsupported item in impl",
-                                            impl_item_ref
-                                        ),
-                                    }
-                                }
-                                _ => unsupported_err!(
-                                    item.span,
-                                    "unsupported item in impl",
-                                    impl_item_ref
-                                ),
-                            }
-                        }
+                check_impl_fns(ctxt, vir, module_path, item, impll, None)?;
+            }
+        }
+        ItemKind::Trait(is_auto, unsafety, generics, bounds, trait_item_refs) => {
+            unsupported_err_unless!(
+                *is_auto == IsAuto::No && *unsafety == Unsafety::Normal,
+                item.span,
+                "unsupported trait",
+                item
+            );
+            unsupported_err_unless!(
+                generics.params.len() == 0 && generics.where_clause.predicates.len() == 0,
+                item.span,
+                "trait generics",
+                item
+            );
+            unsupported_err_unless!(bounds.len() == 0, item.span, "trait bounds", item);
+            let trait_path = def_id_to_vir_path(ctxt.tcx, item.def_id.to_def_id());
+            for trait_item_ref in trait_item_refs.iter() {
+                let trait_item = ctxt.tcx.hir().trait_item(trait_item_ref.id);
+                match (&trait_item_ref.kind, &trait_item.kind) {
+                    (
+                        AssocItemKind::Fn { has_self: true },
+                        TraitItemKind::Fn(sig, TraitFn::Provided(body_id)),
+                    ) => {
+                        unsupported_err_unless!(
+                            trait_item.generics.params.len() == 0,
+                            trait_item.span,
+                            "trait method generics",
+                            trait_item
+                        );
+                        check_item_fn(
+                            ctxt,
+                            vir,
+                            None,
+                            FunctionKind::TraitMethodDecl { trait_path: trait_path.clone() },
+                            trait_item.def_id.to_def_id(),
+                            visibility.clone(),
+                            ctxt.tcx.hir().attrs(trait_item.hir_id()),
+                            sig,
+                            &trait_item.generics,
+                            body_id,
+                        )?;
                     }
-                    _ => {
-                        unsupported_err!(item.span, "unsupported impl of non-path type", item);
+                    (_, TraitItemKind::Fn(_, TraitFn::Required(_))) => {
+                        return err_span_str(
+                            trait_item.span,
+                            "trait method declaration must have a body with its requires/ensures, ending in no_method_body()",
+                        );
                     }
+                    _ => unsupported_err!(item.span, "unsupported item in trait", trait_item_ref),
                 }
             }
         }
@@ -219,7 +327,9 @@ fn check_module<'tcx>(
             for item_id in items {
                 item_to_module.insert(*item_id, module_path.clone());
             }
-            unsupported_unless!(trait_items.len() == 0, "trait definitions", trait_items);
+            for _id in trait_items {
+                // trait items are handled by check_item
+            }
             for _id in impl_items {
                 // TODO?
             }
@@ -307,7 +417,14 @@ pub fn crate_to_vir<'tcx>(ctxt: &Context<'tcx>) -> Result<Krate, VirErr> {
     for (id, item) in items {
         check_item(ctxt, &mut vir, &item_to_module[id], id, item)?;
     }
-    unsupported_unless!(trait_items.len() == 0, "trait definitions", trait_items);
+    for (_id, trait_item) in trait_items {
+        match trait_item.kind {
+            TraitItemKind::Fn(..) => {}
+            _ => {
+                unsupported_err!(trait_item.span, "unsupported_trait_item", trait_item);
+            }
+        }
+    }
     for (_id, impl_item) in impl_items {
         match impl_item.kind {
             ImplItemKind::Fn(_, _) => {
@@ -332,7 +449,8 @@ pub fn crate_to_vir<'tcx>(ctxt: &Context<'tcx>) -> Result<Krate, VirErr> {
                 || id_name == "core::cmp::Eq"
                 || id_name == "core::marker::StructuralPartialEq"
                 || id_name == "core::cmp::PartialEq"
-                || id_name == "builtin::Structural",
+                || id_name == "builtin::Structural"
+                || id.is_local(),
             "non_eq_trait_impl",
             id
         );
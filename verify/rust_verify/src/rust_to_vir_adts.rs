@@ -1,7 +1,7 @@
 use crate::context::Context;
 use crate::rust_to_vir_base::{
-    check_generics, def_id_to_vir_path, get_mode, get_verifier_attrs, hack_get_def_name,
-    is_visibility_private, ty_to_vir,
+    check_generics, check_no_mut_ref, def_id_to_vir_path, get_mode, get_verifier_attrs,
+    hack_get_def_name, is_visibility_private, ty_to_vir,
 };
 use crate::unsupported_unless;
 use crate::util::spanned_new;
@@ -14,6 +14,17 @@
 use vir::ast_util::ident_binder;
 use vir::def::positional_field_ident;
 
+fn check_variant_fields<'tcx>(
+    ctxt: &Context<'tcx>,
+    variant_data: &'tcx VariantData<'tcx>,
+) -> Result<(), VirErr> {
+    for field in variant_data.fields() {
+        let field_ty = ctxt.tcx.type_of(ctxt.tcx.hir().local_def_id(field.hir_id));
+        check_no_mut_ref(field.span, field_ty)?;
+    }
+    Ok(())
+}
+
 fn check_variant_data<'tcx>(
     ctxt: &Context<'tcx>,
     name: &Ident,
@@ -75,10 +86,11 @@ pub fn check_item_struct<'tcx>(
     variant_data: &'tcx VariantData<'tcx>,
     generics: &'tcx Generics<'tcx>,
 ) -> Result<(), VirErr> {
-    let typ_params = check_generics(generics)?;
+    let typ_params = check_generics(ctxt.tcx, generics)?;
     let name = hack_get_def_name(ctxt.tcx, id.def_id.to_def_id());
     let path = def_id_to_vir_path(ctxt.tcx, id.def_id.to_def_id());
     let variant_name = Arc::new(name.clone());
+    check_variant_fields(ctxt, variant_data)?;
     let (variant, one_field_private) = check_variant_data(ctxt, &variant_name, variant_data);
     let vattrs = get_verifier_attrs(attrs)?;
     let transparency = if !vattrs.do_verify {
@@ -104,8 +116,11 @@ pub fn check_item_enum<'tcx>(
     enum_def: &'tcx EnumDef<'tcx>,
     generics: &'tcx Generics<'tcx>,
 ) -> Result<(), VirErr> {
-    let typ_params = check_generics(generics)?;
+    let typ_params = check_generics(ctxt.tcx, generics)?;
     let path = def_id_to_vir_path(ctxt.tcx, id.def_id.to_def_id());
+    for variant in enum_def.variants.iter() {
+        check_variant_fields(ctxt, &variant.data)?;
+    }
     let (variants, one_field_private): (Vec<_>, Vec<_>) = enum_def
         .variants
         .iter()
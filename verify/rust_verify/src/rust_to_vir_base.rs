@@ -2,20 +2,21 @@
 use crate::util::{err_span_str, err_span_string, unsupported_err_span};
 use crate::{unsupported, unsupported_err, unsupported_err_unless};
 use rustc_ast::token::{Token, TokenKind};
-use rustc_ast::tokenstream::TokenTree;
+use rustc_ast::tokenstream::{TokenStream, TokenTree};
 use rustc_ast::{AttrKind, Attribute, IntTy, MacArgs, UintTy};
 use rustc_hir::def::{DefKind, Res};
 use rustc_hir::definitions::DefPath;
 use rustc_hir::{
-    GenericParam, GenericParamKind, Generics, HirId, ParamName, PathSegment, PrimTy, QPath, Ty,
-    Visibility, VisibilityKind,
+    GenericBound, GenericParam, GenericParamKind, Generics, HirId, ParamName, PathSegment,
+    PolyTraitRef, PrimTy, QPath, TraitBoundModifier, Ty, Visibility, VisibilityKind,
+    WhereBoundPredicate, WherePredicate,
 };
 use rustc_middle::ty::{AdtDef, TyCtxt, TyKind, TypeckResults};
 use rustc_span::def_id::{DefId, LOCAL_CRATE};
 use rustc_span::symbol::Ident;
 use rustc_span::Span;
 use std::sync::Arc;
-use vir::ast::{Idents, IntRange, Mode, Path, PathX, Typ, TypX, Typs, VirErr};
+use vir::ast::{GenericBoundX, IntRange, Mode, Path, PathX, Typ, TypParams, TypX, Typs, VirErr};
 use vir::ast_util::types_equal;
 
 pub(crate) fn def_path_to_vir_path<'tcx>(tcx: TyCtxt<'tcx>, def_path: DefPath) -> Path {
@@ -41,6 +42,16 @@ pub(crate) fn def_to_path_ident<'tcx>(tcx: TyCtxt<'tcx>, def_id: DefId) -> vir::
     }
 }
 
+// The spec-only collection types builtin::Seq, builtin::Set, and builtin::Map
+fn builtin_collection_typ(def_name: &str, typ_args: &[Typ]) -> Option<TypX> {
+    match def_name {
+        "builtin::Seq" => Some(TypX::Seq(typ_args[0].clone())),
+        "builtin::Set" => Some(TypX::Set(typ_args[0].clone())),
+        "builtin::Map" => Some(TypX::Map(typ_args[0].clone(), typ_args[1].clone())),
+        _ => None,
+    }
+}
+
 pub(crate) fn def_id_to_datatype<'tcx, 'hir>(
     tcx: TyCtxt<'tcx>,
     def_id: DefId,
@@ -120,15 +131,32 @@ pub(crate) fn token_tree_to_tree(token_tree: TokenTree) -> Result<AttrTree, ()>
     }
 }
 
+// Parse separated arguments, where each argument is a token optionally followed by (args),
+// as in rlimit(10)
+pub(crate) fn token_stream_to_trees(token_stream: &TokenStream) -> Result<Box<[AttrTree]>, ()> {
+    let mut token_trees = token_stream.trees().peekable();
+    let mut fargs: Vec<AttrTree> = Vec::new();
+    while let Some(token_tree) = token_trees.next() {
+        let arg = match (token_tree_to_tree(token_tree)?, token_trees.peek()) {
+            (AttrTree::Fun(span, name, None), Some(TokenTree::Delimited(_, _, args))) => {
+                let args = token_stream_to_trees(args)?;
+                token_trees.next();
+                AttrTree::Fun(span, name, Some(args))
+            }
+            (arg, _) => arg,
+        };
+        fargs.push(arg);
+        // skip separator
+        token_trees.next();
+    }
+    Ok(fargs.into_boxed_slice())
+}
+
 pub(crate) fn mac_args_to_tree(span: Span, name: String, args: &MacArgs) -> Result<AttrTree, ()> {
     match args {
         MacArgs::Empty => Ok(AttrTree::Fun(span, name, None)),
         MacArgs::Delimited(_, _, token_stream) => {
-            let mut fargs: Vec<AttrTree> = Vec::new();
-            for arg in token_stream.trees().step_by(2) {
-                fargs.push(token_tree_to_tree(arg)?);
-            }
-            Ok(AttrTree::Fun(span, name, Some(fargs.into_boxed_slice())))
+            Ok(AttrTree::Fun(span, name, Some(token_stream_to_trees(token_stream)?)))
         }
         MacArgs::Eq(_, token) => Ok(AttrTree::Eq(span, name, token_to_string(token)?)),
     }
@@ -172,7 +200,24 @@ pub(crate) enum Attr {
     Trigger(Option<Vec<u64>>),
     // custom error string to report for precondition failures
     CustomReqErr(String),
-}
+    // prove the function's ensures from its requires using bit-vector reasoning
+    BitVector,
+    // verify the function body with nonlinear arithmetic enabled
+    NonLinear,
+    // export the proof function's requires ==> ensures as an axiom, for all arguments
+    Broadcast,
+    // resource limit for the function's queries
+    RLimit(u32),
+    // SMT solver option (Z3 parameter name, value) for the function's queries
+    SmtOption(String, String),
+}
+
+/// The SMT options that can be set with #[verifier(smt_option(name, value))]:
+/// (name, Z3 parameter, value used by the verifier for all other functions)
+/// (nonlinear arithmetic is not an smt_option: proof functions enable it for the whole body
+/// with #[verifier(nonlinear)], and other functions use assert_nonlinear_by for specific facts)
+pub(crate) const SMT_OPTIONS: &[(&str, &str, &str)] =
+    &[("random_seed", "smt.random_seed", "0"), ("case_split", "smt.case_split", "3")];
 
 fn get_trigger_arg(span: Span, attr_tree: &AttrTree) -> Result<u64, VirErr> {
     let i = match attr_tree {
@@ -220,11 +265,55 @@ pub(crate) fn parse_attrs(attrs: &[Attribute]) -> Result<Vec<Attr>, VirErr> {
                 Some(box [AttrTree::Fun(_, arg, None)]) if arg == "pub_abstract" => {
                     v.push(Attr::Abstract)
                 }
+                Some(box [AttrTree::Fun(_, arg, None)]) if arg == "bit_vector" => {
+                    v.push(Attr::BitVector)
+                }
+                Some(box [AttrTree::Fun(_, arg, None)]) if arg == "nonlinear" => {
+                    v.push(Attr::NonLinear)
+                }
+                Some(box [AttrTree::Fun(_, arg, None)]) if arg == "broadcast" => {
+                    v.push(Attr::Broadcast)
+                }
                 Some(box [AttrTree::Fun(_, arg, None), AttrTree::Fun(_, msg, None)])
                     if arg == "custom_req_err" =>
                 {
                     v.push(Attr::CustomReqErr(msg.clone()))
                 }
+                Some(box [AttrTree::Fun(_, arg, Some(box [AttrTree::Fun(_, n, None)]))])
+                    if arg == "rlimit" =>
+                {
+                    // the verifier passes rlimit * 1000000 to the SMT solver
+                    match n.parse::<u32>() {
+                        Ok(n) if n.checked_mul(1000000).is_some() => v.push(Attr::RLimit(n)),
+                        Ok(_) => return err_span_str(span, "rlimit(...) is too large"),
+                        Err(_) => return err_span_str(span, "expected integer in rlimit(...)"),
+                    }
+                }
+                Some(
+                    box [AttrTree::Fun(
+                        _,
+                        arg,
+                        Some(box [AttrTree::Fun(_, opt, None), AttrTree::Fun(_, value, None)]),
+                    )],
+                ) if arg == "smt_option" => {
+                    let option = match SMT_OPTIONS.iter().find(|(n, _, _)| n == opt) {
+                        Some((_, option, _)) => option,
+                        None => {
+                            let names: Vec<&str> = SMT_OPTIONS.iter().map(|(n, _, _)| *n).collect();
+                            return err_span_string(
+                                span,
+                                format!(
+                                    "unsupported smt_option {} (expected one of {:?})",
+                                    opt, names
+                                ),
+                            );
+                        }
+                    };
+                    if value.parse::<u32>().is_err() {
+                        return err_span_str(span, "expected integer value in smt_option(...)");
+                    }
+                    v.push(Attr::SmtOption(option.to_string(), value.clone()))
+                }
                 _ => return err_span_str(span, "unrecognized verifier attribute"),
             },
             _ => {}
@@ -286,6 +375,11 @@ pub(crate) struct VerifierAttrs {
     pub(crate) external: bool,
     pub(crate) is_abstract: bool,
     pub(crate) custom_req_err: Option<String>,
+    pub(crate) bit_vector: bool,
+    pub(crate) nonlinear: bool,
+    pub(crate) broadcast: bool,
+    pub(crate) rlimit: Option<u32>,
+    pub(crate) smt_options: Vec<(vir::ast::Ident, vir::ast::Ident)>,
 }
 
 pub(crate) fn get_verifier_attrs(attrs: &[Attribute]) -> Result<VerifierAttrs, VirErr> {
@@ -294,6 +388,11 @@ pub(crate) fn get_verifier_attrs(attrs: &[Attribute]) -> Result<VerifierAttrs, V
         external: false,
         is_abstract: false,
         custom_req_err: None,
+        bit_vector: false,
+        nonlinear: false,
+        broadcast: false,
+        rlimit: None,
+        smt_options: vec![],
     };
     for attr in parse_attrs(attrs)? {
         match attr {
@@ -301,6 +400,13 @@ pub(crate) fn get_verifier_attrs(attrs: &[Attribute]) -> Result<VerifierAttrs, V
             Attr::External => vs.external = true,
             Attr::Abstract => vs.is_abstract = true,
             Attr::CustomReqErr(s) => vs.custom_req_err = Some(s.clone()),
+            Attr::BitVector => vs.bit_vector = true,
+            Attr::NonLinear => vs.nonlinear = true,
+            Attr::Broadcast => vs.broadcast = true,
+            Attr::RLimit(n) => vs.rlimit = Some(n),
+            Attr::SmtOption(option, value) => {
+                vs.smt_options.push((Arc::new(option), Arc::new(value)))
+            }
             _ => {}
         }
     }
@@ -339,13 +445,24 @@ pub(crate) fn mid_ty_to_vir<'tcx>(tcx: TyCtxt<'tcx>, ty: rustc_middle::ty::Ty<'t
     match ty.kind() {
         TyKind::Bool => Arc::new(TypX::Bool),
         TyKind::Uint(_) | TyKind::Int(_) => Arc::new(TypX::Int(mk_range(ty))),
-        TyKind::Ref(_, tys, rustc_ast::Mutability::Not) => mid_ty_to_vir(tcx, tys),
+        // &mut T is modeled as T; rust_to_vir tracks the mutability separately (ParamX::is_mut)
+        // and uses check_no_mut_ref to reject &mut anywhere other than on a parameter
+        TyKind::Ref(_, tys, _) => mid_ty_to_vir(tcx, tys),
         TyKind::Param(param) => Arc::new(TypX::TypParam(Arc::new(param.name.to_string()))),
         TyKind::Tuple(_) => {
             let typs: Vec<(Typ, Mode)> =
                 ty.tuple_fields().map(|t| (mid_ty_to_vir(tcx, t), Mode::Exec)).collect();
             Arc::new(TypX::Tuple(Arc::new(typs)))
         }
+        // A diverging expression (e.g. return) never produces a value, so any type will do
+        TyKind::Never => Arc::new(TypX::Tuple(Arc::new(vec![]))),
+        TyKind::Array(t, len) => {
+            match len.try_eval_usize(tcx, rustc_middle::ty::ParamEnv::empty()) {
+                Some(len) => Arc::new(TypX::Array(mid_ty_to_vir(tcx, t), Some(len))),
+                None => unsupported!(format!("array length {:?}", ty)),
+            }
+        }
+        TyKind::Slice(t) => Arc::new(TypX::Array(mid_ty_to_vir(tcx, t), None)),
         TyKind::Adt(AdtDef { did, .. }, args) => Arc::new({
             let s = ty.to_string();
             // TODO use lang items instead of string comparisons
@@ -361,7 +478,11 @@ pub(crate) fn mid_ty_to_vir<'tcx>(tcx: TyCtxt<'tcx>, ty: rustc_middle::ty::Ty<'t
                         _ => panic!("unexpected type argument"),
                     })
                     .collect();
-                def_id_to_datatype(tcx, *did, Arc::new(typ_args))
+                let def_name = vir::ast_util::path_as_rust_name(&def_id_to_vir_path(tcx, *did));
+                match builtin_collection_typ(&def_name, &typ_args) {
+                    Some(typ) => typ,
+                    None => def_id_to_datatype(tcx, *did, Arc::new(typ_args)),
+                }
             }
         }),
         _ => {
@@ -405,6 +526,15 @@ pub(crate) fn ty_to_vir<'tcx>(tcx: TyCtxt<'tcx>, ty: &Ty) -> Typ {
             _,
             rustc_hir::MutTy { ty: tys, mutbl: rustc_ast::Mutability::Not },
         ) => ty_to_vir(tcx, tys),
+        rustc_hir::TyKind::Slice(t) => Arc::new(TypX::Array(ty_to_vir(tcx, t), None)),
+        rustc_hir::TyKind::Array(t, len) => {
+            let len =
+                rustc_middle::ty::Const::from_anon_const(tcx, tcx.hir().local_def_id(len.hir_id));
+            match len.try_eval_usize(tcx, rustc_middle::ty::ParamEnv::empty()) {
+                Some(len) => Arc::new(TypX::Array(ty_to_vir(tcx, t), Some(len))),
+                None => unsupported!(format!("array length {:?}", span)),
+            }
+        }
         rustc_hir::TyKind::Path(QPath::Resolved(None, path)) => Arc::new(match path.res {
             Res::PrimTy(PrimTy::Bool) => TypX::Bool,
             Res::PrimTy(PrimTy::Uint(UintTy::U8)) => TypX::Int(IntRange::U(8)),
@@ -436,15 +566,25 @@ pub(crate) fn ty_to_vir<'tcx>(tcx: TyCtxt<'tcx>, ty: &Ty) -> Typ {
                         _ => panic!("unexpected arg to Box"),
                     }
                 } else {
-                    def_id_to_datatype_segments(tcx, def_id, &path.segments)
+                    match def_id_to_datatype_segments(tcx, def_id, &path.segments) {
+                        TypX::Datatype(path, typ_args) => {
+                            builtin_collection_typ(&def_name, &typ_args)
+                                .unwrap_or(TypX::Datatype(path, typ_args))
+                        }
+                        _ => panic!("internal error: expected datatype"),
+                    }
                 }
             }
             Res::Def(DefKind::Enum, def_id) => {
                 def_id_to_datatype_segments(tcx, def_id, &path.segments)
             }
-            Res::SelfTy(None, Some((impl_def_id, false))) => {
+            Res::SelfTy(_, Some((impl_def_id, false))) => {
                 def_id_to_datatype_segments(tcx, impl_def_id, &path.segments)
             }
+            Res::SelfTy(Some(_), None) => {
+                // Self inside a trait declaration
+                TypX::TypParam(Arc::new(vir::def::TRAIT_SELF_TYPE_PARAM.to_string()))
+            }
             _ => {
                 unsupported!(format!("type {:#?} {:?} {:?}", kind, path.res, span))
             }
@@ -455,6 +595,34 @@ pub(crate) fn ty_to_vir<'tcx>(tcx: TyCtxt<'tcx>, ty: &Ty) -> Typ {
     }
 }
 
+/// Parameters of type &mut T are modeled as in/out values of type T.
+/// Returns the type with the outer &mut removed, and whether there was an outer &mut.
+pub(crate) fn strip_mut_ref<'a, 'tcx>(ty: &'a Ty<'tcx>) -> (&'a Ty<'tcx>, bool) {
+    match &ty.kind {
+        rustc_hir::TyKind::Rptr(
+            _,
+            rustc_hir::MutTy { ty: tys, mutbl: rustc_ast::Mutability::Mut },
+        ) => (tys, true),
+        _ => (ty, false),
+    }
+}
+
+/// &mut T is only supported as the outermost type of a function parameter;
+/// reject it anywhere else (let bindings, fields, return types, type arguments)
+pub(crate) fn check_no_mut_ref<'tcx>(
+    span: Span,
+    ty: rustc_middle::ty::Ty<'tcx>,
+) -> Result<(), VirErr> {
+    for arg in ty.walk() {
+        if let rustc_middle::ty::subst::GenericArgKind::Type(t) = arg.unpack() {
+            if let TyKind::Ref(_, _, rustc_ast::Mutability::Mut) = t.kind() {
+                unsupported_err!(span, "&mut types, except on function parameters", ty);
+            }
+        }
+    }
+    Ok(())
+}
+
 pub(crate) struct BodyCtxt<'tcx> {
     pub(crate) ctxt: Context<'tcx>,
     pub(crate) types: &'tcx TypeckResults<'tcx>,
@@ -467,18 +635,15 @@ pub(crate) fn typ_of_node<'tcx>(bctx: &BodyCtxt<'tcx>, id: &HirId) -> Typ {
 
 pub(crate) fn implements_structural<'tcx>(
     tcx: TyCtxt<'tcx>,
+    param_env: rustc_middle::ty::ParamEnv<'tcx>,
     ty: &'tcx rustc_middle::ty::TyS<'tcx>,
 ) -> bool {
     let structural_def_id = tcx
         .get_diagnostic_item(rustc_span::Symbol::intern("builtin::Structural"))
         .expect("structural trait is not defined");
     let substs_ref = tcx.mk_substs([].iter());
-    let ty_impls_structural = tcx.type_implements_trait((
-        structural_def_id,
-        ty,
-        substs_ref,
-        rustc_middle::ty::ParamEnv::empty(),
-    ));
+    let ty_impls_structural =
+        tcx.type_implements_trait((structural_def_id, ty, substs_ref, param_env));
     ty_impls_structural
 }
 
@@ -490,12 +655,17 @@ pub(crate) fn is_smt_equality<'tcx>(
     id2: &HirId,
 ) -> bool {
     let (t1, t2) = (typ_of_node(bctx, id1), typ_of_node(bctx, id2));
+    // The function's own bounds (e.g. T: Structural) can establish that a type is structural
+    let tcx = bctx.ctxt.tcx;
+    let param_env = tcx.param_env(bctx.types.hir_owner.to_def_id());
     match (&*t1, &*t2) {
         (TypX::Bool, TypX::Bool) => true,
         (TypX::Int(_), TypX::Int(_)) => true,
-        (TypX::Datatype(..), TypX::Datatype(..)) if types_equal(&t1, &t2) => {
+        (TypX::Datatype(..), TypX::Datatype(..)) | (TypX::TypParam(_), TypX::TypParam(_))
+            if types_equal(&t1, &t2) =>
+        {
             let ty = bctx.types.node_type(*id1);
-            implements_structural(bctx.ctxt.tcx, &ty)
+            implements_structural(tcx, param_env, &ty)
         }
         _ => false,
     }
@@ -511,20 +681,93 @@ pub(crate) fn is_smt_arith<'tcx>(bctx: &BodyCtxt<'tcx>, id1: &HirId, id2: &HirId
     }
 }
 
-pub(crate) fn check_generics<'tcx>(generics: &'tcx Generics<'tcx>) -> Result<Idents, VirErr> {
+fn check_generic_bound<'tcx>(
+    tcx: TyCtxt<'tcx>,
+    span: Span,
+    bound: &'tcx GenericBound<'tcx>,
+) -> Result<Path, VirErr> {
+    match bound {
+        GenericBound::Trait(
+            PolyTraitRef { bound_generic_params, trait_ref, span: _ },
+            TraitBoundModifier::None,
+        ) => {
+            unsupported_err_unless!(bound_generic_params.len() == 0, span, "for<...> in bounds");
+            let segment = trait_ref.path.segments.last().expect("trait path");
+            unsupported_err_unless!(
+                segment.args.is_none(),
+                span,
+                "trait bounds with type arguments"
+            );
+            match trait_ref.trait_def_id() {
+                Some(def_id) => Ok(def_id_to_vir_path(tcx, def_id)),
+                None => unsupported_err!(span, "unresolved trait bound", bound),
+            }
+        }
+        _ => unsupported_err!(span, "generic bounds other than traits", bound),
+    }
+}
+
+pub(crate) fn check_generics<'tcx>(
+    tcx: TyCtxt<'tcx>,
+    generics: &'tcx Generics<'tcx>,
+) -> Result<TypParams, VirErr> {
     let Generics { params, where_clause, span: _ } = generics;
-    let mut typ_params: Vec<vir::ast::Ident> = Vec::new();
+    let mut typ_params: Vec<(vir::ast::Ident, Vec<Path>)> = Vec::new();
     for param in params.iter() {
         let GenericParam { hir_id: _, name, bounds, span: _, pure_wrt_drop, kind } = param;
-        unsupported_err_unless!(bounds.len() == 0, generics.span, "generic bounds");
         unsupported_err_unless!(!pure_wrt_drop, generics.span, "generic pure_wrt_drop");
         match (name, kind) {
             (ParamName::Plain(id), GenericParamKind::Type { default: None, synthetic: None }) => {
-                typ_params.push(Arc::new(id.name.as_str().to_string()));
+                let mut traits: Vec<Path> = Vec::new();
+                for bound in bounds.iter() {
+                    traits.push(check_generic_bound(tcx, generics.span, bound)?);
+                }
+                typ_params.push((Arc::new(id.name.as_str().to_string()), traits));
             }
             _ => unsupported_err!(generics.span, "complex generics"),
         }
     }
-    unsupported_err_unless!(where_clause.predicates.len() == 0, generics.span, "where clause");
+    for predicate in where_clause.predicates.iter() {
+        match predicate {
+            WherePredicate::BoundPredicate(WhereBoundPredicate {
+                span,
+                bound_generic_params,
+                bounded_ty,
+                bounds,
+            }) => {
+                unsupported_err_unless!(
+                    bound_generic_params.len() == 0,
+                    *span,
+                    "for<...> in where clause"
+                );
+                // Only bounds on the function's or datatype's own type parameters are supported
+                let name = match &bounded_ty.kind {
+                    rustc_hir::TyKind::Path(QPath::Resolved(None, path)) => match path.res {
+                        Res::Def(DefKind::TyParam, _) if path.segments.len() == 1 => {
+                            Arc::new(path.segments[0].ident.name.as_str().to_string())
+                        }
+                        _ => unsupported_err!(
+                            *span,
+                            "where clause on a non-type-parameter",
+                            predicate
+                        ),
+                    },
+                    _ => unsupported_err!(*span, "where clause on a non-type-parameter", predicate),
+                };
+                let traits = match typ_params.iter_mut().find(|(x, _)| *x == name) {
+                    Some((_, traits)) => traits,
+                    None => unsupported_err!(*span, "where clause on an outer type parameter"),
+                };
+                for bound in bounds.iter() {
+                    traits.push(check_generic_bound(tcx, *span, bound)?);
+                }
+            }
+            _ => unsupported_err!(generics.span, "where clause", predicate),
+        }
+    }
+    let typ_params = typ_params
+        .into_iter()
+        .map(|(x, traits)| (x, Arc::new(GenericBoundX::Traits(traits))))
+        .collect();
     Ok(Arc::new(typ_params))
 }
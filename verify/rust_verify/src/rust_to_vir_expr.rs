@@ -1,8 +1,8 @@
 use crate::erase::ResolvedCall;
 use crate::rust_to_vir_base::{
-    def_id_to_vir_path, def_to_path_ident, get_range, get_trigger, get_var_mode, hack_get_def_name,
-    ident_to_var, is_smt_arith, is_smt_equality, mid_ty_to_vir, mid_ty_to_vir_opt, mk_range,
-    ty_to_vir, typ_of_node, BodyCtxt,
+    check_no_mut_ref, def_id_to_vir_path, def_to_path_ident, get_range, get_trigger, get_var_mode,
+    hack_get_def_name, ident_to_var, is_smt_arith, is_smt_equality, mid_ty_to_vir,
+    mid_ty_to_vir_opt, mk_range, ty_to_vir, typ_of_node, BodyCtxt,
 };
 use crate::util::{
     err_span_str, slice_vec_map_result, spanned_new, spanned_typed_new, unsupported_err_span,
@@ -11,7 +11,7 @@
 use crate::{unsupported, unsupported_err, unsupported_err_unless, unsupported_unless};
 use air::ast::{Binder, BinderX, Quant};
 use air::ast_util::str_ident;
-use rustc_ast::{Attribute, BorrowKind, LitKind, Mutability};
+use rustc_ast::{Attribute, BorrowKind, Label, LitKind, Mutability};
 use rustc_hir::def::{DefKind, Res};
 use rustc_hir::{
     Arm, BinOpKind, BindingAnnotation, Block, Destination, Expr, ExprKind, Guard, Local,
@@ -23,8 +23,9 @@
 use rustc_span::Span;
 use std::sync::Arc;
 use vir::ast::{
-    ArmX, BinaryOp, Constant, ExprX, HeaderExpr, HeaderExprX, Ident, IntRange, Mode, PatternX,
-    SpannedTyped, StmtX, Stmts, Typ, TypX, UnaryOp, UnaryOpr, VirErr,
+    ArmX, BinaryOp, Constant, ExprX, HeaderExpr, HeaderExprX, Ident, IntRange, MapOp, Mode,
+    PatternX, SeqOp, SetOp, SpannedTyped, StmtX, Stmts, Typ, TypX, UnaryOp, UnaryOpr, VarAt,
+    VirErr,
 };
 use vir::ast_util::{ident_binder, path_as_rust_name};
 use vir::def::positional_field_ident;
@@ -143,19 +144,198 @@ fn extract_quant<'tcx>(
     }
 }
 
-fn mk_clip<'tcx>(range: &IntRange, expr: &vir::ast::Expr) -> vir::ast::Expr {
+fn extract_choose<'tcx>(
+    bctx: &BodyCtxt<'tcx>,
+    span: Span,
+    expr: &'tcx Expr<'tcx>,
+) -> Result<vir::ast::Expr, VirErr> {
+    let tcx = bctx.ctxt.tcx;
+    match &expr.kind {
+        ExprKind::Closure(_, fn_decl, body_id, _, _) => {
+            let body = tcx.hir().body(*body_id);
+            unsupported_err_unless!(body.params.len() == 1, span, "choose with multiple variables");
+            let name = Arc::new(pat_to_var(body.params[0].pat));
+            let typ = ty_to_vir(tcx, &fn_decl.inputs[0]);
+            let binder = Arc::new(BinderX { name, a: typ.clone() });
+            let expr = &body.value;
+            if !matches!(bctx.types.node_type(expr.hir_id).kind(), TyKind::Bool) {
+                return err_span_str(expr.span, "choose needs a bool expression");
+            }
+            let vir_expr = expr_to_vir(bctx, expr)?;
+            Ok(spanned_typed_new(span, &typ, ExprX::Choose(binder, vir_expr)))
+        }
+        _ => err_span_str(expr.span, "argument to choose must be a closure"),
+    }
+}
+
+fn extract_assert_forall_by<'tcx>(
+    bctx: &BodyCtxt<'tcx>,
+    span: Span,
+    expr: &'tcx Expr<'tcx>,
+) -> Result<vir::ast::Expr, VirErr> {
+    let tcx = bctx.ctxt.tcx;
+    match &expr.kind {
+        ExprKind::Closure(_, fn_decl, body_id, _, _) => {
+            let body = tcx.hir().body(*body_id);
+            let vars: Vec<Binder<Typ>> = body
+                .params
+                .iter()
+                .zip(fn_decl.inputs)
+                .map(|(x, t)| {
+                    Arc::new(BinderX { name: Arc::new(pat_to_var(x.pat)), a: ty_to_vir(tcx, t) })
+                })
+                .collect();
+            let mut proof = expr_to_vir(bctx, &body.value)?;
+            let header = vir::headers::read_header(&mut proof)?;
+            if header.invariant.len() > 0 || header.decrease.len() > 0 {
+                return err_span_str(span, "assert_forall_by cannot have invariant or decreases");
+            }
+            if header.hidden.len() > 0 || header.ensure_id_typ.is_some() {
+                return err_span_str(span, "assert_forall_by cannot hide or name a return value");
+            }
+            if header.ensure.len() == 0 {
+                return err_span_str(span, "assert_forall_by must have ensures");
+            }
+            let bool_typ = Arc::new(TypX::Bool);
+            let conjoin = |es: &vir::ast::Exprs| {
+                let mut conj: Option<vir::ast::Expr> = None;
+                for e in es.iter() {
+                    conj = Some(match conj {
+                        None => e.clone(),
+                        Some(c) => {
+                            let and = ExprX::Binary(BinaryOp::And, c, e.clone());
+                            SpannedTyped::new(&e.span, &bool_typ, and)
+                        }
+                    });
+                }
+                conj.unwrap_or_else(|| {
+                    spanned_typed_new(span, &bool_typ, ExprX::Const(Constant::Bool(true)))
+                })
+            };
+            let require = conjoin(&header.require);
+            let ensure = conjoin(&header.ensure);
+            let assert_by = ExprX::AssertBy { vars: Arc::new(vars), require, ensure, proof };
+            let unit_typ = Arc::new(TypX::Tuple(Arc::new(vec![])));
+            Ok(spanned_typed_new(span, &unit_typ, assert_by))
+        }
+        _ => err_span_str(expr.span, "argument to assert_forall_by must be a closure"),
+    }
+}
+
+fn mk_clip<'tcx>(range: &IntRange, expr: &vir::ast::Expr, truncate: bool) -> vir::ast::Expr {
     match range {
         IntRange::Int => expr.clone(),
         range => SpannedTyped::new(
             &expr.span,
             &Arc::new(TypX::Int(*range)),
-            ExprX::Unary(UnaryOp::Clip(*range), expr.clone()),
+            ExprX::Unary(UnaryOp::Clip { range: *range, truncate }, expr.clone()),
         ),
     }
 }
 
 fn mk_ty_clip<'tcx>(typ: &Typ, expr: &vir::ast::Expr) -> vir::ast::Expr {
-    mk_clip(&get_range(typ), expr)
+    mk_clip(&get_range(typ), expr, false)
+}
+
+// Elements of sequences, arrays, and slices are SMT-boxed
+fn mk_box_elem(expr: &vir::ast::Expr) -> vir::ast::Expr {
+    match &*expr.typ {
+        TypX::TypParam(_) => expr.clone(), // already boxed
+        _ => SpannedTyped::new(
+            &expr.span,
+            &Arc::new(TypX::Boxed(expr.typ.clone())),
+            ExprX::UnaryOpr(UnaryOpr::Box(expr.typ.clone()), expr.clone()),
+        ),
+    }
+}
+
+// Unbox an element read from a sequence, array, slice, or map
+fn mk_unbox_elem(span: Span, elem_typ: &Typ, x: ExprX) -> vir::ast::Expr {
+    match &**elem_typ {
+        TypX::TypParam(_) => spanned_typed_new(span, elem_typ, x), // already boxed
+        _ => {
+            let boxed_typ = Arc::new(TypX::Boxed(elem_typ.clone()));
+            let boxed = spanned_typed_new(span, &boxed_typ, x);
+            spanned_typed_new(
+                span,
+                elem_typ,
+                ExprX::UnaryOpr(UnaryOpr::Unbox(elem_typ.clone()), boxed),
+            )
+        }
+    }
+}
+
+// If f is a function of builtin::Seq, builtin::Set, or builtin::Map, return that type's name
+fn builtin_collection_of_fn<'tcx>(tcx: TyCtxt<'tcx>, f: DefId) -> Option<String> {
+    let impl_id = tcx.impl_of_method(f)?;
+    match tcx.type_of(impl_id).kind() {
+        TyKind::Adt(adt, _) => {
+            let name = path_as_rust_name(&def_id_to_vir_path(tcx, adt.did));
+            match name.as_str() {
+                "builtin::Seq" | "builtin::Set" | "builtin::Map" => Some(name),
+                _ => None,
+            }
+        }
+        _ => None,
+    }
+}
+
+// Spec operations on builtin::Seq, builtin::Set, and builtin::Map (args includes any receiver)
+fn collection_fn_to_vir<'tcx>(
+    bctx: &BodyCtxt<'tcx>,
+    expr: &Expr<'tcx>,
+    collection: &str,
+    f: DefId,
+    args: &[&'tcx Expr<'tcx>],
+) -> Result<vir::ast::Expr, VirErr> {
+    let expr_typ = typ_of_node(bctx, &expr.hir_id);
+    let name = bctx.ctxt.tcx.item_name(f);
+    let vir_args = args.iter().map(|arg| expr_to_vir(bctx, arg)).collect::<Result<Vec<_>, _>>()?;
+    let arg = |i: usize| vir_args[i].clone();
+    // elements, keys, and values are boxed
+    let boxed = |i: usize| mk_box_elem(&vir_args[i]);
+    let x = match (collection, &*name.as_str()) {
+        ("builtin::Seq", "empty") => ExprX::SeqOp(SeqOp::Empty, Arc::new(vec![])),
+        ("builtin::Seq", "len") => ExprX::SeqOp(SeqOp::Len, Arc::new(vec![arg(0)])),
+        ("builtin::Seq", "index") => {
+            let index = ExprX::SeqOp(SeqOp::Index, Arc::new(vec![arg(0), arg(1)]));
+            return Ok(mk_unbox_elem(expr.span, &expr_typ, index));
+        }
+        ("builtin::Seq", "push") => ExprX::SeqOp(SeqOp::Push, Arc::new(vec![arg(0), boxed(1)])),
+        ("builtin::Seq", "update") => {
+            ExprX::SeqOp(SeqOp::Update, Arc::new(vec![arg(0), arg(1), boxed(2)]))
+        }
+        ("builtin::Seq", "subrange") => {
+            ExprX::SeqOp(SeqOp::Subrange, Arc::new(vec![arg(0), arg(1), arg(2)]))
+        }
+        ("builtin::Seq", "ext_equal") => {
+            ExprX::SeqOp(SeqOp::ExtEqual, Arc::new(vec![arg(0), arg(1)]))
+        }
+        ("builtin::Set", "empty") => ExprX::SetOp(SetOp::Empty, Arc::new(vec![])),
+        ("builtin::Set", "contains") => {
+            ExprX::SetOp(SetOp::Contains, Arc::new(vec![arg(0), boxed(1)]))
+        }
+        ("builtin::Set", "insert") => ExprX::SetOp(SetOp::Insert, Arc::new(vec![arg(0), boxed(1)])),
+        ("builtin::Set", "remove") => ExprX::SetOp(SetOp::Remove, Arc::new(vec![arg(0), boxed(1)])),
+        ("builtin::Set", "ext_equal") => {
+            ExprX::SetOp(SetOp::ExtEqual, Arc::new(vec![arg(0), arg(1)]))
+        }
+        ("builtin::Map", "empty") => ExprX::MapOp(MapOp::Empty, Arc::new(vec![])),
+        ("builtin::Map", "dom") => ExprX::MapOp(MapOp::Dom, Arc::new(vec![arg(0)])),
+        ("builtin::Map", "index") => {
+            let index = ExprX::MapOp(MapOp::Index, Arc::new(vec![arg(0), boxed(1)]));
+            return Ok(mk_unbox_elem(expr.span, &expr_typ, index));
+        }
+        ("builtin::Map", "insert") => {
+            ExprX::MapOp(MapOp::Insert, Arc::new(vec![arg(0), boxed(1), boxed(2)]))
+        }
+        ("builtin::Map", "remove") => ExprX::MapOp(MapOp::Remove, Arc::new(vec![arg(0), boxed(1)])),
+        ("builtin::Map", "ext_equal") => {
+            ExprX::MapOp(MapOp::ExtEqual, Arc::new(vec![arg(0), arg(1)]))
+        }
+        _ => unsupported_err!(expr.span, format!("function {} of {}", name, collection)),
+    };
+    Ok(spanned_typed_new(expr.span, &expr_typ, x))
 }
 
 pub(crate) fn expr_to_vir<'tcx>(
@@ -223,17 +403,27 @@ fn fn_call_to_vir<'tcx>(
 
     let f_name = path_as_rust_name(&def_id_to_vir_path(tcx, f));
     let is_admit = f_name == "builtin::admit";
+    let is_assert_bit_vector = f_name == "builtin::assert_bit_vector";
+    let is_assert_by = f_name == "builtin::assert_by";
+    let is_assert_forall_by = f_name == "builtin::assert_forall_by";
+    let is_assert_nonlinear_by = f_name == "builtin::assert_nonlinear_by";
     let is_requires = f_name == "builtin::requires";
     let is_ensures = f_name == "builtin::ensures";
     let is_invariant = f_name == "builtin::invariant";
     let is_decreases = f_name == "builtin::decreases";
+    let is_old = f_name == "builtin::old";
+    let is_no_method_body = f_name == "builtin::no_method_body";
     let is_forall = f_name == "builtin::forall";
     let is_exists = f_name == "builtin::exists";
+    let is_choose = f_name == "builtin::choose";
     let is_equal = f_name == "builtin::equal";
     let is_hide = f_name == "builtin::hide";
     let is_reveal = f_name == "builtin::reveal";
     let is_reveal_fuel = f_name == "builtin::reveal_with_fuel";
     let is_implies = f_name == "builtin::imply";
+    let is_view = f_name == "builtin::view";
+    let is_ext_equal = f_name == "builtin::ext_equal";
+    let is_truncate = f_name == "builtin::truncate";
     let is_eq = f_name == "core::cmp::PartialEq::eq";
     let is_ne = f_name == "core::cmp::PartialEq::ne";
     let is_le = f_name == "core::cmp::PartialOrd::le";
@@ -243,12 +433,25 @@ fn fn_call_to_vir<'tcx>(
     let is_add = f_name == "core::ops::arith::Add::add";
     let is_sub = f_name == "core::ops::arith::Sub::sub";
     let is_mul = f_name == "core::ops::arith::Mul::mul";
-    let is_spec = is_admit || is_requires || is_ensures || is_invariant || is_decreases;
-    let is_quant = is_forall || is_exists;
+    let is_spec = is_admit
+        || is_assert_bit_vector
+        || is_assert_by
+        || is_assert_forall_by
+        || is_assert_nonlinear_by
+        || is_requires
+        || is_ensures
+        || is_invariant
+        || is_decreases
+        || is_old
+        || is_view
+        || is_ext_equal
+        || is_no_method_body;
+    let is_quant = is_forall || is_exists || is_choose;
     let is_directive = is_hide || is_reveal || is_reveal_fuel;
     let is_cmp = is_equal || is_eq || is_ne || is_le || is_ge || is_lt || is_gt;
     let is_arith_binary = is_add || is_sub || is_mul;
-    record_fun(&bctx.ctxt, fn_span, f, is_spec || is_quant || is_directive, is_implies);
+    let is_compilable_operator = is_implies || is_truncate;
+    record_fun(&bctx.ctxt, fn_span, f, is_spec || is_quant || is_directive, is_compilable_operator);
 
     let len = args.len();
     if is_requires {
@@ -282,6 +485,91 @@ fn fn_call_to_vir<'tcx>(
         return extract_quant(bctx, expr.span, quant, args[0]);
     }
 
+    if is_choose {
+        unsupported_err_unless!(len == 1, expr.span, "expected choose", &args);
+        return extract_choose(bctx, expr.span, args[0]);
+    }
+
+    if is_assert_by {
+        unsupported_err_unless!(len == 2, expr.span, "expected assert_by", &args);
+        let vars = Arc::new(vec![]);
+        let bool_typ = Arc::new(TypX::Bool);
+        let require = spanned_typed_new(expr.span, &bool_typ, ExprX::Const(Constant::Bool(true)));
+        let ensure = expr_to_vir(bctx, &args[0])?;
+        let proof = expr_to_vir(bctx, &args[1])?;
+        return Ok(mk_expr(ExprX::AssertBy { vars, require, ensure, proof }));
+    }
+
+    if is_assert_forall_by {
+        unsupported_err_unless!(len == 1, expr.span, "expected assert_forall_by", &args);
+        return extract_assert_forall_by(bctx, expr.span, args[0]);
+    }
+
+    if is_assert_nonlinear_by {
+        unsupported_err_unless!(len == 1, expr.span, "expected assert_nonlinear_by", &args);
+        let mut proof = expr_to_vir(bctx, &args[0])?;
+        let header = vir::headers::read_header(&mut proof)?;
+        if header.invariant.len() > 0 || header.decrease.len() > 0 {
+            return err_span_str(
+                expr.span,
+                "assert_nonlinear_by cannot have invariant or decreases",
+            );
+        }
+        if header.hidden.len() > 0 || header.ensure_id_typ.is_some() {
+            return err_span_str(
+                expr.span,
+                "assert_nonlinear_by cannot hide or name a return value",
+            );
+        }
+        if header.ensure.len() == 0 {
+            return err_span_str(expr.span, "assert_nonlinear_by must have ensures");
+        }
+        let (requires, ensures) = (header.require, header.ensure);
+        return Ok(mk_expr(ExprX::AssertNonlinear { requires, ensures, proof }));
+    }
+
+    if is_old {
+        unsupported_err_unless!(len == 1, expr.span, "expected old", &args);
+        match &expr_to_vir(bctx, &args[0])?.x {
+            ExprX::Var(x) => return Ok(mk_expr(ExprX::VarAt(x.clone(), VarAt::Pre))),
+            _ => return err_span_str(args[0].span, "the argument of old must be a parameter"),
+        }
+    }
+
+    if is_view {
+        unsupported_err_unless!(len == 1, expr.span, "expected view", &args);
+        let vir_arg = expr_to_vir(bctx, &args[0])?;
+        return Ok(mk_expr(ExprX::SeqOp(SeqOp::View, Arc::new(vec![vir_arg]))));
+    }
+
+    if is_ext_equal {
+        unsupported_err_unless!(len == 2, expr.span, "expected ext_equal", &args);
+        let lhs = expr_to_vir(bctx, &args[0])?;
+        let rhs = expr_to_vir(bctx, &args[1])?;
+        let x = match &*lhs.typ {
+            TypX::Seq(_) | TypX::Array(..) => {
+                ExprX::SeqOp(SeqOp::ExtEqual, Arc::new(vec![lhs, rhs]))
+            }
+            TypX::Set(_) => ExprX::SetOp(SetOp::ExtEqual, Arc::new(vec![lhs, rhs])),
+            TypX::Map(..) => ExprX::MapOp(MapOp::ExtEqual, Arc::new(vec![lhs, rhs])),
+            TypX::TypParam(_) => {
+                return err_span_str(expr.span, "ext_equal is not supported for type parameters");
+            }
+            // integers, bools, and datatypes are already equal when they are extensionally equal
+            _ => ExprX::Binary(BinaryOp::Eq(Mode::Spec), lhs, rhs),
+        };
+        return Ok(mk_expr(x));
+    }
+
+    if is_truncate {
+        unsupported_err_unless!(len == 1, expr.span, "expected truncate", &args);
+        let source = expr_to_vir(bctx, &args[0])?;
+        match (&*source.typ, &*expr_typ) {
+            (TypX::Int(_), TypX::Int(range)) => return Ok(mk_clip(range, &source, true)),
+            _ => return err_span_str(expr.span, "truncate expects integer types"),
+        }
+    }
+
     if is_hide || is_reveal {
         unsupported_err_unless!(len == 1, expr.span, "expected hide/reveal", &args);
         let x = get_fn_path(tcx, &args[0])?;
@@ -324,11 +612,22 @@ fn fn_call_to_vir<'tcx>(
         Ok(mk_expr(ExprX::Header(header)))
     } else if is_decreases {
         unsupported_err_unless!(len == 1, expr.span, "expected decreases", &args);
-        let header = Arc::new(HeaderExprX::Decreases(vir_args[0].clone()));
+        // decreases((e1, ..., en)) orders e1, ..., en lexicographically
+        let decrease = match &vir_args[0].x {
+            ExprX::Tuple(es) => es.clone(),
+            _ => Arc::new(vec![vir_args[0].clone()]),
+        };
+        let header = Arc::new(HeaderExprX::Decreases(decrease));
         Ok(mk_expr(ExprX::Header(header)))
     } else if is_admit {
         unsupported_err_unless!(len == 0, expr.span, "expected admit", args);
         Ok(mk_expr(ExprX::Admit))
+    } else if is_assert_bit_vector {
+        unsupported_err_unless!(len == 1, expr.span, "expected assert_bit_vector", args);
+        Ok(mk_expr(ExprX::AssertBV(vir_args[0].clone())))
+    } else if is_no_method_body {
+        unsupported_err_unless!(len == 0, expr.span, "expected no_method_body", args);
+        Ok(mk_expr(ExprX::Header(Arc::new(HeaderExprX::NoMethodBody))))
     } else if is_smt_binary {
         unsupported_err_unless!(len == 2, expr.span, "expected binary op", args);
         let lhs = vir_args[0].clone();
@@ -361,15 +660,20 @@ fn fn_call_to_vir<'tcx>(
         let e = mk_expr(ExprX::Binary(vop, lhs, rhs));
         if is_arith_binary { Ok(mk_ty_clip(&expr_typ, &e)) } else { Ok(e) }
     } else {
-        let (param_typs, ret_typ) = match fun_ty.kind() {
+        let (param_typs, param_muts, ret_typ) = match fun_ty.kind() {
             TyKind::FnDef(def_id, _substs) => {
                 let fn_sig = tcx.fn_sig(*def_id);
-                // TODO: I believe this remains safe in this context until we implement mutable
-                // references, at least
+                // TODO: I believe this remains safe in this context, since mid_ty_to_vir
+                // ignores the lifetimes of references
                 let f = fn_sig.skip_binder();
                 let params: Vec<Typ> = f.inputs().iter().map(|t| mid_ty_to_vir(tcx, *t)).collect();
+                let muts: Vec<bool> = f
+                    .inputs()
+                    .iter()
+                    .map(|t| matches!(t.kind(), TyKind::Ref(_, _, Mutability::Mut)))
+                    .collect();
                 let ret = mid_ty_to_vir_opt(tcx, f.output());
-                (params, ret)
+                (params, muts, ret)
             }
             _ => {
                 unsupported_err!(expr.span, format!("call to non-FnDef function"), expr)
@@ -379,6 +683,20 @@ fn fn_call_to_vir<'tcx>(
         assert_eq!(vir_args.len(), param_typs.len());
         for i in 0..vir_args.len() {
             let arg = &vir_args[i].clone();
+            if param_muts[i] {
+                // pass a location to a &mut parameter, either explicitly (&mut x)
+                // or implicitly (reborrowing a &mut variable, or autoref of a method receiver)
+                let boxed = match (&*param_typs[i], &*arg.typ) {
+                    (TypX::TypParam(_), TypX::TypParam(_)) => false,
+                    (TypX::TypParam(_), _) => true,
+                    _ => false,
+                };
+                unsupported_err_unless!(!boxed, args[i].span, "&mut T argument for generic T");
+                if !matches!(&arg.x, ExprX::Loc(_)) {
+                    vir_args[i] = arg.new_x(ExprX::Loc(arg.clone()));
+                }
+                continue;
+            }
             match (&*param_typs[i], &*arg.typ) {
                 (TypX::TypParam(_), TypX::TypParam(_)) => {} // already boxed
                 (TypX::TypParam(_), _) => {
@@ -394,6 +712,7 @@ fn fn_call_to_vir<'tcx>(
         for typ_arg in node_substs {
             match typ_arg.unpack() {
                 GenericArgKind::Type(ty) => {
+                    check_no_mut_ref(expr.span, ty)?;
                     typ_args.push(mid_ty_to_vir(tcx, ty));
                 }
                 _ => unsupported_err!(expr.span, format!("lifetime/const type arguments"), expr),
@@ -570,6 +889,48 @@ pub(crate) fn pattern_to_vir<'tcx>(
     Ok(spanned_typed_new(pat.span, &pat_typ, pattern))
 }
 
+fn block_to_vir<'tcx>(
+    bctx: &BodyCtxt<'tcx>,
+    block: &Block<'tcx>,
+    span: Span,
+    typ: &Typ,
+) -> Result<vir::ast::Expr, VirErr> {
+    let vir_stmts: Stmts = Arc::new(
+        slice_vec_map_result(block.stmts, |stmt| stmt_to_vir(bctx, stmt))?
+            .into_iter()
+            .flatten()
+            .collect(),
+    );
+    let vir_expr = block.expr.map(|expr| expr_to_vir(bctx, &expr)).transpose()?;
+    Ok(spanned_typed_new(span, typ, ExprX::Block(vir_stmts, vir_expr)))
+}
+
+fn loop_to_vir<'tcx>(
+    expr: &Expr<'tcx>,
+    label: &Option<Label>,
+    cond: Option<vir::ast::Expr>,
+    mut body: vir::ast::Expr,
+    typ: &Typ,
+) -> Result<vir::ast::Expr, VirErr> {
+    let header = vir::headers::read_header(&mut body)?;
+    if header.require.len() > 0 {
+        return err_span_str(expr.span, "loops cannot have requires");
+    }
+    if header.ensure_id_typ.is_some() {
+        return err_span_str(expr.span, "loop ensures cannot refer to a return value");
+    }
+    let label = label.map(|l| Arc::new(l.ident.to_string()));
+    let invs = header.invariant;
+    let enss = header.ensure;
+    let decrease = match header.decrease.len() {
+        0 => None,
+        1 => Some(header.decrease[0].clone()),
+        _ => return err_span_str(expr.span, "not yet supported: lexicographic decreases on loops"),
+    };
+    let loopx = ExprX::Loop { label, cond, body, invs, enss, decrease };
+    Ok(spanned_typed_new(expr.span, typ, loopx))
+}
+
 pub(crate) fn expr_to_vir_inner<'tcx>(
     bctx: &BodyCtxt<'tcx>,
     expr: &Expr<'tcx>,
@@ -580,16 +941,7 @@ pub(crate) fn expr_to_vir_inner<'tcx>(
     let mk_expr = |x: ExprX| spanned_typed_new(expr.span, &expr_typ, x);
 
     match &expr.kind {
-        ExprKind::Block(body, _) => {
-            let vir_stmts: Stmts = Arc::new(
-                slice_vec_map_result(body.stmts, |stmt| stmt_to_vir(bctx, stmt))?
-                    .into_iter()
-                    .flatten()
-                    .collect(),
-            );
-            let vir_expr = body.expr.map(|expr| expr_to_vir(bctx, &expr)).transpose()?;
-            Ok(mk_expr(ExprX::Block(vir_stmts, vir_expr)))
-        }
+        ExprKind::Block(body, _) => block_to_vir(bctx, body, expr.span, &expr_typ),
         ExprKind::Call(fun, args_slice) => {
             match fun.kind {
                 // a tuple-style datatype constructor
@@ -611,6 +963,21 @@ pub(crate) fn expr_to_vir_inner<'tcx>(
                     fun.span,
                     args_slice,
                 ),
+                // an associated function of a builtin collection type, such as Seq::empty()
+                ExprKind::Path(QPath::TypeRelative(..)) => {
+                    let f = bctx
+                        .types
+                        .type_dependent_def_id(fun.hir_id)
+                        .expect("def id of the associated function");
+                    match builtin_collection_of_fn(tcx, f) {
+                        Some(collection) => {
+                            record_fun(&bctx.ctxt, fun.span, f, true, false);
+                            let args: Vec<&Expr> = args_slice.iter().collect();
+                            collection_fn_to_vir(bctx, expr, &collection, f, &args)
+                        }
+                        None => unsupported_err!(expr.span, "call to an associated function"),
+                    }
+                }
                 _ => unsupported!("fun_kind_not_ctor_or_fn", expr.span),
             }
         }
@@ -639,7 +1006,7 @@ pub(crate) fn expr_to_vir_inner<'tcx>(
                         _ => {
                             // If we're not sure the constant fits in the range,
                             // be cautious and clip it
-                            Ok(mk_clip(&range, &mk_expr(ExprX::Const(c))))
+                            Ok(mk_clip(&range, &mk_expr(ExprX::Const(c)), false))
                         }
                     }
                 } else {
@@ -652,20 +1019,30 @@ pub(crate) fn expr_to_vir_inner<'tcx>(
         },
         ExprKind::Cast(source, _) => Ok(mk_ty_clip(&expr_typ, &expr_to_vir(bctx, source)?)),
         ExprKind::AddrOf(BorrowKind::Ref, Mutability::Not, e) => expr_to_vir_inner(bctx, e),
+        ExprKind::AddrOf(BorrowKind::Ref, Mutability::Mut, e) => {
+            Ok(mk_expr(ExprX::Loc(expr_to_vir(bctx, e)?)))
+        }
         ExprKind::Box(e) => expr_to_vir_inner(bctx, e),
         ExprKind::Unary(op, arg) => match op {
             UnOp::Not => {
                 let varg = expr_to_vir(bctx, arg)?;
-                Ok(mk_expr(ExprX::Unary(UnaryOp::Not, varg)))
+                match &*expr_typ {
+                    TypX::Int(_) => {
+                        let e = mk_expr(ExprX::Unary(UnaryOp::BitNot, varg));
+                        Ok(mk_ty_clip(&expr_typ, &e))
+                    }
+                    _ => Ok(mk_expr(ExprX::Unary(UnaryOp::Not, varg))),
+                }
             }
             UnOp::Neg => {
                 let zero_const = vir::ast::Constant::Nat(Arc::new("0".to_string()));
                 let zero = mk_expr(ExprX::Const(zero_const));
                 let varg = expr_to_vir(bctx, arg)?;
-                Ok(mk_expr(ExprX::Binary(BinaryOp::Sub, zero, varg)))
+                let e = mk_expr(ExprX::Binary(BinaryOp::Sub, zero, varg));
+                Ok(mk_ty_clip(&expr_typ, &e))
             }
             UnOp::Deref => match bctx.types.node_type(arg.hir_id).kind() {
-                TyKind::Ref(_, _tys, rustc_ast::Mutability::Not) => expr_to_vir_inner(bctx, arg),
+                TyKind::Ref(_, _tys, _) => expr_to_vir_inner(bctx, arg),
                 _ => unsupported_err!(expr.span, "dereferencing this type is unsupported", expr),
             },
         },
@@ -689,6 +1066,18 @@ pub(crate) fn expr_to_vir_inner<'tcx>(
                     "cmp or arithmetic for non smt arithmetic types",
                     expr
                 ),
+                BinOpKind::BitAnd
+                | BinOpKind::BitOr
+                | BinOpKind::BitXor
+                | BinOpKind::Shl
+                | BinOpKind::Shr => unsupported_err_unless!(
+                    matches!(
+                        (&*typ_of_node(bctx, &lhs.hir_id), &*typ_of_node(bctx, &rhs.hir_id)),
+                        (TypX::Int(_), TypX::Int(_))
+                    ),
+                    expr.span,
+                    "bitwise operators on non-integer types"
+                ),
                 _ => (),
             }
             let vop = match op.node {
@@ -703,26 +1092,51 @@ pub(crate) fn expr_to_vir_inner<'tcx>(
                 BinOpKind::Add => BinaryOp::Add,
                 BinOpKind::Sub => BinaryOp::Sub,
                 BinOpKind::Mul => BinaryOp::Mul,
-                BinOpKind::Div => BinaryOp::EuclideanDiv,
-                BinOpKind::Rem => BinaryOp::EuclideanMod,
-                _ => unsupported_err!(expr.span, format!("binary operator {:?}", op)),
-            };
-            let e = mk_expr(ExprX::Binary(vop, vlhs, vrhs));
-            match op.node {
-                BinOpKind::Add | BinOpKind::Sub | BinOpKind::Mul => Ok(mk_ty_clip(&expr_typ, &e)),
+                BinOpKind::BitAnd => BinaryOp::BitAnd,
+                BinOpKind::BitOr => BinaryOp::BitOr,
+                BinOpKind::BitXor => BinaryOp::BitXor,
+                BinOpKind::Shl => BinaryOp::Shl,
+                BinOpKind::Shr => BinaryOp::Shr,
                 BinOpKind::Div | BinOpKind::Rem => {
-                    // TODO: disallow divide-by-zero in executable code?
+                    let is_div = op.node == BinOpKind::Div;
                     match mk_range(tc.node_type(expr.hir_id)) {
                         IntRange::Int | IntRange::Nat | IntRange::U(_) | IntRange::USize => {
                             // Euclidean division
-                            Ok(e)
+                            if is_div {
+                                BinaryOp::EuclideanDiv
+                            } else {
+                                BinaryOp::EuclideanMod
+                            }
                         }
                         IntRange::I(_) | IntRange::ISize => {
-                            // Non-Euclidean division, which will need more encoding
-                            unsupported_err!(expr.span, "div/mod on signed finite-width integers")
+                            // Rust's signed division rounds towards zero
+                            if is_div {
+                                BinaryOp::TruncDiv
+                            } else {
+                                BinaryOp::TruncMod
+                            }
                         }
                     }
                 }
+                _ => unsupported_err!(expr.span, format!("binary operator {:?}", op)),
+            };
+            let e = mk_expr(ExprX::Binary(vop, vlhs, vrhs));
+            match op.node {
+                BinOpKind::Add | BinOpKind::Sub | BinOpKind::Mul => Ok(mk_ty_clip(&expr_typ, &e)),
+                // the bitwise operators are uninterpreted, so clip to keep the result in range
+                BinOpKind::BitAnd
+                | BinOpKind::BitOr
+                | BinOpKind::BitXor
+                | BinOpKind::Shl
+                | BinOpKind::Shr => Ok(mk_ty_clip(&expr_typ, &e)),
+                BinOpKind::Div | BinOpKind::Rem => {
+                    // exec code checks for divide-by-zero in ast_to_sst
+                    match vop {
+                        // MIN / -1 overflows (exec code checks for this in ast_to_sst)
+                        BinaryOp::TruncDiv => Ok(mk_ty_clip(&expr_typ, &e)),
+                        _ => Ok(e),
+                    }
+                }
                 _ => Ok(e),
             }
         }
@@ -747,14 +1161,65 @@ pub(crate) fn expr_to_vir_inner<'tcx>(
             }
             res => unsupported_err!(expr.span, format!("Path {:?}", res)),
         },
-        ExprKind::Assign(lhs, rhs, _) => {
-            Ok(mk_expr(ExprX::Assign(expr_to_vir(bctx, lhs)?, expr_to_vir(bctx, rhs)?)))
+        ExprKind::Assign(lhs, rhs, _) => match &lhs.kind {
+            ExprKind::Index(base, idx) => {
+                // a[i] = v assigns a whole new array or slice to a: a = update(a, i, v)
+                let vir_base = expr_to_vir(bctx, base)?;
+                let x = match &vir_base.x {
+                    ExprX::Var(x) => x.clone(),
+                    _ => unsupported_err!(lhs.span, "assignment to an element of a complex array"),
+                };
+                let vir_idx = expr_to_vir(bctx, idx)?;
+                let vir_rhs = mk_box_elem(&expr_to_vir(bctx, rhs)?);
+                let args = Arc::new(vec![vir_base.clone(), vir_idx, vir_rhs]);
+                let update =
+                    spanned_typed_new(lhs.span, &vir_base.typ, ExprX::SeqOp(SeqOp::Update, args));
+                let dest = spanned_typed_new(lhs.span, &vir_base.typ, ExprX::Var(x));
+                Ok(mk_expr(ExprX::Assign(dest, update)))
+            }
+            _ => Ok(mk_expr(ExprX::Assign(expr_to_vir(bctx, lhs)?, expr_to_vir(bctx, rhs)?))),
+        },
+        ExprKind::Index(base, idx) => {
+            unsupported_err_unless!(
+                matches!(&*typ_of_node(bctx, &base.hir_id), TypX::Array(..))
+                    && matches!(&*typ_of_node(bctx, &idx.hir_id), TypX::Int(_)),
+                expr.span,
+                "index expression other than an integer index into an array or slice"
+            );
+            let vir_base = expr_to_vir(bctx, base)?;
+            let vir_idx = expr_to_vir(bctx, idx)?;
+            let index = ExprX::SeqOp(SeqOp::Index, Arc::new(vec![vir_base, vir_idx]));
+            Ok(mk_unbox_elem(expr.span, &expr_typ, index))
+        }
+        ExprKind::Array(elems) => {
+            let elem_typ = match &*expr_typ {
+                TypX::Array(t, _) => t.clone(),
+                _ => panic!("internal error: array literal of non-array type"),
+            };
+            // [e0, ..., en] is push(...push(empty, e0)..., en)
+            let empty_typ = Arc::new(TypX::Array(elem_typ.clone(), Some(0)));
+            let mut seq = spanned_typed_new(
+                expr.span,
+                &empty_typ,
+                ExprX::SeqOp(SeqOp::Empty, Arc::new(vec![])),
+            );
+            for (i, elem) in elems.iter().enumerate() {
+                let vir_elem = mk_box_elem(&expr_to_vir(bctx, elem)?);
+                let typ = Arc::new(TypX::Array(elem_typ.clone(), Some(i as u64 + 1)));
+                let push = ExprX::SeqOp(SeqOp::Push, Arc::new(vec![seq, vir_elem]));
+                seq = spanned_typed_new(expr.span, &typ, push);
+            }
+            Ok(seq)
+        }
+        ExprKind::Ret(e1) => {
+            let vir_e1 = e1.map(|e1| expr_to_vir(bctx, e1)).transpose()?;
+            Ok(mk_expr(ExprX::Return(vir_e1)))
         }
         ExprKind::Field(lhs, name) => {
             let vir_lhs = expr_to_vir(bctx, lhs)?;
             let lhs_ty = tc.node_type(lhs.hir_id);
             let lhs_ty = match lhs_ty.kind() {
-                TyKind::Ref(_, lt, Mutability::Not) => lt,
+                TyKind::Ref(_, lt, _) => lt,
                 _ => lhs_ty,
             };
             let (datatype, variant_name, field_name, unbox) = if let Some(adt_def) =
@@ -862,12 +1327,7 @@ pub(crate) fn expr_to_vir_inner<'tcx>(
                                 }, Arm {
                                     pat: Pat { kind: PatKind::Wild, .. },
                                     guard: None,
-                                    body:
-                                        Expr {
-                                            kind:
-                                                ExprKind::Break(Destination { label: None, .. }, None),
-                                            ..
-                                        },
+                                    body: Expr { kind: ExprKind::Break(_, None), .. },
                                     ..
                                 }],
                                 MatchSource::WhileDesugar,
@@ -876,7 +1336,7 @@ pub(crate) fn expr_to_vir_inner<'tcx>(
                     }),
                 ..
             },
-            None,
+            label,
             LoopSource::While,
             _span,
         ) => {
@@ -885,10 +1345,21 @@ pub(crate) fn expr_to_vir_inner<'tcx>(
                 _ => cond,
             };
             let cond = expr_to_vir(bctx, cond)?;
-            let mut body = expr_to_vir(bctx, body)?;
-            let header = vir::headers::read_header(&mut body)?;
-            let invs = header.invariant;
-            Ok(mk_expr(ExprX::While { cond, body, invs }))
+            let body = expr_to_vir(bctx, body)?;
+            loop_to_vir(expr, label, Some(cond), body, &expr_typ)
+        }
+        ExprKind::Loop(block, label, LoopSource::Loop, _span) => {
+            let unit_typ = Arc::new(TypX::Tuple(Arc::new(vec![])));
+            let body = block_to_vir(bctx, block, block.span, &unit_typ)?;
+            loop_to_vir(expr, label, None, body, &expr_typ)
+        }
+        ExprKind::Break(Destination { label, .. }, None) => {
+            let label = label.map(|l| Arc::new(l.ident.to_string()));
+            Ok(mk_expr(ExprX::BreakOrContinue { label, is_break: true }))
+        }
+        ExprKind::Continue(Destination { label, .. }) => {
+            let label = label.map(|l| Arc::new(l.ident.to_string()));
+            Ok(mk_expr(ExprX::BreakOrContinue { label, is_break: false }))
         }
         ExprKind::Struct(qpath, fields, spread) => {
             unsupported_unless!(spread.is_none(), "spread_in_struct_ctor");
@@ -943,32 +1414,70 @@ pub(crate) fn expr_to_vir_inner<'tcx>(
             );
             Ok(mk_expr(ExprX::Ctor(path, variant_name, vir_fields)))
         }
-        ExprKind::MethodCall(_name_and_generics, _call_span_0, all_args, _call_span_1) => {
+        ExprKind::MethodCall(name_and_generics, _call_span_0, all_args, _call_span_1) => {
             let receiver = all_args.first().expect("receiver in method call");
-            let self_path = match &(*typ_of_node(bctx, &receiver.hir_id)) {
-                TypX::Datatype(path, _) => path.clone(),
-                _ => panic!("unexpected receiver type"),
-            };
+            let method_name = name_and_generics.ident.as_str();
+            match (&*typ_of_node(bctx, &receiver.hir_id), &*method_name) {
+                (TypX::Array(..), "len") => {
+                    let vir_receiver = expr_to_vir(bctx, receiver)?;
+                    return Ok(mk_expr(ExprX::SeqOp(SeqOp::Len, Arc::new(vec![vir_receiver]))));
+                }
+                (TypX::Array(..), _) => {
+                    unsupported_err!(
+                        expr.span,
+                        format!("method {} on an array or slice", method_name)
+                    )
+                }
+                _ => {}
+            }
             let fn_def_id = bctx
                 .types
                 .type_dependent_def_id(expr.hir_id)
                 .expect("def id of the method definition");
-            let sig = if let rustc_hir::Node::ImplItem(rustc_hir::ImplItem {
-                kind: rustc_hir::ImplItemKind::Fn(sig, _body_id),
-                ..
-            }) = tcx.hir().get_if_local(fn_def_id).expect("fn def for method in hir")
-            {
-                sig
+            if let Some(collection) = builtin_collection_of_fn(tcx, fn_def_id) {
+                let args: Vec<&Expr> = all_args.iter().collect();
+                return collection_fn_to_vir(bctx, expr, &collection, fn_def_id, &args);
+            }
+            let node_substs = bctx.types.node_substs(expr.hir_id);
+            // For a trait method, call the impl's method if the receiver type determines the impl;
+            // otherwise (e.g. for a receiver of generic type), call the trait's declaration
+            let (fn_def_id, node_substs) = if tcx.trait_of_item(fn_def_id).is_some() {
+                let param_env = tcx.param_env(bctx.types.hir_owner.to_def_id());
+                match rustc_middle::ty::Instance::resolve(tcx, param_env, fn_def_id, node_substs) {
+                    Ok(Some(instance)) if tcx.impl_of_method(instance.def_id()).is_some() => {
+                        (instance.def_id(), instance.substs)
+                    }
+                    _ => (fn_def_id, node_substs),
+                }
             } else {
-                panic!("unexpected hir for method impl item");
+                (fn_def_id, node_substs)
+            };
+            let self_path = if tcx.trait_of_item(fn_def_id).is_some() {
+                None
+            } else {
+                match &(*typ_of_node(bctx, &receiver.hir_id)) {
+                    TypX::Datatype(path, _) => Some(path.clone()),
+                    _ => unsupported_err!(expr.span, "method call on non-datatype receiver", expr),
+                }
+            };
+            let sig = match tcx.hir().get_if_local(fn_def_id).expect("fn def for method in hir") {
+                Node::ImplItem(rustc_hir::ImplItem {
+                    kind: rustc_hir::ImplItemKind::Fn(sig, _body_id),
+                    ..
+                }) => sig,
+                Node::TraitItem(rustc_hir::TraitItem {
+                    kind: rustc_hir::TraitItemKind::Fn(sig, _trait_fn),
+                    ..
+                }) => sig,
+                _ => panic!("unexpected hir for method impl item"),
             };
             fn_call_to_vir(
                 bctx,
                 expr,
-                Some(self_path),
+                self_path,
                 fn_def_id,
                 tcx.type_of(fn_def_id),
-                bctx.types.node_substs(expr.hir_id),
+                node_substs,
                 sig.span,
                 all_args,
             )
@@ -985,6 +1494,7 @@ pub(crate) fn let_stmt_to_vir<'tcx>(
     initializer: &Option<&Expr<'tcx>>,
     attrs: &[Attribute],
 ) -> Result<Vec<vir::ast::Stmt>, VirErr> {
+    check_no_mut_ref(pattern.span, bctx.types.node_type(pattern.hir_id))?;
     let vir_pattern = pattern_to_vir(bctx, pattern)?;
     let mode = get_var_mode(bctx.mode, attrs);
     let init = initializer.map(|e| expr_to_vir(bctx, e)).transpose()?;
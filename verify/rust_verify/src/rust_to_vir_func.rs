@@ -1,19 +1,19 @@
 use crate::context::Context;
 use crate::rust_to_vir_base::{
-    check_generics, def_id_to_vir_path, def_to_path_ident, get_fuel, get_mode, get_var_mode,
-    get_verifier_attrs, ident_to_var, ty_to_vir, BodyCtxt,
+    check_generics, check_no_mut_ref, def_id_to_vir_path, def_to_path_ident, get_fuel, get_mode,
+    get_var_mode, get_verifier_attrs, ident_to_var, strip_mut_ref, ty_to_vir, BodyCtxt,
 };
 use crate::rust_to_vir_expr::{expr_to_vir, pat_to_var};
 use crate::util::{err_span_str, err_span_string, spanned_new, unsupported_err_span, vec_map};
 use crate::{unsupported, unsupported_err, unsupported_err_unless, unsupported_unless};
 use rustc_ast::Attribute;
 use rustc_hir::{Body, BodyId, FnDecl, FnHeader, FnSig, Generics, Param, Unsafety};
-use rustc_middle::ty::TyCtxt;
+use rustc_middle::ty::{TyCtxt, TyKind};
 use rustc_span::symbol::Ident;
 use rustc_span::Span;
 use std::sync::Arc;
-use vir::ast::{FunctionX, KrateX, Mode, ParamX, Typ, TypX, VirErr};
-use vir::def::RETURN_VALUE;
+use vir::ast::{FunctionKind, FunctionX, GenericBoundX, KrateX, Mode, ParamX, Typ, TypX, VirErr};
+use vir::def::{RETURN_VALUE, TRAIT_SELF_TYPE_PARAM};
 
 pub(crate) fn body_to_vir<'tcx>(
     ctxt: &Context<'tcx>,
@@ -38,6 +38,7 @@ fn check_fn_decl<'tcx>(
         rustc_hir::ImplicitSelfKind::None => {}
         rustc_hir::ImplicitSelfKind::Imm => {}
         rustc_hir::ImplicitSelfKind::ImmRef => {}
+        rustc_hir::ImplicitSelfKind::MutRef => {}
         _ => unsupported!("implicit_self"),
     }
     match output {
@@ -49,10 +50,29 @@ fn check_fn_decl<'tcx>(
     }
 }
 
+fn check_fn_sig_mut_refs<'tcx>(
+    tcx: TyCtxt<'tcx>,
+    id: rustc_span::def_id::DefId,
+    span: Span,
+) -> Result<(), VirErr> {
+    let fn_sig = tcx.fn_sig(id);
+    // the lifetimes of references are irrelevant here
+    let fn_sig = fn_sig.skip_binder();
+    for input in fn_sig.inputs().iter() {
+        let input = match input.kind() {
+            TyKind::Ref(_, t, rustc_ast::Mutability::Mut) => t,
+            _ => input,
+        };
+        check_no_mut_ref(span, *input)?;
+    }
+    check_no_mut_ref(span, fn_sig.output())
+}
+
 pub(crate) fn check_item_fn<'tcx>(
     ctxt: &Context<'tcx>,
     vir: &mut KrateX,
     self_path: Option<vir::ast::Path>,
+    kind: FunctionKind,
     id: rustc_span::def_id::DefId,
     visibility: vir::ast::Visibility,
     attrs: &[Attribute],
@@ -75,10 +95,16 @@ pub(crate) fn check_item_fn<'tcx>(
             span: _,
         } => {
             unsupported_err_unless!(*unsafety == Unsafety::Normal, sig.span, "unsafe");
+            check_fn_sig_mut_refs(ctxt.tcx, id, sig.span)?;
             check_fn_decl(ctxt.tcx, decl, mode)?
         }
     };
-    let typ_params = check_generics(generics)?;
+    let mut typ_params = (*check_generics(ctxt.tcx, generics)?).clone();
+    if let FunctionKind::TraitMethodDecl { trait_path } = &kind {
+        let bound = Arc::new(GenericBoundX::Traits(vec![trait_path.clone()]));
+        typ_params.insert(0, (Arc::new(TRAIT_SELF_TYPE_PARAM.to_string()), bound));
+    }
+    let typ_params = Arc::new(typ_params);
     let fuel = get_fuel(attrs);
     let vattrs = get_verifier_attrs(attrs)?;
     if vattrs.external {
@@ -92,24 +118,23 @@ pub(crate) fn check_item_fn<'tcx>(
     for (param, input) in params.iter().zip(sig.decl.inputs.iter()) {
         let Param { hir_id, pat, ty_span: _, span } = param;
         let name = Arc::new(pat_to_var(pat));
-        fn is_self_or_self_ref(span: Span, ty: &rustc_hir::Ty) -> Result<bool, VirErr> {
+        fn is_self_or_self_ref(ty: &rustc_hir::Ty) -> bool {
             match ty.kind {
-                rustc_hir::TyKind::Rptr(
-                    _,
-                    rustc_hir::MutTy { ty: rty, mutbl: rustc_hir::Mutability::Not, .. },
-                ) => is_self_or_self_ref(span, rty),
+                rustc_hir::TyKind::Rptr(_, rustc_hir::MutTy { ty: rty, .. }) => {
+                    is_self_or_self_ref(rty)
+                }
                 rustc_hir::TyKind::Path(rustc_hir::QPath::Resolved(None, path)) => match path.res {
-                    rustc_hir::def::Res::SelfTy(Some(_), _impl_def_id) => {
-                        unsupported_err!(span, "trait self", ty)
-                    }
-                    rustc_hir::def::Res::SelfTy(None, _) => Ok(true),
-                    _ => Ok(false),
+                    rustc_hir::def::Res::SelfTy(_, Some(_)) => true,
+                    // Self in a trait declaration is a type parameter; ty_to_vir handles it
+                    rustc_hir::def::Res::SelfTy(_, None) => false,
+                    _ => false,
                 },
-                _ => Ok(false),
+                _ => false,
             }
         }
-        let typ_args = vec_map(&typ_params, |t| Arc::new(TypX::TypParam(t.clone())));
-        let typ = if is_self_or_self_ref(*span, &input)? {
+        let typ_args = vec_map(&typ_params, |(t, _)| Arc::new(TypX::TypParam(t.clone())));
+        let (input, is_mut) = strip_mut_ref(input);
+        let typ = if is_self_or_self_ref(&input) {
             Arc::new(TypX::Datatype(
                 self_path.as_ref().expect("a param is Self, so this must be an impl").clone(),
                 Arc::new(typ_args),
@@ -118,7 +143,7 @@ fn is_self_or_self_ref(span: Span, ty: &rustc_hir::Ty) -> Result<bool, VirErr> {
             ty_to_vir(ctxt.tcx, input)
         };
         let mode = get_var_mode(mode, ctxt.tcx.hir().attrs(*hir_id));
-        let vir_param = spanned_new(*span, ParamX { name, typ, mode });
+        let vir_param = spanned_new(*span, ParamX { name, typ, mode, is_mut });
         vir_params.push(vir_param);
     }
     match generator_kind {
@@ -129,6 +154,22 @@ fn is_self_or_self_ref(span: Span, ty: &rustc_hir::Ty) -> Result<bool, VirErr> {
     }
     let mut vir_body = body_to_vir(ctxt, body_id, body, mode)?;
     let header = vir::headers::read_header(&mut vir_body)?;
+    match (&kind, header.no_method_body) {
+        (FunctionKind::TraitMethodDecl { .. }, true) => {}
+        (FunctionKind::TraitMethodDecl { .. }, false) => {
+            return err_span_str(
+                sig.span,
+                "trait method declaration body must end with no_method_body() (default method implementations are not supported)",
+            );
+        }
+        (_, true) => {
+            return err_span_str(
+                sig.span,
+                "no_method_body can only appear in trait method declarations",
+            );
+        }
+        (_, false) => {}
+    }
     if mode == Mode::Spec && (header.require.len() + header.ensure.len()) > 0 {
         return err_span_str(sig.span, "spec functions cannot have requires/ensures");
     }
@@ -163,9 +204,15 @@ fn is_self_or_self_ref(span: Span, ty: &rustc_hir::Ty) -> Result<bool, VirErr> {
         (Some((x, _)), Some((typ, mode))) => (x, typ, mode),
         _ => panic!("internal error: ret_typ"),
     };
-    let ret = spanned_new(sig.span, ParamX { name: ret_name, typ: ret_typ, mode: ret_mode });
+    let ret_param = ParamX { name: ret_name, typ: ret_typ, mode: ret_mode, is_mut: false };
+    let ret = spanned_new(sig.span, ret_param);
+    let body = match (&kind, vattrs.do_verify) {
+        (FunctionKind::TraitMethodDecl { .. }, _) | (_, false) => None,
+        _ => Some(vir_body),
+    };
     let func = FunctionX {
         path,
+        kind,
         visibility,
         mode,
         fuel,
@@ -178,7 +225,12 @@ fn is_self_or_self_ref(span: Span, ty: &rustc_hir::Ty) -> Result<bool, VirErr> {
         custom_req_err: vattrs.custom_req_err,
         hidden: Arc::new(header.hidden),
         is_abstract: vattrs.is_abstract,
-        body: if vattrs.do_verify { Some(vir_body) } else { None },
+        bit_vector: vattrs.bit_vector,
+        nonlinear: vattrs.nonlinear,
+        broadcast: vattrs.broadcast,
+        rlimit: vattrs.rlimit,
+        smt_options: Arc::new(vattrs.smt_options),
+        body,
     };
     let function = spanned_new(sig.span, func);
     vir.functions.push(function);
@@ -197,15 +249,17 @@ pub(crate) fn check_foreign_item_fn<'tcx>(
     generics: &'tcx Generics,
 ) -> Result<(), VirErr> {
     let mode = get_mode(Mode::Exec, attrs);
+    check_fn_sig_mut_refs(ctxt.tcx, id, span)?;
     let ret_typ_mode = check_fn_decl(ctxt.tcx, decl, mode)?;
-    let typ_params = check_generics(generics)?;
+    let typ_params = check_generics(ctxt.tcx, generics)?;
     let fuel = get_fuel(attrs);
     let mut vir_params: Vec<vir::ast::Param> = Vec::new();
     for (param, input) in idents.iter().zip(decl.inputs.iter()) {
         let name = Arc::new(ident_to_var(param));
+        let (input, is_mut) = strip_mut_ref(input);
         let typ = ty_to_vir(ctxt.tcx, input);
         // REVIEW: the parameters don't have attributes, so we use the overall mode
-        let vir_param = spanned_new(param.span, ParamX { name, typ, mode });
+        let vir_param = spanned_new(param.span, ParamX { name, typ, mode, is_mut });
         vir_params.push(vir_param);
     }
     let path = def_id_to_vir_path(ctxt.tcx, id);
@@ -214,11 +268,16 @@ pub(crate) fn check_foreign_item_fn<'tcx>(
         None => (Arc::new(TypX::Tuple(Arc::new(vec![]))), mode),
         Some((typ, mode)) => (typ, mode),
     };
-    let ret_param =
-        ParamX { name: Arc::new(RETURN_VALUE.to_string()), typ: ret_typ, mode: ret_mode };
+    let ret_param = ParamX {
+        name: Arc::new(RETURN_VALUE.to_string()),
+        typ: ret_typ,
+        mode: ret_mode,
+        is_mut: false,
+    };
     let ret = spanned_new(span, ret_param);
     let func = FunctionX {
         path,
+        kind: FunctionKind::Static,
         visibility,
         fuel,
         mode,
@@ -227,10 +286,15 @@ pub(crate) fn check_foreign_item_fn<'tcx>(
         ret,
         require: Arc::new(vec![]),
         ensure: Arc::new(vec![]),
-        decrease: None,
+        decrease: Arc::new(vec![]),
         custom_req_err: None,
         hidden: Arc::new(vec![]),
         is_abstract: false,
+        bit_vector: false,
+        nonlinear: false,
+        broadcast: false,
+        rlimit: None,
+        smt_options: Arc::new(vec![]),
         body: None,
     };
     let function = spanned_new(span, func);
@@ -1,17 +1,22 @@
+use crate::cache::QueryCache;
 use crate::config::Args;
 use crate::context::{Context, ErasureInfo};
 use crate::model::Model;
 use crate::unsupported;
 use crate::util::from_raw_span;
-use air::ast::{Command, CommandX, SpanOption};
+use air::ast::{Command, CommandX, Commands, SpanOption};
 use air::context::ValidityResult;
 use rustc_interface::interface::Compiler;
 use rustc_middle::ty::TyCtxt;
 use rustc_span::source_map::SourceMap;
 use rustc_span::{CharPos, FileName, MultiSpan, Span};
+use std::collections::HashMap;
 use std::fs::File;
 use std::io::Write;
-use vir::ast::{Krate, VirErr, VirErrX, Visibility};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::mpsc::channel;
+use std::sync::{Arc, Mutex};
+use vir::ast::{Function, Krate, VirErr, VirErrX, Visibility};
 use vir::ast_util::{is_visible_to, path_as_rust_name};
 use vir::def::SnapPos;
 use vir::model::Model as VModel;
@@ -19,6 +24,7 @@
 pub struct Verifier {
     pub encountered_vir_error: bool,
     pub count_verified: u64,
+    pub count_cached: u64,
     // Two error slots that can be filled in if needed.  TODO: Convert to list/vec
     pub errors: Vec<(Option<ErrorSpan>, Option<ErrorSpan>)>,
     args: Args,
@@ -104,11 +110,143 @@ fn report_chosen_triggers(
     compiler.session().parse_sess.span_diagnostic.span_note_without_error(span, &msg);
 }
 
+fn print_verifying_module(module_name: &String) {
+    if module_name.len() == 0 {
+        println!("Verifying root module");
+    } else {
+        println!("Verifying module {}", module_name);
+    }
+}
+
+/// AIR commands generated for one function (or other section) of a module
+struct CommandBatch {
+    comment: String,
+    commands: Commands,
+    /// For queries, the function whose code is checked.
+    /// Commands other than queries are expected to always succeed.
+    query_function: Option<Function>,
+    snap_map: Vec<(air::ast::Span, SnapPos)>,
+}
+
+/// The results of each command in a batch, or None for queries skipped because they were cached.
+/// With --multiple-errors, a failed query may have more than one Invalid result.
+type BatchResults = Vec<Option<Vec<ValidityResult>>>;
+
+impl CommandBatch {
+    fn new(comment: String, commands: Commands) -> Self {
+        CommandBatch { comment, commands, query_function: None, snap_map: vec![] }
+    }
+
+    fn queries(function: &Function, comment: String, commands: Commands) -> Self {
+        CommandBatch {
+            query_function: Some(function.clone()),
+            ..CommandBatch::new(comment, commands)
+        }
+    }
+}
+
+/// The --log-air, --log-air-final, and --log-smt logs.
+/// Worker threads log each module into buffers, which are appended to the files in module order.
+struct AirLogs<W> {
+    air_initial: Option<Arc<Mutex<W>>>,
+    air_final: Option<Arc<Mutex<W>>>,
+    smt: Option<Arc<Mutex<W>>>,
+}
+
+impl<W> Clone for AirLogs<W> {
+    fn clone(&self) -> Self {
+        AirLogs {
+            air_initial: self.air_initial.clone(),
+            air_final: self.air_final.clone(),
+            smt: self.smt.clone(),
+        }
+    }
+}
+
+impl<W: std::io::Write + 'static> AirLogs<W> {
+    fn attach(&self, air_context: &mut air::context::Context) {
+        if let Some(output) = &self.air_initial {
+            air_context
+                .set_air_initial_log(Box::new(SharedOutputBuffer { output: output.clone() }));
+        }
+        if let Some(output) = &self.air_final {
+            air_context.set_air_final_log(Box::new(SharedOutputBuffer { output: output.clone() }));
+        }
+        if let Some(output) = &self.smt {
+            air_context.set_smt_log(Box::new(SharedOutputBuffer { output: output.clone() }));
+        }
+    }
+
+    fn new_buffers(&self) -> AirLogs<Vec<u8>> {
+        let new_buffer =
+            |log: &Option<Arc<Mutex<W>>>| log.as_ref().map(|_| Arc::new(Mutex::new(Vec::new())));
+        AirLogs {
+            air_initial: new_buffer(&self.air_initial),
+            air_final: new_buffer(&self.air_final),
+            smt: new_buffer(&self.smt),
+        }
+    }
+}
+
+impl AirLogs<File> {
+    fn create(args: &Args) -> Self {
+        let create = |filename: &Option<String>| {
+            filename.as_ref().map(|filename| {
+                let file =
+                    File::create(filename).expect(&format!("could not open file {}", filename));
+                Arc::new(Mutex::new(file))
+            })
+        };
+        AirLogs {
+            air_initial: create(&args.log_air_initial),
+            air_final: create(&args.log_air_final),
+            smt: create(&args.log_smt),
+        }
+    }
+
+    fn append(&self, buffers: &AirLogs<Vec<u8>>) {
+        let append = |file: &Option<Arc<Mutex<File>>>, buffer: &Option<Arc<Mutex<Vec<u8>>>>| {
+            if let (Some(file), Some(buffer)) = (file, buffer) {
+                let buffer = buffer.lock().expect("internal error: cannot lock log buffer");
+                let mut file = file.lock().expect("internal error: cannot lock log file");
+                file.write_all(&buffer).expect("error writing to log file");
+            }
+        };
+        append(&self.air_initial, &buffers.air_initial);
+        append(&self.air_final, &buffers.air_final);
+        append(&self.smt, &buffers.smt);
+    }
+}
+
+fn new_air_context(
+    debug: bool,
+    rlimit: u32,
+    multiple_errors: u32,
+    logs: Option<&AirLogs<File>>,
+    cache: Option<&QueryCache>,
+) -> air::context::Context {
+    let mut air_context = air::context::Context::new(air::smt_manager::SmtManager::new());
+    air_context.set_debug(debug);
+    air_context.set_multiple_errors(multiple_errors > 1);
+    if cache.is_some() {
+        air_context.enable_fingerprints();
+    }
+    if let Some(logs) = logs {
+        logs.attach(&mut air_context);
+    }
+
+    // air_recommended_options causes AIR to apply a preset collection of Z3 options
+    air_context.set_z3_param("air_recommended_options", "true");
+    air_context.set_rlimit(rlimit * 1000000);
+    air_context
+}
+
 impl Verifier {
     pub fn new(args: Args) -> Verifier {
         Verifier {
             encountered_vir_error: false,
             count_verified: 0,
+            count_cached: 0,
             errors: Vec::new(),
             args: args,
             test_capture_output: None,
@@ -134,6 +272,7 @@ fn check_internal_result(result: ValidityResult) {
     fn check_result_validity(
         &mut self,
         compiler: &Compiler,
+        function: &Function,
         snap_map: &Vec<(air::ast::Span, SnapPos)>,
         command: &Command,
         result: ValidityResult,
@@ -166,54 +305,130 @@ fn check_result_validity(
                     println!("Build Rust model: {}", model);
                 }
             }
+            ValidityResult::Canceled(reason) => {
+                let name = path_as_rust_name(&function.x.path);
+                let rlimit = function.x.rlimit.unwrap_or(self.args.rlimit);
+                let msg = if rlimit > 0 {
+                    format!("function {} timed out (rlimit {})", name, rlimit)
+                } else {
+                    format!("function {} timed out ({})", name, reason)
+                };
+                let span = air::ast::Span { description: Some(msg), ..function.span.clone() };
+                report_verify_error(compiler, &Arc::new(Some(span.clone())), &Arc::new(None));
+                self.errors.push((
+                    Some(ErrorSpan::new_from_air_span(compiler.session().source_map(), &span)),
+                    None,
+                ));
+            }
         }
     }
 
     fn run_commands(
         air_context: &mut air::context::Context,
-        commands: &Vec<Command>,
-        comment: &str,
-    ) {
-        if commands.len() > 0 {
+        cache: Option<&QueryCache>,
+        multiple_errors: u32,
+        batch: &CommandBatch,
+    ) -> BatchResults {
+        if batch.commands.len() > 0 {
             air_context.blank_line();
-            air_context.comment(comment);
+            air_context.comment(&batch.comment);
         }
-        for command in commands.iter() {
-            Self::check_internal_result(air_context.command(&command));
+        let mut results = Vec::new();
+        for command in batch.commands.iter() {
+            let cached = match (&**command, cache) {
+                (CommandX::CheckValid(query), Some(cache)) => {
+                    air_context.query_fingerprint(query).map(|fingerprint| (cache, fingerprint))
+                }
+                _ => None,
+            };
+            if let Some((cache, fingerprint)) = &cached {
+                if cache.contains(fingerprint) {
+                    air_context.comment("skipped: query found in cache");
+                    results.push(None);
+                    continue;
+                }
+            }
+            let mut command_results = vec![air_context.command(&command)];
+            if let (Some((cache, fingerprint)), ValidityResult::Valid) =
+                (&cached, &command_results[0])
+            {
+                cache.insert(fingerprint);
+            }
+            // Look for more failing assertions in the same query, up to --multiple-errors
+            while let Some(ValidityResult::Invalid(..)) = command_results.last() {
+                if command_results.len() >= multiple_errors as usize {
+                    break;
+                }
+                match air_context.check_valid_again() {
+                    result @ ValidityResult::Invalid(..) => command_results.push(result),
+                    _ => break,
+                }
+            }
+            results.push(Some(command_results));
         }
+        results
     }
 
-    fn run_commands_queries(
-        &mut self,
-        compiler: &Compiler,
-        air_context: &mut air::context::Context,
-        commands: &Vec<Command>,
-        snap_map: &Vec<(air::ast::Span, SnapPos)>,
-        comment: &str,
-    ) {
-        if commands.len() > 0 {
-            air_context.blank_line();
-            air_context.comment(comment);
+    fn check_results(&mut self, compiler: &Compiler, batch: &CommandBatch, results: BatchResults) {
+        for (command, command_results) in batch.commands.iter().zip(results) {
+            let command_results = match command_results {
+                None => {
+                    self.count_cached += 1;
+                    continue;
+                }
+                Some(command_results) => command_results,
+            };
+            for result in command_results {
+                match &batch.query_function {
+                    Some(function) => self.check_result_validity(
+                        compiler,
+                        function,
+                        &batch.snap_map,
+                        command,
+                        result,
+                    ),
+                    None => Self::check_internal_result(result),
+                }
+            }
         }
-        for command in commands.iter() {
-            self.check_result_validity(compiler, snap_map, &command, air_context.command(&command));
+    }
+
+    // Set the function's #[verifier(rlimit(...))] and #[verifier(smt_option(...))] options
+    // for its queries only, and restore the defaults afterward
+    fn function_options(&self, function: &Function, commands: Commands) -> Commands {
+        let x = &function.x;
+        if commands.len() == 0 || (x.rlimit.is_none() && x.smt_options.len() == 0) {
+            return commands;
+        }
+        let set_option = |option: &str, value: &str| {
+            Arc::new(CommandX::SetOption(Arc::new(option.to_string()), Arc::new(value.to_string())))
+        };
+        let mut options: Vec<Command> = Vec::new();
+        let mut defaults: Vec<Command> = Vec::new();
+        if let Some(rlimit) = x.rlimit {
+            options.push(set_option("rlimit", &(rlimit * 1000000).to_string()));
+            defaults.push(set_option("rlimit", &(self.args.rlimit * 1000000).to_string()));
         }
+        for (option, value) in x.smt_options.iter() {
+            let (_, _, default) = crate::rust_to_vir_base::SMT_OPTIONS
+                .iter()
+                .find(|(_, o, _)| o == &**option)
+                .expect("internal error: unknown smt option");
+            options.push(set_option(option, value));
+            defaults.push(set_option(option, default));
+        }
+        Arc::new(options.into_iter().chain(commands.iter().cloned()).chain(defaults).collect())
     }
 
-    // Verify a single module
-    fn verify_module(
-        &mut self,
-        compiler: &Compiler,
+    // Generate the AIR commands for a single module
+    fn module_commands(
+        &self,
         krate: &Krate,
-        air_context: &mut air::context::Context,
         ctx: &mut vir::context::Ctx,
-    ) -> Result<(), VirErr> {
+    ) -> Result<Vec<CommandBatch>, VirErr> {
         let module = &ctx.module();
-        air_context.blank_line();
-        air_context.comment("Fuel");
-        for command in ctx.fuel().iter() {
-            Self::check_internal_result(air_context.command(&command));
-        }
+        let mut batches: Vec<CommandBatch> = Vec::new();
+        batches.push(CommandBatch::new("Fuel".to_string(), ctx.fuel()));
 
         let datatype_commands = vir::datatype_to_air::datatypes_to_air(
             ctx,
@@ -224,7 +439,7 @@ fn verify_module(
                 .filter(|d| is_visible_to(&d.x.visibility, module))
                 .collect(),
         );
-        Self::run_commands(air_context, &datatype_commands, &("Datatypes".to_string()));
+        batches.push(CommandBatch::new("Datatypes".to_string(), datatype_commands));
 
         // Declare the function symbols
         for function in &krate.functions {
@@ -232,11 +447,8 @@ fn verify_module(
                 continue;
             }
             let commands = vir::func_to_air::func_name_to_air(ctx, &function)?;
-            Self::run_commands(
-                air_context,
-                &commands,
-                &("Function-Decl ".to_string() + &path_as_rust_name(&function.x.path)),
-            );
+            let comment = "Function-Decl ".to_string() + &path_as_rust_name(&function.x.path);
+            batches.push(CommandBatch::new(comment, commands));
         }
 
         // Declare consequence axioms for spec functions, and function signatures for proof/exec functions
@@ -249,23 +461,17 @@ fn verify_module(
             }
             let (decl_commands, check_commands) =
                 vir::func_to_air::func_decl_to_air(ctx, &function)?;
-            Self::run_commands(
-                air_context,
-                &decl_commands,
-                &("Function-Axioms ".to_string() + &path_as_rust_name(&function.x.path)),
-            );
+            let comment = "Function-Axioms ".to_string() + &path_as_rust_name(&function.x.path);
+            batches.push(CommandBatch::new(comment, decl_commands));
 
             // Check termination
             if Some(module.clone()) != function.x.visibility.owning_module {
                 continue;
             }
-            self.run_commands_queries(
-                compiler,
-                air_context,
-                &check_commands,
-                &vec![],
-                &("Function-Termination ".to_string() + &path_as_rust_name(&function.x.path)),
-            );
+            let comment =
+                "Function-Termination ".to_string() + &path_as_rust_name(&function.x.path);
+            let check_commands = self.function_options(function, check_commands);
+            batches.push(CommandBatch::queries(function, comment, check_commands));
         }
 
         // Create queries to check the validity of proof/exec function bodies
@@ -274,60 +480,154 @@ fn verify_module(
                 continue;
             }
             let (commands, snap_map) = vir::func_to_air::func_def_to_air(ctx, &function)?;
-            self.run_commands_queries(
-                compiler,
-                air_context,
-                &commands,
-                &snap_map,
-                &("Function-Def ".to_string() + &path_as_rust_name(&function.x.path)),
-            );
+            let commands = self.function_options(function, commands);
+            let comment = "Function-Def ".to_string() + &path_as_rust_name(&function.x.path);
+            batches.push(CommandBatch {
+                snap_map,
+                ..CommandBatch::queries(function, comment, commands)
+            });
         }
 
-        Ok(())
+        Ok(batches)
     }
 
-    // Verify one or more modules in a crate
-    fn verify_crate(
+    // Verify a single module
+    fn verify_module(
         &mut self,
         compiler: &Compiler,
         krate: &Krate,
-        no_span: Span,
+        air_context: &mut air::context::Context,
+        cache: Option<&QueryCache>,
+        ctx: &mut vir::context::Ctx,
     ) -> Result<(), VirErr> {
-        let mut air_context = air::context::Context::new(air::smt_manager::SmtManager::new());
-        air_context.set_debug(self.args.debug);
-
-        if let Some(filename) = &self.args.log_air_initial {
-            let file = File::create(filename).expect(&format!("could not open file {}", filename));
-            air_context.set_air_initial_log(Box::new(file));
+        for batch in self.module_commands(krate, ctx)? {
+            let results = Self::run_commands(air_context, cache, self.args.multiple_errors, &batch);
+            self.check_results(compiler, &batch, results);
         }
-        if let Some(filename) = &self.args.log_air_final {
-            let file = File::create(filename).expect(&format!("could not open file {}", filename));
-            air_context.set_air_final_log(Box::new(file));
+        Ok(())
+    }
+
+    // Verify the modules on num_threads worker threads, each with its own AIR context and
+    // SMT process.  Results (and logs) are reported in module order, as in sequential mode.
+    fn verify_modules_parallel(
+        &mut self,
+        compiler: &Compiler,
+        prelude: Commands,
+        modules: Vec<(String, Vec<CommandBatch>)>,
+        log_files: &AirLogs<File>,
+        cache: Option<Arc<QueryCache>>,
+    ) {
+        let modules = Arc::new(modules);
+        let next_module = Arc::new(AtomicUsize::new(0));
+        let (sender, receiver) = channel();
+        for _ in 0..std::cmp::min(self.args.num_threads, modules.len()) {
+            let modules = modules.clone();
+            let prelude = prelude.clone();
+            let next_module = next_module.clone();
+            let sender = sender.clone();
+            let (debug, rlimit) = (self.args.debug, self.args.rlimit);
+            let multiple_errors = self.args.multiple_errors;
+            let log_files = log_files.clone();
+            let cache = cache.clone();
+            std::thread::spawn(move || {
+                let cache = cache.as_deref();
+                let mut air_context = new_air_context(debug, rlimit, multiple_errors, None, cache);
+                for command in prelude.iter() {
+                    Self::check_internal_result(air_context.command(&command));
+                }
+                loop {
+                    let i = next_module.fetch_add(1, Ordering::SeqCst);
+                    if i >= modules.len() {
+                        break;
+                    }
+                    let (module_name, batches) = &modules[i];
+                    let log_buffers = log_files.new_buffers();
+                    log_buffers.attach(&mut air_context);
+                    air_context.blank_line();
+                    air_context.comment(&("MODULE '".to_string() + module_name + "'"));
+                    air_context.push();
+                    let results: Vec<BatchResults> = batches
+                        .iter()
+                        .map(|batch| {
+                            Self::run_commands(&mut air_context, cache, multiple_errors, batch)
+                        })
+                        .collect();
+                    air_context.pop();
+                    if sender.send((i, results, log_buffers)).is_err() {
+                        break;
+                    }
+                }
+            });
         }
-        if let Some(filename) = &self.args.log_smt {
-            let file = File::create(filename).expect(&format!("could not open file {}", filename));
-            air_context.set_smt_log(Box::new(file));
+        drop(sender);
+
+        let mut finished: HashMap<usize, (Vec<BatchResults>, AirLogs<Vec<u8>>)> = HashMap::new();
+        for i in 0..modules.len() {
+            while !finished.contains_key(&i) {
+                let (j, results, log_buffers) =
+                    receiver.recv().expect("internal error: verification thread failed");
+                finished.insert(j, (results, log_buffers));
+            }
+            let (results, log_buffers) = finished.remove(&i).expect("finished module");
+            let (module_name, batches) = &modules[i];
+            print_verifying_module(module_name);
+            log_files.append(&log_buffers);
+            for (batch, results) in batches.iter().zip(results) {
+                self.check_results(compiler, batch, results);
+            }
         }
+    }
 
-        // air_recommended_options causes AIR to apply a preset collection of Z3 options
-        air_context.set_z3_param("air_recommended_options", "true");
-        air_context.set_rlimit(self.args.rlimit * 1000000);
+    // Verify one or more modules in a crate
+    fn verify_crate(
+        &mut self,
+        compiler: &Compiler,
+        krate: &Krate,
+        no_span: Span,
+    ) -> Result<(), VirErr> {
+        let log_files = AirLogs::create(&self.args);
+        let cache = self.args.cache_dir.as_ref().map(|dir| Arc::new(QueryCache::new(dir)));
+        let mut air_context = new_air_context(
+            self.args.debug,
+            self.args.rlimit,
+            self.args.multiple_errors,
+            Some(&log_files),
+            cache.as_deref(),
+        );
 
         let air_no_span = air::ast::Span {
             description: None,
             raw_span: crate::util::to_raw_span(no_span),
             as_string: "no location".to_string(),
         };
-        let mut global_ctx = vir::context::GlobalCtx::new(&krate, air_no_span);
+
+        // Unless overridden, usize/isize have the rustc target's pointer width
+        let arch_word_bits = match self.args.arch_word_bits {
+            Some(bits) => bits,
+            None => compiler.session().target.pointer_width,
+        };
+        if arch_word_bits != 32 && arch_word_bits != 64 {
+            return vir::ast_util::err_string(
+                &air_no_span,
+                format!(
+                    "unsupported target pointer width {}; use --arch-word-bits",
+                    arch_word_bits
+                ),
+            );
+        }
+
+        let mut global_ctx = vir::context::GlobalCtx::new(&krate, air_no_span, arch_word_bits);
         let krate = vir::ast_simplify::simplify_krate(&mut global_ctx, &krate)?;
 
+        let prelude = vir::context::Ctx::prelude(arch_word_bits);
         air_context.blank_line();
         air_context.comment("Prelude");
-        for command in vir::context::Ctx::prelude().iter() {
+        for command in prelude.iter() {
             Self::check_internal_result(air_context.command(&command));
         }
 
         let verify_entire_crate = !self.args.verify_root && self.args.verify_module.is_none();
+        let mut parallel_modules: Vec<(String, Vec<CommandBatch>)> = Vec::new();
         for module in &krate.module_ids {
             let module_name =
                 module.segments.iter().map(|s| s.to_string()).collect::<Vec<_>>().join("::");
@@ -335,21 +635,28 @@ fn verify_crate(
                 if !verify_entire_crate && !self.args.verify_root {
                     continue;
                 }
-                println!("Verifying root module");
             } else {
                 if !verify_entire_crate && self.args.verify_module != Some(module_name.clone()) {
                     continue;
                 }
-                println!("Verifying module {}", &module_name);
             }
-            air_context.blank_line();
-            air_context.comment(&("MODULE '".to_string() + &module_name + "'"));
-            air_context.push();
             let mut ctx =
                 vir::context::Ctx::new(&krate, global_ctx, module.clone(), self.args.debug)?;
-            self.verify_module(compiler, &krate, &mut air_context, &mut ctx)?;
+            if self.args.num_threads > 1 {
+                // Generate the commands now, and run them on worker threads below
+                parallel_modules.push((module_name, self.module_commands(&krate, &mut ctx)?));
+            } else {
+                print_verifying_module(&module_name);
+                air_context.blank_line();
+                air_context.comment(&("MODULE '".to_string() + &module_name + "'"));
+                air_context.push();
+                self.verify_module(compiler, &krate, &mut air_context, cache.as_deref(), &mut ctx)?;
+                air_context.pop();
+            }
             global_ctx = ctx.free();
-            air_context.pop();
+        }
+        if parallel_modules.len() > 0 {
+            self.verify_modules_parallel(compiler, prelude, parallel_modules, &log_files, cache);
         }
 
         if let Some(filename) = &self.args.log_triggers {
@@ -449,16 +756,16 @@ fn run<'tcx>(&mut self, compiler: &Compiler, tcx: TyCtxt<'tcx>) -> Result<bool,
     }
 }
 
-struct DiagnosticOutputBuffer {
-    output: std::sync::Arc<std::sync::Mutex<Vec<u8>>>,
+struct SharedOutputBuffer<W> {
+    output: Arc<Mutex<W>>,
 }
 
-impl std::io::Write for DiagnosticOutputBuffer {
+impl<W: std::io::Write> std::io::Write for SharedOutputBuffer<W> {
     fn write(&mut self, buf: &[u8]) -> Result<usize, std::io::Error> {
-        self.output.lock().expect("internal error: cannot lock captured output").write(buf)
+        self.output.lock().expect("internal error: cannot lock shared output").write(buf)
     }
     fn flush(&mut self) -> Result<(), std::io::Error> {
-        self.output.lock().expect("internal error: cannot lock captured output").flush()
+        self.output.lock().expect("internal error: cannot lock shared output").flush()
     }
 }
 
@@ -466,7 +773,7 @@ impl rustc_driver::Callbacks for Verifier {
     fn config(&mut self, config: &mut rustc_interface::interface::Config) {
         if let Some(target) = &self.test_capture_output {
             config.diagnostic_output =
-                rustc_session::DiagnosticOutput::Raw(Box::new(DiagnosticOutputBuffer {
+                rustc_session::DiagnosticOutput::Raw(Box::new(SharedOutputBuffer {
                     output: target.clone(),
                 }));
         }
@@ -0,0 +1,98 @@
+#![feature(rustc_private)]
+#[macro_use]
+mod common;
+use common::*;
+
+test_verify_with_pervasive! {
+    #[test] test_slice_index code! {
+        fn get(a: &[u64], i: usize) -> u64 {
+            requires(i < a.len());
+            ensures(|r: u64| r == view(a).index(i));
+            a[i]
+        }
+    } => Ok(())
+}
+
+test_verify_with_pervasive! {
+    #[test] test_slice_index_fails code! {
+        fn get(a: &[u64], i: usize) -> u64 {
+            requires(i <= a.len());
+            a[i] // FAILS
+        }
+    } => Err(err) => assert_one_fails(err)
+}
+
+test_verify_with_pervasive! {
+    #[test] test_slice_update code! {
+        fn set(a: &mut [u64], i: usize, v: u64) {
+            requires(i < old(a).len());
+            ensures([
+                view(a).len() == view(old(a)).len(),
+                view(a).index(i) == v,
+            ]);
+            a[i] = v;
+        }
+    } => Ok(())
+}
+
+test_verify_with_pervasive! {
+    #[test] test_slice_loop code! {
+        fn zero(a: &mut [u64]) {
+            ensures([
+                view(a).len() == view(old(a)).len(),
+                forall(|j: int| imply(0 <= j && j < view(a).len(), view(a).index(j) == 0)),
+            ]);
+            let mut i: usize = 0;
+            while i < a.len() {
+                invariant([
+                    i <= a.len(),
+                    view(a).len() == view(old(a)).len(),
+                    forall(|j: int| imply(0 <= j && j < i, view(a).index(j) == 0)),
+                ]);
+                a[i] = 0;
+                i = i + 1;
+            }
+        }
+    } => Ok(())
+}
+
+test_verify_with_pervasive! {
+    #[test] test_slice_sorted code! {
+        fn get_sorted(a: &[u64], i: usize, j: usize) {
+            requires([
+                forall(|k: int, l: int| imply(0 <= k && k <= l && l < view(a).len(),
+                    view(a).index(k) <= view(a).index(l))),
+                i <= j,
+                j < a.len(),
+            ]);
+            assert(a[i] <= a[j]);
+        }
+    } => Ok(())
+}
+
+test_verify_with_pervasive! {
+    #[test] test_array_literal code! {
+        fn first(a: [u8; 4]) -> u8 {
+            ensures(|r: u8| r == a[0]);
+            a[0]
+        }
+
+        fn test() {
+            let a = [1u8, 2, 3, 4];
+            let x = first(a);
+            assert(x == 1);
+            assert(a.len() == 4);
+            let b = a[3];
+            assert(b == 4);
+        }
+    } => Ok(())
+}
+
+test_verify_with_pervasive! {
+    #[test] test_array_update_fails code! {
+        fn test(i: usize) {
+            let mut a = [0u64, 1];
+            a[i] = 5; // FAILS
+        }
+    } => Err(err) => assert_one_fails(err)
+}
@@ -0,0 +1,112 @@
+#![feature(rustc_private)]
+#[macro_use]
+mod common;
+use common::*;
+
+const OPAQUE: &str = code_str! {
+    #[spec]
+    #[opaque]
+    fn g(i: int) -> bool {
+        i > 0
+    }
+
+    #[proof]
+    fn lemma_g(i: int) {
+        requires(i >= 1);
+        ensures(g(i));
+        reveal(g);
+    }
+};
+
+test_verify_with_pervasive! {
+    #[test] test_assert_by OPAQUE.to_string() + code_str! {
+        fn test1() {
+            assert_by(g(5), {
+                reveal(g);
+            });
+            assert(g(5));
+        }
+
+        fn test2(n: int) {
+            requires(n > 10);
+            assert_forall_by(|i: int| {
+                requires(i > 10);
+                ensures(g(i - 10));
+                lemma_g(i - 10);
+            });
+            assert(g(n - 10));
+        }
+    } => Ok(())
+}
+
+test_verify_with_pervasive! {
+    #[test] test_assert_by_no_context_fails OPAQUE.to_string() + code_str! {
+        fn test1(n: int) {
+            requires(n > 10);
+            // the proof is checked in a query of its own, without requires(n > 10)
+            assert_by(g(n - 10), lemma_g(n - 10)); // FAILS
+        }
+    } => Err(err) => assert_one_fails(err)
+}
+
+test_verify_with_pervasive! {
+    #[test] test_assert_by_local_fails OPAQUE.to_string() + code_str! {
+        fn test1() {
+            assert_by(g(5), {
+                reveal(g);
+                assert(g(6));
+            });
+            assert(g(5));
+            assert(g(6)); // FAILS
+        }
+    } => Err(err) => assert_one_fails(err)
+}
+
+test_verify_with_pervasive! {
+    #[test] test_assert_by_fails OPAQUE.to_string() + code_str! {
+        fn test1() {
+            assert_by(g(0), { // FAILS
+                reveal(g);
+            });
+        }
+    } => Err(err) => assert_one_fails(err)
+}
+
+test_verify_with_pervasive! {
+    #[test] test_assert_forall_by OPAQUE.to_string() + code_str! {
+        fn test1() {
+            assert_forall_by(|i: int| {
+                requires(i >= 1);
+                ensures(g(i));
+                lemma_g(i);
+            });
+            assert(g(7));
+            assert(forall(|i: int| imply(i >= 1, g(i))));
+        }
+    } => Ok(())
+}
+
+test_verify_with_pervasive! {
+    #[test] test_assert_forall_by_fails OPAQUE.to_string() + code_str! {
+        fn test1() {
+            assert_forall_by(|i: int| {
+                requires(i >= 0);
+                ensures(g(i)); // FAILS
+                reveal(g);
+            });
+        }
+    } => Err(err) => assert_one_fails(err)
+}
+
+test_verify_with_pervasive! {
+    #[test] test_assert_forall_by_requires_fails OPAQUE.to_string() + code_str! {
+        fn test1() {
+            assert_forall_by(|i: int| {
+                requires(i >= 1);
+                ensures(g(i));
+                lemma_g(i);
+            });
+            assert(g(0)); // FAILS
+        }
+    } => Err(err) => assert_one_fails(err)
+}
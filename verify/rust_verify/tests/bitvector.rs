@@ -0,0 +1,93 @@
+#![feature(rustc_private)]
+#[macro_use]
+mod common;
+use common::*;
+
+test_verify_with_pervasive! {
+    #[test] test_assert_bit_vector code! {
+        #[proof]
+        fn test1(x: u32, y: u32) {
+            assert_bit_vector(x & y == y & x);
+            assert(x & y == y & x);
+            assert_bit_vector(x & 0xff <= 0xff);
+            assert_bit_vector(x ^ x == 0);
+            assert_bit_vector(x | 0 == x);
+            assert_bit_vector(!(!x) == x);
+            assert_bit_vector(x + y == y + x);
+            assert_bit_vector((x << 1u32) == x + x);
+            assert_bit_vector(x >> 31u32 <= 1);
+        }
+
+        fn test2(a: u8) -> u8 {
+            ensures(|r: u8| r <= 15);
+            assert_bit_vector(a & 15 <= 15);
+            a & 15
+        }
+    } => Ok(())
+}
+
+test_verify_with_pervasive! {
+    #[test] test_assert_bit_vector_fails code! {
+        #[proof]
+        fn test1(x: u32) {
+            assert_bit_vector(x + 1 > x); // FAILS
+        }
+    } => Err(err) => assert_one_fails(err)
+}
+
+test_verify_with_pervasive! {
+    #[test] test_bitwise_uninterpreted_fails code! {
+        #[proof]
+        fn test1(x: u32, y: u32) {
+            assert(x & y == y & x); // FAILS
+        }
+    } => Err(err) => assert_one_fails(err)
+}
+
+test_verify_with_pervasive! {
+    #[test] test_bit_vector_function code! {
+        #[proof]
+        #[verifier(bit_vector)]
+        fn lemma_and_mask(x: u32, y: u32) {
+            requires(y <= 0xff);
+            ensures([
+                x & y == y & x,
+                x & y <= 0xff,
+            ]);
+        }
+
+        #[proof]
+        fn test1(x: u32) {
+            lemma_and_mask(x, 0x0f);
+            assert(x & 0x0f <= 0xff);
+        }
+    } => Ok(())
+}
+
+test_verify_with_pervasive! {
+    #[test] test_bit_vector_function_fails code! {
+        #[proof]
+        #[verifier(bit_vector)]
+        fn lemma_or(x: u32, y: u32) {
+            ensures(x | y <= x); // FAILS
+        }
+    } => Err(err) => assert_one_fails(err)
+}
+
+test_verify_with_pervasive! {
+    #[test] test_bit_vector_signed_unsupported code! {
+        #[proof]
+        fn test1(x: i32) {
+            assert_bit_vector(x & x == x);
+        }
+    } => Err(_)
+}
+
+test_verify_with_pervasive! {
+    #[test] test_bit_vector_cast_unsupported code! {
+        #[proof]
+        fn test1(x: u32) {
+            assert_bit_vector((x as u8) <= 0xff);
+        }
+    } => Err(_)
+}
@@ -0,0 +1,131 @@
+#![feature(rustc_private)]
+#[macro_use]
+mod common;
+use common::*;
+
+const DOUBLE: &str = code_str! {
+    #[spec]
+    #[opaque]
+    fn double(i: int) -> int {
+        i + i
+    }
+};
+
+test_verify_with_pervasive! {
+    #[test] test_broadcast DOUBLE.to_string() + code_str! {
+        #[proof]
+        #[verifier(broadcast)]
+        fn lemma_double_gt(i: int) {
+            requires(i > 0);
+            ensures(double(i) > i);
+            reveal(double);
+        }
+
+        fn test1(x: int) {
+            requires(x > 5);
+            assert(double(x) > x);
+        }
+
+        #[proof]
+        fn test2(x: int, y: int) {
+            requires([x > 0, y > x]);
+            assert(double(y) > x);
+        }
+    } => Ok(())
+}
+
+/* REVIEW: manual triggers need #![feature(stmt_expr_attributes)], which doesn't work here
+   (see quantifiers.rs), so broadcast lemmas in these tests only use automatic triggers
+test_verify_with_pervasive! {
+    #[test] test_broadcast_trigger DOUBLE.to_string() + code_str! {
+        #[proof]
+        #[verifier(broadcast)]
+        fn lemma_double_def(i: int) {
+            ensures(#[trigger] double(i) == i + i);
+            reveal(double);
+        }
+
+        fn test1(x: int) {
+            assert(double(x) - x == x);
+        }
+    } => Ok(())
+}
+*/
+
+test_verify_with_pervasive! {
+    #[test] test_broadcast_generic code! {
+        #[spec]
+        #[opaque]
+        fn ident<A>(a: A) -> A {
+            a
+        }
+
+        #[proof]
+        #[verifier(broadcast)]
+        fn lemma_ident<A>(a: A) {
+            ensures(equal(ident(a), a));
+            reveal(ident);
+        }
+
+        fn test1(x: u64, b: bool) {
+            assert(ident(x) == x);
+            assert(ident(b) == b);
+        }
+    } => Ok(())
+}
+
+test_verify_with_pervasive! {
+    #[test] test_broadcast_hidden_fails DOUBLE.to_string() + code_str! {
+        #[proof]
+        #[verifier(broadcast)]
+        fn lemma_double_gt(i: int) {
+            requires(i > 0);
+            ensures(double(i) > i);
+            reveal(double);
+        }
+
+        fn test1(x: int) {
+            requires(x > 5);
+            hide(lemma_double_gt);
+            assert(double(x) > x); // FAILS
+        }
+    } => Err(err) => assert_one_fails(err)
+}
+
+test_verify_with_pervasive! {
+    #[test] test_broadcast_not_self_fails DOUBLE.to_string() + code_str! {
+        #[proof]
+        #[verifier(broadcast)]
+        fn lemma_double_gt(i: int) {
+            requires(i > 0);
+            ensures(double(i) > i); // FAILS
+        }
+    } => Err(err) => assert_one_fails(err)
+}
+
+test_verify_with_pervasive! {
+    #[test] test_broadcast_not_callees_fails DOUBLE.to_string() + code_str! {
+        #[proof]
+        fn helper(i: int) {
+            requires(i > 0);
+            ensures(double(i) > i); // FAILS
+        }
+
+        #[proof]
+        #[verifier(broadcast)]
+        fn lemma_double_gt(i: int) {
+            requires(i > 0);
+            ensures(double(i) > i);
+            helper(i);
+        }
+    } => Err(err) => assert_one_fails(err)
+}
+
+test_verify_with_pervasive! {
+    #[test] test_broadcast_exec_fn_rejected code! {
+        #[verifier(broadcast)]
+        fn lemma(i: u64) {
+            ensures(i >= 0);
+        }
+    } => Err(_)
+}
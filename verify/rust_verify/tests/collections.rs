@@ -0,0 +1,185 @@
+#![feature(rustc_private)]
+#[macro_use]
+mod common;
+use common::*;
+
+test_verify_with_pervasive! {
+    #[test] test_seq code! {
+        #[proof]
+        fn test_seq(s: Seq<int>) {
+            requires(equal(s, Seq::empty().push(10).push(20)));
+            assert(s.len() == 2);
+            assert(s.index(0) == 10);
+            assert(s.index(1) == 20);
+            assert(s.update(1, 30).index(1) == 30);
+            assert(s.update(1, 30).index(0) == 10);
+            assert(s.push(40).subrange(1, 3).len() == 2);
+            assert(s.push(40).subrange(1, 3).index(0) == 20);
+            assert(s.push(40).subrange(1, 3).index(1) == 40);
+        }
+
+        #[proof]
+        fn test_seq_typ(s: Seq<u8>, i: int) {
+            requires([0 <= i, i < s.len()]);
+            assert(s.index(i) <= 255);
+        }
+    } => Ok(())
+}
+
+test_verify_with_pervasive! {
+    #[test] test_seq_fails code! {
+        #[proof]
+        fn test_seq(s: Seq<int>) {
+            requires(s.len() == 2);
+            assert(s.push(5).index(2) == 5);
+            assert(s.subrange(0, 1).index(0) == s.index(1)); // FAILS
+        }
+    } => Err(err) => assert_one_fails(err)
+}
+
+test_verify_with_pervasive! {
+    #[test] test_seq_ext_equal_fails code! {
+        #[proof]
+        fn test_seq(s: Seq<int>) {
+            requires(s.len() == 2);
+            assert(s.push(1).subrange(0, 2).ext_equal(s));
+            assert(equal(s.push(1).subrange(0, 2), s));
+            assert(s.push(1).ext_equal(s)); // FAILS
+        }
+    } => Err(err) => assert_one_fails(err)
+}
+
+test_verify_with_pervasive! {
+    #[test] test_set code! {
+        #[proof]
+        fn test_set(s: Set<u64>, a: u64, b: u64) {
+            requires(a != b);
+            assert(!Set::<u64>::empty().contains(a));
+            assert(s.insert(a).contains(a));
+            assert(imply(s.contains(b), s.insert(a).contains(b)));
+            assert(!s.remove(a).contains(a));
+            assert(s.insert(a).remove(a).contains(b) == s.contains(b));
+            assert(s.insert(a).ext_equal(s.insert(a).insert(a)));
+            assert(equal(s.insert(a), s.insert(a).insert(a)));
+        }
+    } => Ok(())
+}
+
+test_verify_with_pervasive! {
+    #[test] test_set_fails code! {
+        #[proof]
+        fn test_set(s: Set<u64>, a: u64, b: u64) {
+            assert(s.insert(a).contains(b)); // FAILS
+        }
+    } => Err(err) => assert_one_fails(err)
+}
+
+test_verify_with_pervasive! {
+    #[test] test_map code! {
+        #[proof]
+        fn test_map(m: Map<int, u8>, k: int, v: u8) {
+            assert(m.insert(k, v).dom().contains(k));
+            assert(m.insert(k, v).index(k) == v);
+            assert(!m.remove(k).dom().contains(k));
+            assert(imply(m.dom().contains(k + 1), m.remove(k).dom().contains(k + 1)));
+            assert(imply(m.dom().contains(k), m.index(k) <= 255));
+            assert(Map::<int, u8>::empty().insert(k, v).ext_equal(Map::empty().insert(k, v).insert(k, v)));
+        }
+    } => Ok(())
+}
+
+test_verify_with_pervasive! {
+    #[test] test_map_fails code! {
+        #[proof]
+        fn test_map(m: Map<int, int>, k: int, j: int) {
+            assert(m.insert(k, 1).index(j) == 1); // FAILS
+        }
+    } => Err(err) => assert_one_fails(err)
+}
+
+test_verify_with_pervasive! {
+    #[test] test_ext_equal code! {
+        #[spec]
+        fn set_at(s: Seq<int>, i: int, v: int) -> Seq<int> {
+            s.update(i, v)
+        }
+
+        #[proof]
+        fn test_seq(s: Seq<int>, i: int) {
+            requires([0 <= i, i < s.len()]);
+            ensures(ext_equal(set_at(s, i, s.index(i)), s));
+        }
+
+        #[proof]
+        fn test_set(s: Set<int>, a: int) {
+            requires(s.contains(a));
+            ensures(ext_equal(s.insert(a), s));
+        }
+
+        #[proof]
+        fn test_map(m: Map<int, int>, k: int) {
+            requires(m.dom().contains(k));
+            ensures(ext_equal(m.insert(k, m.index(k)), m));
+        }
+
+        #[proof]
+        fn test_int(i: int, j: int) {
+            requires(i == j);
+            ensures(ext_equal(i, j));
+        }
+    } => Ok(())
+}
+
+test_verify_with_pervasive! {
+    #[test] test_equal_is_extensional code! {
+        #[proof]
+        fn test_seq(s: Seq<int>, i: int) {
+            requires([0 <= i, i < s.len()]);
+            ensures(equal(s.update(i, s.index(i)), s));
+        }
+
+        #[proof]
+        fn test_set(s: Set<int>, a: int) {
+            requires(s.contains(a));
+            ensures(equal(s.insert(a), s));
+        }
+
+        #[proof]
+        fn test_map(m: Map<int, int>, k: int) {
+            requires(m.dom().contains(k));
+            ensures(equal(m.insert(k, m.index(k)), m));
+        }
+
+        #[proof]
+        fn test_update(s: Seq<int>, t: Seq<int>, i: int) {
+            requires([
+                0 <= i,
+                i < s.len(),
+                s.len() == t.len(),
+                forall(|j: int| imply(0 <= j && j < s.len() && j != i, s.index(j) == t.index(j))),
+                t.index(i) == 7,
+            ]);
+            ensures(equal(s.update(i, 7), t));
+        }
+    } => Ok(())
+}
+
+test_verify_with_pervasive! {
+    #[test] test_ext_equal_typ_param code! {
+        #[proof]
+        fn test<A>(a: A, b: A) {
+            requires(equal(a, b));
+            ensures(ext_equal(a, b));
+        }
+    } => Err(_)
+}
+
+test_verify_with_pervasive! {
+    #[test] test_ext_equal_fails code! {
+        #[proof]
+        fn test_seq(s: Seq<int>, i: int) {
+            requires([0 <= i, i < s.len()]);
+            ensures(ext_equal(s.update(i, 0), s)); // FAILS
+        }
+    } => Err(err) => assert_one_fails(err)
+}
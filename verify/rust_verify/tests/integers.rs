@@ -164,3 +164,240 @@ fn typing(u: u64, i: int, n: nat) {
         }
     } => Err(_)
 }
+
+test_verify_with_pervasive! {
+    #[test] test_signed_div_mod code! {
+        #[spec]
+        fn negate(i: i32) -> i32 {
+            i / -1
+        }
+
+        fn div_mod(a: i32) {
+            requires(a == -7);
+            let q = a / 2;
+            let r = a % 2;
+            assert(q == -3);
+            assert(r == -1);
+            let q2 = a / -2;
+            let r2 = a % -2;
+            assert(q2 == 3);
+            assert(r2 == -1);
+            let q3 = 7 / -2;
+            let r3 = 7 % -2;
+            assert(q3 == -3);
+            assert(r3 == 1);
+        }
+    } => Ok(())
+}
+
+test_verify_with_pervasive! {
+    #[test] test_signed_div_overflow_fails code! {
+        fn div(a: i32, b: i32) -> i32 {
+            requires(b != 0);
+            a / b // FAILS
+        }
+    } => Err(err) => assert_one_fails(err)
+}
+
+test_verify_with_pervasive! {
+    #[test] test_signed_mod_overflow_fails code! {
+        fn rem(a: i8, b: i8) -> i8 {
+            requires(b != 0);
+            a % b // FAILS
+        }
+    } => Err(err) => assert_one_fails(err)
+}
+
+test_verify_with_pervasive! {
+    #[test] test_exec_overflow code! {
+        fn add(a: u8, b: u8) -> u8 {
+            requires(a as int + b as int <= 255);
+            a + b
+        }
+
+        fn sub(a: u32, b: u32) -> u32 {
+            requires(b <= a);
+            a - b
+        }
+
+        fn cast(a: u64) -> u8 {
+            requires(a < 256);
+            a as u8
+        }
+
+        fn div(a: u16, b: u16) -> u16 {
+            requires(b != 0);
+            a / b
+        }
+
+        fn rem(a: i16, b: i16) -> i16 {
+            requires(b > 0);
+            a % b
+        }
+
+        fn shl(a: u64, b: u32) -> u64 {
+            requires(b < 64);
+            a << b
+        }
+
+        fn shr(a: usize, b: u32) -> usize {
+            requires(b < 32);
+            a >> b
+        }
+
+        #[spec]
+        fn spec_add(a: u8, b: u8) -> u8 {
+            a + b
+        }
+
+        #[proof]
+        fn proof_add(a: u8, b: u8) -> u8 {
+            a + b
+        }
+    } => Ok(())
+}
+
+test_verify_with_pervasive! {
+    #[test] test_exec_add_overflow_fails code! {
+        fn add(a: u8, b: u8) -> u8 {
+            a + b // FAILS
+        }
+    } => Err(err) => assert_one_fails(err)
+}
+
+test_verify_with_pervasive! {
+    #[test] test_exec_sub_underflow_fails code! {
+        fn sub(a: u32, b: u32) -> u32 {
+            a - b // FAILS
+        }
+    } => Err(err) => assert_one_fails(err)
+}
+
+test_verify_with_pervasive! {
+    #[test] test_exec_mul_overflow_fails code! {
+        fn mul(a: i64) -> i64 {
+            requires(a > 0);
+            a * 2 // FAILS
+        }
+    } => Err(err) => assert_one_fails(err)
+}
+
+test_verify_with_pervasive! {
+    #[test] test_exec_neg_overflow_fails code! {
+        fn neg(x: i8) -> i8 {
+            -x // FAILS
+        }
+    } => Err(err) => assert_one_fails(err)
+}
+
+test_verify_with_pervasive! {
+    #[test] test_exec_cast_overflow_fails code! {
+        fn cast(a: u64) -> u8 {
+            a as u8 // FAILS
+        }
+    } => Err(err) => assert_one_fails(err)
+}
+
+test_verify_with_pervasive! {
+    #[test] test_exec_shl_overflow_fails code! {
+        fn shl(a: u64, b: u32) -> u64 {
+            requires(b <= 64);
+            a << b // FAILS
+        }
+    } => Err(err) => assert_one_fails(err)
+}
+
+test_verify_with_pervasive! {
+    #[test] test_exec_shr_overflow_fails code! {
+        fn shr(a: i8, b: i32) -> i8 {
+            requires(b < 8);
+            a >> b // FAILS
+        }
+    } => Err(err) => assert_one_fails(err)
+}
+
+test_verify_with_pervasive! {
+    #[test] test_exec_div_by_zero_fails code! {
+        fn div(a: u32, b: u32) -> u32 {
+            a / b // FAILS
+        }
+    } => Err(err) => assert_one_fails(err)
+}
+
+test_verify_with_pervasive! {
+    #[test] test_exec_mod_by_zero_fails code! {
+        fn rem(a: u32, b: u32) -> u32 {
+            requires(b <= 1);
+            a % b // FAILS
+        }
+    } => Err(err) => assert_one_fails(err)
+}
+
+test_verify_with_pervasive! {
+    #[test] test_exec_cast code! {
+        fn narrow(a: u64) -> u8 {
+            requires(a < 256);
+            ensures(|r: u8| r as u64 == a);
+            a as u8
+        }
+
+        fn wrap(a: u64) -> u8 {
+            truncate(a)
+        }
+
+        fn widen(a: u32) -> usize {
+            a as usize
+        }
+
+        fn test() {
+            let x: u32 = 300;
+            let y: u8 = truncate(x);
+            assert(y == 44);
+            let z: i8 = truncate(x);
+            assert(z == 44);
+            let w: u8 = truncate(-1i32);
+            assert(w == 255);
+        }
+    } => Ok(())
+}
+
+test_verify_with_pervasive! {
+    #[test] test_exec_cast_to_usize_fails code! {
+        fn cast(a: u128) -> usize {
+            a as usize // FAILS
+        }
+    } => Err(err) => assert_one_fails(err)
+}
+
+test_verify_with_pervasive! {
+    #[test] test_exec_cast_from_usize_fails code! {
+        fn cast(a: usize) -> u32 {
+            a as u32 // FAILS
+        }
+    } => Err(err) => assert_one_fails(err)
+}
+
+test_verify_with_pervasive! {
+    #[test] test_exec_cast_signed_fails code! {
+        fn cast(a: i32) -> u32 {
+            a as u32 // FAILS
+        }
+    } => Err(err) => assert_one_fails(err)
+}
+
+test_verify_with_pervasive! {
+    // tests run on a 64-bit target, so usize is 64 bits wide
+    #[test] test_arch_word_bits code! {
+        fn to_usize(a: u64) -> usize {
+            a as usize
+        }
+
+        fn from_usize(a: usize) -> u64 {
+            a as u64
+        }
+
+        fn to_isize(a: i64) -> isize {
+            a as isize
+        }
+    } => Ok(())
+}
@@ -19,16 +19,223 @@ fn test1() {
 }
 
 test_verify_with_pervasive! {
-    // TODO: support break in loops?
-    #[test] #[ignore] break_test code! {
-        fn test1(a: int, b: int) {
-            let mut i = a;
-            while i >= 1 {
-                if a % i == 0 && b % i == 0 {
+    #[test] break_test code! {
+        fn test1(a: u64) {
+            let mut i: u64 = 0;
+            loop {
+                invariant(i <= a);
+                ensures(i == a);
+                if i == a {
                     break;
                 }
+                i = i + 1;
+            }
+            assert(i == a);
+        }
+
+        fn test2(n: u64) {
+            let mut i: u64 = 0;
+            while i < n {
+                invariant(i <= n);
+                if i == 5 {
+                    break;
+                }
+                i = i + 1;
+            }
+            assert(i <= n);
+        }
+    } => Ok(())
+}
+
+test_verify_with_pervasive! {
+    #[test] break_no_cond_fails code! {
+        fn test1(n: u64) {
+            let mut i: u64 = 0;
+            while i < n {
+                invariant(i <= n);
+                if i == 5 {
+                    break;
+                }
+                i = i + 1;
+            }
+            assert(i == n); // FAILS
+        }
+    } => Err(err) => assert_one_fails(err)
+}
+
+test_verify_with_pervasive! {
+    #[test] break_ensures_fails code! {
+        fn test1(n: u64) {
+            let mut i: u64 = 0;
+            while i < n {
+                invariant(i <= n);
+                ensures(i == n);
+                if i == 5 {
+                    break; // FAILS
+                }
+                i = i + 1;
             }
-            assert(a % i == 0);
+        }
+    } => Err(err) => assert_one_fails(err)
+}
+
+test_verify_with_pervasive! {
+    #[test] loop_exit_ensures_fails code! {
+        fn test1(n: u64) {
+            let mut i: u64 = 0;
+            while i < n {
+                invariant(i <= n);
+                ensures(i == 5); // FAILS
+                i = i + 1;
+            }
+        }
+    } => Err(err) => assert_one_fails(err)
+}
+
+test_verify_with_pervasive! {
+    #[test] continue_test code! {
+        fn test1(n: u64) {
+            let mut i: u64 = 0;
+            let mut j: u64 = 0;
+            while i < n {
+                invariant([i <= n, j <= i]);
+                i = i + 1;
+                if i == 3 {
+                    continue;
+                }
+                j = j + 1;
+            }
+            assert(j <= n);
         }
     } => Ok(())
 }
+
+test_verify_with_pervasive! {
+    #[test] continue_fails code! {
+        fn test1(n: u64) {
+            let mut i: u64 = 0;
+            let mut j: u64 = 0;
+            while i < n {
+                invariant([i <= n, j == i]);
+                i = i + 1;
+                if i == 3 {
+                    continue; // FAILS
+                }
+                j = j + 1;
+            }
+        }
+    } => Err(err) => assert_one_fails(err)
+}
+
+test_verify_with_pervasive! {
+    #[test] labeled_break_test code! {
+        fn test1(n: u64) {
+            let mut i: u64 = 0;
+            'outer: while i < n {
+                invariant(i <= n);
+                let mut j: u64 = 0;
+                while j < i {
+                    invariant([i < n, j <= i]);
+                    if j == 3 {
+                        break 'outer;
+                    }
+                    j = j + 1;
+                }
+                i = i + 1;
+            }
+            assert(i <= n);
+        }
+    } => Ok(())
+}
+
+test_verify_with_pervasive! {
+    #[test] loop_no_break code! {
+        fn test1() {
+            let mut i: u64 = 0;
+            loop {
+                invariant(i < 10);
+                i = if i == 9 { 0 } else { i + 1 };
+            }
+            assert(false); // unreachable
+        }
+    } => Ok(())
+}
+
+test_verify_with_pervasive! {
+    #[test] decreases_test code! {
+        fn test1(n: u64) {
+            let mut i: u64 = 0;
+            while i < n {
+                invariant(i <= n);
+                decreases(n - i);
+                i = i + 1;
+            }
+        }
+
+        fn test2(n: u64) {
+            let mut i: u64 = n;
+            loop {
+                invariant(i <= n);
+                ensures(i == 0);
+                decreases(i);
+                if i == 0 {
+                    break;
+                }
+                i = i - 1;
+            }
+        }
+
+        #[proof]
+        fn test3(n: nat) {
+            let mut i: int = n;
+            while i > 0 {
+                invariant(i >= 0);
+                decreases(i);
+                i = i - 1;
+            }
+            assert(i == 0);
+        }
+    } => Ok(())
+}
+
+test_verify_with_pervasive! {
+    #[test] decreases_fails code! {
+        fn test1(n: u64) {
+            let mut i: u64 = 0;
+            while i < n {
+                invariant(i <= n);
+                decreases(i); // FAILS
+                i = i + 1;
+            }
+        }
+    } => Err(err) => assert_one_fails(err)
+}
+
+test_verify_with_pervasive! {
+    #[test] decreases_continue_fails code! {
+        fn test1(n: u64) {
+            let mut i: u64 = 0;
+            while i < n {
+                invariant(i <= n);
+                decreases(n - i);
+                if i == 3 {
+                    continue; // FAILS
+                }
+                i = i + 1;
+            }
+        }
+    } => Err(err) => assert_one_fails(err)
+}
+
+test_verify_with_pervasive! {
+    #[test] proof_loop_requires_decreases code! {
+        #[proof]
+        fn test1(n: nat) {
+            let mut i: int = n;
+            while i > 0 {
+                invariant(i >= 0);
+                i = i - 1;
+            }
+        }
+    } => Err(_)
+}
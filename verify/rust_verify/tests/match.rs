@@ -139,7 +139,7 @@ fn get_len<A>(list: &List<A>) -> u64 {
             let mut iter = list;
             while !done {
                 invariant([
-                    n + len(iter) == len(list), // FAILS
+                    n + len(iter) == len(list),
                     imply(done, len(iter) == 0),
                 ]);
 
@@ -149,7 +149,7 @@ fn get_len<A>(list: &List<A>) -> u64 {
                     }
                     List::Cons(_, tl) => {
                         iter = tl;
-                        n = n + 1;
+                        n = n + 1; // FAILS
                     }
                 }
             }
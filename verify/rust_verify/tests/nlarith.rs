@@ -12,3 +12,80 @@ fn test(x: nat, y: nat, z: nat) {
         }
     } => Ok(())
 }
+
+test_verify_with_pervasive! {
+    #[test] test_nonlinear_proof_fn code! {
+        #[proof]
+        #[verifier(nonlinear)]
+        fn lemma_mul_pos(x: int, y: int) {
+            requires([x > 0, y > 0]);
+            ensures(x * y > 0);
+        }
+
+        fn test(a: int, b: int) {
+            requires([a > 1, b > 1]);
+            lemma_mul_pos(a, b);
+            assert(a * b > 0);
+        }
+    } => Ok(())
+}
+
+test_verify_with_pervasive! {
+    #[test] test_nonlinear_exec_fn_rejected code! {
+        #[verifier(nonlinear)]
+        fn test(x: u64) {
+        }
+    } => Err(_)
+}
+
+test_verify_with_pervasive! {
+    #[test] test_assert_nonlinear_by code! {
+        fn test(x: int, y: int, z: int) {
+            requires([x > 0, y > 0]);
+            assert_nonlinear_by({
+                requires([x > 0, y > 0]);
+                ensures(x * y > 0);
+            });
+            assert(x * y > 0);
+        }
+    } => Ok(())
+}
+
+test_verify_with_pervasive! {
+    #[test] test_assert_nonlinear_by_in_nonlinear_fn code! {
+        #[proof]
+        #[verifier(nonlinear)]
+        fn lemma_mul_pos(x: int, y: int, z: int) {
+            requires([x > 0, y > 0, z > 0]);
+            ensures(x * y * z > 0);
+            assert_nonlinear_by({
+                requires([x > 0, y > 0]);
+                ensures(x * y > 0);
+            });
+            // the rest of the body is still checked with nonlinear arithmetic
+            assert((x * y) * z > 0);
+        }
+    } => Ok(())
+}
+
+test_verify_with_pervasive! {
+    #[test] test_assert_nonlinear_by_requires_fails code! {
+        fn test(x: int) {
+            assert_nonlinear_by({
+                requires(x > 0); // FAILS
+                ensures(x * x > 0);
+            });
+        }
+    } => Err(err) => assert_one_fails(err)
+}
+
+test_verify_with_pervasive! {
+    #[test] test_assert_nonlinear_by_isolated_fails code! {
+        fn test(x: int) {
+            requires(x > 0);
+            assert_nonlinear_by({
+                ensures(x * x > 0); // FAILS
+            });
+        }
+    } => Err(err) => assert_one_fails(err)
+}
@@ -146,6 +146,57 @@ fn test1() {
 
 ////
 
+test_verify_with_pervasive! {
+    #[test] test_choose code! {
+        #[spec]
+        fn f(i: int) -> bool {
+            10 <= i && i < 20
+        }
+
+        #[spec]
+        fn g(x: u8) -> bool {
+            true
+        }
+
+        #[spec]
+        fn witness(lo: int) -> int {
+            choose(|i: int| lo <= i && f(i))
+        }
+
+        #[proof]
+        fn test_witness() {
+            assert(f(15));
+            assert(12 <= witness(12));
+            assert(witness(12) < 20);
+        }
+
+        #[proof]
+        fn test_same_choice() {
+            assert(equal(choose(|i: int| f(i)), choose(|i: int| f(i))));
+            assert(choose(|x: u8| g(x)) <= 255);
+        }
+    } => Ok(())
+}
+
+test_verify_with_pervasive! {
+    #[test] test_choose_fails code! {
+        #[spec]
+        fn f(i: int) -> bool {
+            10 <= i && i < 20
+        }
+
+        #[spec]
+        fn witness(lo: int) -> int {
+            choose(|i: int| lo <= i && f(i))
+        }
+
+        #[proof]
+        fn test_no_witness() {
+            assert(f(witness(30))); // FAILS
+        }
+    } => Err(err) => assert_one_fails(err)
+}
+
 /* REVIEW: these tests need #![feature(stmt_expr_attributes)], which doesn't seem to work here
 test_verify_with_pervasive! {
     #[test] test4 code! {
@@ -241,3 +241,111 @@ fn count_down_tricky(i: nat) {
         }
     } => Err(err) => assert_one_fails(err)
 }
+
+test_verify_with_pervasive! {
+    #[test] lexicographic_expr code! {
+        #[spec]
+        fn ack(m: nat, n: nat) -> nat {
+            decreases((m, n));
+
+            if m == 0 {
+                n + 1
+            } else if n == 0 {
+                ack(m - 1, 1)
+            } else {
+                ack(m - 1, ack(m, n - 1))
+            }
+        }
+    } => Ok(())
+}
+
+test_verify_with_pervasive! {
+    #[test] lexicographic_stmt code! {
+        #[proof]
+        fn count_down_pair(i: nat, j: nat) {
+            decreases((i, j));
+
+            if j != 0 {
+                count_down_pair(i, j - 1);
+            } else if i != 0 {
+                count_down_pair(i - 1, 100);
+            }
+        }
+    } => Ok(())
+}
+
+test_verify_with_pervasive! {
+    #[test] lexicographic_mutual code! {
+        enum List {
+            Nil,
+            Cons(int, Box<List>),
+        }
+
+        #[spec]
+        fn f(list: &List, n: nat) -> int {
+            decreases((list, n));
+
+            if n == 0 {
+                0
+            } else {
+                g(list, n - 1)
+            }
+        }
+
+        #[spec]
+        fn g(list: &List, n: nat) -> int {
+            decreases((list, n));
+
+            match list {
+                List::Nil => 0,
+                List::Cons(_, tl) => f(tl, 10),
+            }
+        }
+    } => Ok(())
+}
+
+test_verify_with_pervasive! {
+    #[test] lexicographic_first_component_fails code! {
+        #[spec]
+        fn count_pair(i: nat, j: nat) -> nat {
+            decreases((i, j));
+
+            if i == 0 {
+                0
+            } else {
+                count_pair(i + 1, j) // FAILS
+            }
+        }
+    } => Err(err) => assert_one_fails(err)
+}
+
+test_verify_with_pervasive! {
+    #[test] lexicographic_second_component_fails code! {
+        #[proof]
+        fn count_pair_stmt(i: nat, j: nat) {
+            decreases((i, j));
+
+            if i != 0 {
+                count_pair_stmt(i, j); // FAILS
+            }
+        }
+    } => Err(err) => assert_one_fails(err)
+}
+
+test_verify_with_pervasive! {
+    #[test] lexicographic_mismatched_mutual code! {
+        #[spec]
+        fn f(i: nat, j: nat) -> int {
+            decreases((i, j));
+
+            if i == 0 { 0 } else { g(i - 1, j) }
+        }
+
+        #[spec]
+        fn g(i: nat, j: nat) -> int {
+            decreases(i);
+
+            f(i, j)
+        }
+    } => Err(_)
+}
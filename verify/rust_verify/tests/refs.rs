@@ -12,3 +12,165 @@ fn test_ref_0(p: int) {
         }
     } => Ok(())
 }
+
+test_verify_with_pervasive! {
+    #[test] test_mut_ref_arg_pass code! {
+        fn add1(a: &mut u64) {
+            requires(*a < 10);
+            ensures(*a < 11);
+            *a = *a + 1;
+        }
+
+        fn caller() {
+            let mut x: u64 = 5;
+            add1(&mut x);
+            assert(x < 11);
+            add1(&mut x);
+            assert(x < 11);
+        }
+    } => Ok(())
+}
+
+test_verify_with_pervasive! {
+    #[test] test_mut_ref_arg_fail code! {
+        fn add1(a: &mut u64) {
+            requires(*a < 10);
+            ensures(*a < 11);
+            *a = *a + 1;
+        }
+
+        fn caller() {
+            let mut x: u64 = 5;
+            add1(&mut x);
+            assert(x == 5); // FAILS
+        }
+    } => Err(err) => assert_one_fails(err)
+}
+
+test_verify_with_pervasive! {
+    #[test] test_mut_ref_ensures_fail code! {
+        fn set(a: &mut u64, v: u64) {
+            ensures(*a == v);
+            *a = 7;
+        }
+    } => Err(err) => assert_one_fails(err)
+}
+
+test_verify_with_pervasive! {
+    #[test] test_mut_self code! {
+        struct Counter {
+            n: u64,
+        }
+
+        impl Counter {
+            fn reset(&mut self) {
+                ensures(self.n == 0);
+                *self = Counter { n: 0 };
+            }
+        }
+
+        fn caller() {
+            let mut c = Counter { n: 3 };
+            c.reset();
+            assert(c.n == 0);
+        }
+    } => Ok(())
+}
+
+test_verify_with_pervasive! {
+    #[test] test_old_pass code! {
+        fn add1(a: &mut u64) {
+            requires(*old(a) < 10);
+            ensures(*a == *old(a) + 1);
+            *a = *a + 1;
+        }
+
+        fn add2(a: &mut u64) {
+            requires(*a < 5);
+            ensures(*a == *old(a) + 2);
+            add1(a);
+            assert(*a == *old(a) + 1);
+            add1(a);
+        }
+
+        fn caller() {
+            let mut x: u64 = 2;
+            add2(&mut x);
+            assert(x == 4);
+        }
+    } => Ok(())
+}
+
+test_verify_with_pervasive! {
+    #[test] test_old_fail code! {
+        fn add1(a: &mut u64) {
+            requires(*a < 10);
+            ensures(*a == *old(a) + 1);
+            *a = *a + 2;
+        }
+    } => Err(err) => assert_one_fails(err)
+}
+
+test_verify_with_pervasive! {
+    #[test] test_old_loop code! {
+        fn count_up(a: &mut int, n: int) {
+            requires(n >= 0);
+            ensures(*a == *old(a) + n);
+            let mut i: int = 0;
+            while i < n {
+                invariant([
+                    i <= n,
+                    *a == *old(a) + i,
+                ]);
+                *a = *a + 1;
+                i = i + 1;
+            }
+        }
+    } => Ok(())
+}
+
+test_verify_with_pervasive! {
+    #[test] test_old_not_param code! {
+        fn f(a: &mut u64) {
+            let b: u64 = 5;
+            assert(old(b) == 5);
+        }
+    } => Err(_)
+}
+
+test_verify_with_pervasive! {
+    #[test] test_mut_ref_let_unsupported code! {
+        fn f(x: &mut u64) {
+            ensures(*x == *old(x));
+            let y = x;
+            *y = 7;
+        }
+    } => Err(_)
+}
+
+test_verify_with_pervasive! {
+    #[test] test_mut_ref_field_unsupported code! {
+        struct S<'a> {
+            r: &'a mut u64,
+        }
+    } => Err(_)
+}
+
+test_verify_with_pervasive! {
+    #[test] test_mut_ref_return_unsupported code! {
+        fn f(x: &mut u64) -> &mut u64 {
+            x
+        }
+    } => Err(_)
+}
+
+test_verify_with_pervasive! {
+    #[test] test_mut_ref_typ_arg_unsupported code! {
+        fn g<A>(a: A) {
+        }
+
+        fn f(x: &mut u64) {
+            g(x);
+        }
+    } => Err(_)
+}
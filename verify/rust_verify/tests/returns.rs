@@ -0,0 +1,103 @@
+#![feature(rustc_private)]
+#[macro_use]
+mod common;
+use common::*;
+
+test_verify_with_pervasive! {
+    #[test] test_early_return code! {
+        fn max(a: u64, b: u64) -> u64 {
+            ensures(|r: u64| r >= a && r >= b);
+            if a >= b {
+                return a;
+            }
+            b
+        }
+
+        fn tail_return(a: u64) -> u64 {
+            ensures(|r: u64| r == a);
+            return a;
+        }
+
+        fn branch_return(a: u64) -> u64 {
+            ensures(|r: u64| r < 10);
+            let x = if a < 10 { a } else { return 0; };
+            x
+        }
+
+        fn test_unit(a: u64) {
+            if a == 0 {
+                return;
+            }
+            assert(a > 0);
+        }
+    } => Ok(())
+}
+
+test_verify_with_pervasive! {
+    #[test] test_early_return_fails code! {
+        fn max(a: u64, b: u64) -> u64 {
+            ensures(|r: u64| r >= a && r >= b);
+            if a > 10 {
+                return a; // FAILS
+            }
+            if a >= b { a } else { b }
+        }
+    } => Err(err) => assert_one_fails(err)
+}
+
+test_verify_with_pervasive! {
+    #[test] test_return_in_loop code! {
+        fn find(n: u64) -> u64 {
+            ensures(|r: u64| r <= n && r <= 5);
+            let mut i = 0;
+            while i < n {
+                invariant([i <= n, i <= 5]);
+                if i == 5 {
+                    return i;
+                }
+                i = i + 1;
+            }
+            i
+        }
+    } => Ok(())
+}
+
+test_verify_with_pervasive! {
+    #[test] test_return_in_loop_fails code! {
+        fn find(n: u64) -> u64 {
+            ensures(|r: u64| r < 5);
+            let mut i = 0;
+            while i < n {
+                invariant([i <= n]);
+                if i == 7 {
+                    return i; // FAILS
+                }
+                i = i + 1;
+            }
+            0
+        }
+    } => Err(err) => assert_one_fails(err)
+}
+
+test_verify_with_pervasive! {
+    #[test] test_return_proof code! {
+        #[proof]
+        fn lemma(x: int) -> int {
+            requires(x >= 0);
+            ensures(|r: int| r >= 0);
+            if x == 0 {
+                return 0;
+            }
+            x
+        }
+    } => Ok(())
+}
+
+test_verify_with_pervasive! {
+    #[test] test_return_spec_fn code! {
+        #[spec]
+        fn f(x: int) -> int {
+            return x;
+        }
+    } => Err(_)
+}
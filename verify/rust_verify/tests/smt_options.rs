@@ -0,0 +1,98 @@
+#![feature(rustc_private)]
+#[macro_use]
+mod common;
+use common::*;
+
+test_verify_with_pervasive! {
+    #[test] test_function_options code! {
+        #[verifier(rlimit(20))]
+        fn test1(x: u64) {
+            requires(x < 100);
+            assert(x + 1 < 200);
+        }
+
+        #[verifier(smt_option(random_seed, 7))]
+        #[verifier(smt_option(case_split, 1))]
+        fn test2(x: u64) {
+            requires(x < 100);
+            assert(x * 2 < 200);
+        }
+
+        fn test3(x: u64) {
+            requires(x < 100);
+            assert(x + 2 < 200);
+        }
+    } => Ok(())
+}
+
+test_verify_with_pervasive! {
+    #[test] test_function_options_fails code! {
+        #[verifier(rlimit(20))]
+        #[verifier(smt_option(random_seed, 7))]
+        fn test1(x: u64) {
+            assert(x < 100); // FAILS
+        }
+
+        fn test2(x: u64) {
+            requires(x < 100);
+            assert(x + 1 < 200);
+        }
+    } => Err(err) => assert_one_fails(err)
+}
+
+test_verify_with_pervasive! {
+    #[test] test_rlimit_timeout code! {
+        #[spec]
+        #[opaque]
+        fn f(i: int) -> int {
+            i
+        }
+
+        // f(i) < f(i + 1) is a matching loop, so the solver can only stop at the rlimit
+        #[proof]
+        #[verifier(rlimit(1))]
+        fn test1() {
+            requires(forall(|i: int| f(i) < f(i + 1)));
+            assert(f(0) < f(-1));
+        }
+
+        // test1's rlimit doesn't apply here
+        #[proof]
+        fn test2(x: int) {
+            requires(forall(|i: int| f(i) == i));
+            assert(f(x) + f(x + 1) == 2 * x + 1);
+        }
+    } => Err(err) => {
+        assert_eq!(err.len(), 1);
+        let description = err[0].0.as_ref().expect("span").description.clone();
+        let description = description.expect("description");
+        assert!(description.contains("test1") && description.contains("timed out"));
+    }
+}
+
+test_verify_with_pervasive! {
+    #[test] test_rlimit_too_large code! {
+        #[verifier(rlimit(5000))]
+        fn test1(x: u64) {
+            assert(x >= 0);
+        }
+    } => Err(_)
+}
+
+test_verify_with_pervasive! {
+    #[test] test_unsupported_smt_option code! {
+        #[verifier(smt_option(auto_config, 1))]
+        fn test1(x: u64) {
+            assert(x >= 0);
+        }
+    } => Err(_)
+}
+
+test_verify_with_pervasive! {
+    #[test] test_rlimit_not_integer code! {
+        #[verifier(rlimit(many))]
+        fn test1(x: u64) {
+            assert(x >= 0);
+        }
+    } => Err(_)
+}
@@ -84,3 +84,20 @@ struct Thing {
         }
     } => Err(err) => assert_eq!(err.len(), 0)
 }
+
+test_verify_with_pervasive! {
+    #[test] test_structural_generic_bound code! {
+        #[derive(PartialEq, Eq, Structural)]
+        struct Thing<V> {
+            v: V,
+        }
+
+        fn test_bound<V: Structural + PartialEq>(a: V, b: V) {
+            requires(equal(a, b));
+            assert(a == b);
+            let t1 = Thing { v: a };
+            let t2 = Thing { v: b };
+            assert(t1 == t2);
+        }
+    } => Ok(())
+}
@@ -0,0 +1,162 @@
+#![feature(rustc_private)]
+#[macro_use]
+mod common;
+use common::*;
+
+const TRAIT: &str = code_str! {
+    trait T1 {
+        fn f(&self, x: u64) -> u64 {
+            requires(x < 100);
+            ensures(|r: u64| r > x);
+            no_method_body()
+        }
+    }
+
+    struct S {
+        n: u64,
+    }
+};
+
+test_verify_with_pervasive! {
+    #[test] test_trait_impl_ok TRAIT.to_string() + code_str! {
+        impl T1 for S {
+            fn f(&self, x: u64) -> u64 {
+                x + 1
+            }
+        }
+
+        fn test(s: S) {
+            let r = s.f(10);
+            assert(r > 10);
+        }
+    } => Ok(())
+}
+
+test_verify_with_pervasive! {
+    #[test] test_trait_impl_fails_ensures TRAIT.to_string() + code_str! {
+        impl T1 for S {
+            fn f(&self, x: u64) -> u64 {
+                x // FAILS
+            }
+        }
+    } => Err(err) => assert_one_fails(err)
+}
+
+test_verify_with_pervasive! {
+    #[test] test_trait_call_fails_requires TRAIT.to_string() + code_str! {
+        impl T1 for S {
+            fn f(&self, x: u64) -> u64 {
+                x + 1
+            }
+        }
+
+        fn test(s: S) {
+            let r = s.f(100); // FAILS
+        }
+    } => Err(err) => assert_one_fails(err)
+}
+
+test_verify_with_pervasive! {
+    #[test] test_trait_impl_own_requires TRAIT.to_string() + code_str! {
+        impl T1 for S {
+            fn f(&self, x: u64) -> u64 {
+                requires(x < 10);
+                x + 1
+            }
+        }
+    } => Err(_)
+}
+
+test_verify_with_pervasive! {
+    #[test] test_trait_impl_missing_method TRAIT.to_string() + code_str! {
+        impl T1 for S {
+        }
+    } => Err(_)
+}
+
+test_verify_with_pervasive! {
+    #[test] test_trait_spec_method code! {
+        trait Counter {
+            #[spec]
+            fn value(&self) -> u64 {
+                no_method_body()
+            }
+
+            fn get(&self) -> u64 {
+                ensures(|r: u64| r == self.value());
+                no_method_body()
+            }
+        }
+
+        struct C {
+            v: u64,
+        }
+
+        impl Counter for C {
+            #[spec]
+            fn value(&self) -> u64 {
+                self.v
+            }
+
+            fn get(&self) -> u64 {
+                self.v
+            }
+        }
+
+        fn test(c: C) {
+            let r = c.get();
+            assert(r == c.value());
+            assert(r == c.v);
+        }
+    } => Ok(())
+}
+
+test_verify_with_pervasive! {
+    #[test] test_trait_decl_requires_body code! {
+        trait T2 {
+            fn f(&self) -> u64;
+        }
+    } => Err(_)
+}
+
+test_verify_with_pervasive! {
+    #[test] test_trait_bound_generic TRAIT.to_string() + code_str! {
+        fn test_bound<A: T1>(a: &A) {
+            let r = a.f(10);
+            assert(r > 10);
+        }
+
+        fn test_where<A>(a: &A) where A: T1 {
+            let r = a.f(20);
+            assert(r > 20);
+        }
+
+        struct Holder<A: T1> {
+            a: A,
+        }
+
+        fn test_holder<A: T1>(h: Holder<A>) {
+            let r = h.a.f(30);
+            assert(r > 31); // FAILS
+        }
+    } => Err(err) => assert_one_fails(err)
+}
+
+test_verify_with_pervasive! {
+    #[test] test_trait_method_generics_unsupported code! {
+        trait T3 {
+            fn f<A>(&self, a: A) -> u64 {
+                no_method_body()
+            }
+        }
+
+        struct S3 {
+        }
+
+        impl T3 for S3 {
+            fn f<A>(&self, a: A) -> u64 {
+                7
+            }
+        }
+    } => Err(_)
+}
@@ -81,18 +81,38 @@ pub enum TypX {
     Tuple(Arc<Vec<(Typ, Mode)>>),
     /// Datatype (concrete or abstract) applied to type arguments
     Datatype(Path, Typs),
+    /// Mathematical sequence (spec only), with SMT-boxed elements
+    Seq(Typ),
+    /// Array [T; N] with Some(N), or slice [T] with None; represented in SMT as a sequence
+    Array(Typ, Option<u64>),
+    /// Mathematical set (spec only), with SMT-boxed elements
+    Set(Typ),
+    /// Mathematical map (spec only) from keys to values, with SMT-boxed keys and values
+    Map(Typ, Typ),
     /// Boxed for SMT encoding (unrelated to Rust Box type), can be unboxed:
     Boxed(Typ),
     /// Type parameter (inherently SMT-boxed, and cannot be unboxed)
     TypParam(Ident),
 }
 
+/// Bound on a type parameter, from either the parameter list (T: Ord) or a where clause
+pub type GenericBound = Arc<GenericBoundX>;
+#[derive(Debug)]
+pub enum GenericBoundX {
+    /// The type parameter implements all of the listed traits (no traits means no bound)
+    Traits(Vec<Path>),
+}
+/// Type parameters of a function or datatype, each with its bound
+pub type TypParams = Arc<Vec<(Ident, GenericBound)>>;
+
 /// Primitive unary operations
 /// (not arbitrary user-defined functions -- these are represented by ExprX::Call)
 #[derive(Copy, Clone, Debug)]
 pub enum UnaryOp {
     /// boolean not
     Not,
+    /// bitwise not on integers
+    BitNot,
     /// Mark an expression as a member of an SMT quantifier trigger group.
     /// Each trigger group becomes one SMT trigger containing all the expressions in the trigger group.
     /// Each group is named by either Some integer, or the unnamed group None.
@@ -101,8 +121,10 @@ pub enum UnaryOp {
     /// Example: #[trigger(1, 2, 3)] expr is translated into three Trigger ops wrapped around expr
     ///   (Trigger(Some(1)), Trigger(Some(2)), Trigger(Some(3)))
     Trigger(Option<u64>),
-    /// Force integer value into range given by IntRange (e.g. by using mod)
-    Clip(IntRange),
+    /// Force integer value into range given by IntRange (e.g. by using mod).
+    /// If truncate is true, the value is explicitly wrapped (as in Rust's `as` casts),
+    /// and exec code does not need to prove that the value already fits in the range.
+    Clip { range: IntRange, truncate: bool },
 }
 
 /// More complex unary operations (requires Clone rather than Copy)
@@ -121,6 +143,60 @@ pub enum UnaryOpr {
     Field { datatype: Path, variant: Ident, field: Ident },
 }
 
+/// Operations on sequences, arrays, and slices
+/// (all elements are SMT-boxed, so Index returns Boxed(Typ), and Push and Update take Boxed(Typ))
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum SeqOp {
+    /// View the contents of an array or slice as a sequence (spec only)
+    View,
+    /// Sequence with no elements
+    Empty,
+    /// Sequence with an element appended to the end: Push(s, a)
+    Push,
+    /// Length of a sequence, array, or slice: Len(s)
+    Len,
+    /// Element at an index: Index(s, i)
+    Index,
+    /// Sequence, array, or slice with the element at an index replaced: Update(s, i, a)
+    Update,
+    /// Elements from index i up to, but not including, index j (spec only): Subrange(s, i, j)
+    Subrange,
+    /// Same length and same element at every index (spec only): ExtEqual(s1, s2)
+    ExtEqual,
+}
+
+/// Operations on mathematical sets (all spec only; elements are SMT-boxed)
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum SetOp {
+    /// Set with no elements
+    Empty,
+    /// Membership test: Contains(s, a)
+    Contains,
+    /// Set with an element added: Insert(s, a)
+    Insert,
+    /// Set with an element removed: Remove(s, a)
+    Remove,
+    /// Same elements: ExtEqual(s1, s2)
+    ExtEqual,
+}
+
+/// Operations on mathematical maps (all spec only; keys and values are SMT-boxed)
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum MapOp {
+    /// Map with an empty domain
+    Empty,
+    /// Set of keys in the map: Dom(m)
+    Dom,
+    /// Value for a key: Index(m, k)
+    Index,
+    /// Map with a key mapped to a value: Insert(m, k, v)
+    Insert,
+    /// Map with a key removed from the domain: Remove(m, k)
+    Remove,
+    /// Same domain and same value for every key in the domain: ExtEqual(m1, m2)
+    ExtEqual,
+}
+
 /// Primitive binary operations
 /// (not arbitrary user-defined functions -- these are represented by ExprX::Call)
 /// Note that all integer operations are on mathematic integers (IntRange::Int),
@@ -158,6 +234,27 @@ pub enum BinaryOp {
     EuclideanDiv,
     /// IntRange::Int % defined as Euclidean (returns non-negative result even for negative divisor)
     EuclideanMod,
+    /// IntRange::Int / rounding towards zero (Rust's / on signed integers)
+    TruncDiv,
+    /// IntRange::Int % with the sign of the dividend (Rust's % on signed integers)
+    TruncMod,
+    /// bitwise and on integers
+    BitAnd,
+    /// bitwise or on integers
+    BitOr,
+    /// bitwise xor on integers
+    BitXor,
+    /// right shift on integers
+    Shr,
+    /// left shift on integers
+    Shl,
+}
+
+/// Point in time at which a variable is read
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum VarAt {
+    /// Value of a parameter at function entry, written old(x)
+    Pre,
 }
 
 /// Ghost annotations on functions and while loops; must appear at the beginning of function body
@@ -171,11 +268,14 @@ pub enum HeaderExprX {
     Ensures(Option<(Ident, Typ)>, Exprs),
     /// Invariants on while loops
     Invariant(Exprs),
-    /// Decreases clauses for functions (possibly also for while loops, but this isn't implemented yet)
-    Decreases(Expr),
+    /// Decreases clauses for functions and loops
+    /// (with more than one expression, the expressions are ordered lexicographically)
+    Decreases(Exprs),
     /// Make a function f opaque (definition hidden) within the current function body.
     /// (The current function body can later reveal f in specific parts of the current function body if desired.)
     Hide(Path),
+    /// Placeholder body for a method declared in a trait
+    NoMethodBody,
 }
 
 /// Primitive constant values
@@ -231,6 +331,10 @@ pub enum ExprX {
     Const(Constant),
     /// Local variable
     Var(Ident),
+    /// Local variable, at a different point in time
+    VarAt(Ident, VarAt),
+    /// Mutable location (e.g. &mut x) passed as an argument to a &mut parameter
+    Loc(Expr),
     /// Call to function with given name, passing some type arguments and some expression arguments
     /// Note: higher-order functions aren't yet supported
     Call(Path, Typs, Exprs),
@@ -244,8 +348,16 @@ pub enum ExprX {
     UnaryOpr(UnaryOpr, Expr),
     /// Primitive binary operation
     Binary(BinaryOp, Expr, Expr),
+    /// Sequence, array, or slice operation
+    SeqOp(SeqOp, Exprs),
+    /// Set operation
+    SetOp(SetOp, Exprs),
+    /// Map operation
+    MapOp(MapOp, Exprs),
     /// Quantifier (forall/exists), binding the variables in Binders, with body Expr
     Quant(Quant, Binders<Typ>, Expr),
+    /// Hilbert choice of a value for the Binder satisfying the body Expr, if any such value exists
+    Choose(Binder<Typ>, Expr),
     /// Assign to local variable
     Assign(Expr, Expr),
     /// Reveal definition of an opaque function with some integer fuel amount
@@ -256,14 +368,40 @@ pub enum ExprX {
     Header(HeaderExpr),
     /// Assume false
     Admit,
+    /// Assert a boolean expression, proven with bit-vector reasoning in a separate query
+    /// that assumes nothing from the surrounding code
+    AssertBV(Expr),
+    /// Prove ensure from require for arbitrary values of vars, using the proof,
+    /// in a separate query that sees nothing else from the surrounding code.
+    /// Only forall vars. require ==> ensure is visible afterwards;
+    /// whatever the proof assumes or reveals stays local to the proof.
+    AssertBy { vars: Binders<Typ>, require: Expr, ensure: Expr, proof: Expr },
+    /// Prove ensures from requires using the proof, in a separate query
+    /// that has nonlinear arithmetic enabled and sees nothing else from the surrounding code.
+    /// requires are checked here, and ensures are visible afterwards.
+    AssertNonlinear { requires: Exprs, ensures: Exprs, proof: Expr },
     /// If-else
     If(Expr, Expr, Option<Expr>),
     /// Match (Note: ast_simplify replaces Match with other expressions)
     Match(Expr, Arms),
-    /// While loop, with invariants
-    While { cond: Expr, body: Expr, invs: Exprs },
+    /// Loop, with invariants, and with ensures that must hold whenever the loop exits.
+    /// A while loop has a cond, which is checked before each iteration;
+    /// otherwise, the loop only exits via break.
+    /// If decrease is present, the loop must decrease it on every iteration (proving termination).
+    Loop {
+        label: Option<Ident>,
+        cond: Option<Expr>,
+        body: Expr,
+        invs: Exprs,
+        enss: Exprs,
+        decrease: Option<Expr>,
+    },
+    /// Break or continue, targeting the loop with the given label (or the innermost loop)
+    BreakOrContinue { label: Option<Ident>, is_break: bool },
     /// Sequence of statements, optionally including an expression at the end
     Block(Stmts, Option<Expr>),
+    /// Return from the enclosing exec or proof function, with an optional return value
+    Return(Option<Expr>),
 }
 
 /// Statement, similar to rustc_hir::Stmt
@@ -287,6 +425,19 @@ pub struct ParamX {
     pub name: Ident,
     pub typ: Typ,
     pub mode: Mode,
+    /// For &mut parameters, the callee may update the value seen by the caller
+    pub is_mut: bool,
+}
+
+/// Where a function is declared, for functions that belong to traits
+#[derive(Clone, Debug)]
+pub enum FunctionKind {
+    /// Ordinary function, or method of an inherent impl
+    Static,
+    /// Method declared in a trait (with type parameter Self); its body is always None
+    TraitMethodDecl { trait_path: Path },
+    /// Method of a trait impl, implementing the trait method declaration `method`
+    TraitMethodImpl { method: Path, trait_path: Path },
 }
 
 /// Function, including signature and body
@@ -294,6 +445,8 @@ pub struct ParamX {
 #[derive(Debug, Clone)]
 pub struct FunctionX {
     pub path: Path,
+    /// Trait that the function belongs to, if any
+    pub kind: FunctionKind,
     pub visibility: Visibility,
     /// exec functions are compiled, proof/spec are erased
     /// exec/proof functions can have requires/ensures, spec cannot
@@ -303,7 +456,7 @@ pub struct FunctionX {
     /// For recursive functions, fuel determines the number of unfoldings that the SMT solver sees
     pub fuel: u32,
     /// Type parameters to generic functions
-    pub typ_params: Idents,
+    pub typ_params: TypParams,
     /// Function parameters
     pub params: Params,
     /// Return value (unit return type is treated specially; see FunctionX::has_return in ast_util)
@@ -313,7 +466,8 @@ pub struct FunctionX {
     /// Postconditions
     pub ensure: Exprs,
     /// Decreases clause to ensure recursive function termination
-    pub decrease: Option<Expr>,
+    /// (empty if none; multiple expressions are compared lexicographically)
+    pub decrease: Exprs,
     /// Custom error message to display when a pre-condition fails
     pub custom_req_err: Option<String>,
     /// List of functions that this function wants to view as opaque
@@ -321,6 +475,19 @@ pub struct FunctionX {
     /// For public spec functions, is_abstract == true means that the body is private
     /// even though the function is public
     pub is_abstract: bool,
+    /// For proof functions, bit_vector == true means that the ensures are proven from the requires
+    /// using bit-vector reasoning alone (the body is not verified)
+    pub bit_vector: bool,
+    /// For proof functions, nonlinear == true means that the body is verified
+    /// with the SMT solver's nonlinear arithmetic enabled
+    pub nonlinear: bool,
+    /// For proof functions, broadcast == true means that forall params. requires ==> ensures
+    /// is an axiom in every module that can see the function, so that it needn't be called
+    pub broadcast: bool,
+    /// Resource limit for the function's queries, overriding the default rlimit
+    pub rlimit: Option<u32>,
+    /// SMT solver options (option, value) set for the function's queries only
+    pub smt_options: Arc<Vec<(Ident, Ident)>>,
     /// Body of the function (may be None for foreign functions or for no_verify functions)
     pub body: Option<Expr>,
 }
@@ -343,7 +510,7 @@ pub struct DatatypeX {
     pub path: Path,
     pub visibility: Visibility,
     pub transparency: DatatypeTransparency,
-    pub typ_params: Idents,
+    pub typ_params: TypParams,
     pub variants: Variants,
 }
 pub type Datatype = Arc<Spanned<DatatypeX>>;
@@ -2,8 +2,8 @@
 
 use crate::ast::{
     BinaryOp, Binder, Constant, Datatype, DatatypeTransparency, DatatypeX, Expr, ExprX, Field,
-    Function, Ident, Krate, KrateX, Mode, Path, Pattern, PatternX, SpannedTyped, Stmt, StmtX, Typ,
-    TypX, UnaryOp, UnaryOpr, VirErr, Visibility,
+    Function, GenericBoundX, Ident, Krate, KrateX, MapOp, Mode, Path, Pattern, PatternX, SeqOp,
+    SetOp, SpannedTyped, Stmt, StmtX, Typ, TypX, UnaryOp, UnaryOpr, VirErr, Visibility,
 };
 use crate::ast_util::err_str;
 use crate::context::GlobalCtx;
@@ -46,8 +46,8 @@ fn tuple_type_name(&mut self, arity: usize) -> Path {
 fn is_small_expr(expr: &Expr) -> bool {
     match &expr.x {
         ExprX::Const(_) => true,
-        ExprX::Var(_) => true,
-        ExprX::Unary(UnaryOp::Not | UnaryOp::Clip(_), e) => is_small_expr(e),
+        ExprX::Var(_) | ExprX::VarAt(..) => true,
+        ExprX::Unary(UnaryOp::Not | UnaryOp::Clip { .. }, e) => is_small_expr(e),
         ExprX::UnaryOpr(UnaryOpr::Box(_) | UnaryOpr::Unbox(_), e) => is_small_expr(e),
         _ => false,
     }
@@ -203,6 +203,25 @@ fn simplify_one_expr(ctx: &GlobalCtx, state: &mut State, expr: &Expr) -> Result<
             };
             Ok(exp)
         }
+        ExprX::Binary(op @ (BinaryOp::Eq(_) | BinaryOp::Ne), lhs, rhs) => {
+            // Equality of sequences, sets, and maps is extensional equality,
+            // so that proving an equality automatically uses the extensionality axioms
+            let args = Arc::new(vec![lhs.clone(), rhs.clone()]);
+            let ext_equal = match &*lhs.typ {
+                TypX::Seq(_) | TypX::Array(..) => ExprX::SeqOp(SeqOp::ExtEqual, args),
+                TypX::Set(_) => ExprX::SetOp(SetOp::ExtEqual, args),
+                TypX::Map(..) => ExprX::MapOp(MapOp::ExtEqual, args),
+                _ => return Ok(expr.clone()),
+            };
+            let ext_equal = SpannedTyped::new(&expr.span, &expr.typ, ext_equal);
+            match op {
+                BinaryOp::Ne => {
+                    let not = ExprX::Unary(UnaryOp::Not, ext_equal);
+                    Ok(SpannedTyped::new(&expr.span, &expr.typ, not))
+                }
+                _ => Ok(ext_equal),
+            }
+        }
         ExprX::Match(expr0, arms1) => {
             let (temp_decl, expr0) = small_or_temp(state, &expr0);
             // Translate into If expression
@@ -301,7 +320,8 @@ fn simplify_datatype(state: &mut State, datatype: &Datatype) -> Result<Datatype,
 }
 
 pub fn simplify_krate(ctx: &mut GlobalCtx, krate: &Krate) -> Result<Krate, VirErr> {
-    let KrateX { functions, datatypes, module_ids } = &**krate;
+    let krate = crate::traits::inherit_trait_specs(krate)?;
+    let KrateX { functions, datatypes, module_ids } = &*krate;
     let mut state = State::new();
     let functions = vec_map_result(functions, |f| simplify_function(ctx, &mut state, f))?;
     let mut datatypes = vec_map_result(&datatypes, |d| simplify_datatype(&mut state, d))?;
@@ -311,7 +331,9 @@ pub fn simplify_krate(ctx: &mut GlobalCtx, krate: &Krate) -> Result<Krate, VirEr
         let path = path.clone();
         let visibility = Visibility { owning_module: None, is_private: false };
         let transparency = DatatypeTransparency::Always;
-        let typ_params = Arc::new((0..arity).map(|i| prefix_tuple_param(i)).collect());
+        let bound = Arc::new(GenericBoundX::Traits(vec![]));
+        let typ_params =
+            Arc::new((0..arity).map(|i| (prefix_tuple_param(i), bound.clone())).collect());
         let mut fields: Vec<Field> = Vec::new();
         for i in 0..arity {
             let typ = Arc::new(TypX::TypParam(prefix_tuple_param(i)));
@@ -326,6 +348,6 @@ pub fn simplify_krate(ctx: &mut GlobalCtx, krate: &Krate) -> Result<Krate, VirEr
 
     let module_ids = module_ids.clone();
     let krate = Arc::new(KrateX { functions, datatypes, module_ids });
-    *ctx = crate::context::GlobalCtx::new(&krate, ctx.no_span.clone());
+    *ctx = crate::context::GlobalCtx::new(&krate, ctx.no_span.clone(), ctx.arch_word_bits);
     Ok(krate)
 }
@@ -1,14 +1,14 @@
 use crate::ast::{
-    BinaryOp, Constant, Expr, ExprX, Function, Ident, Mode, Params, Path, PatternX, Stmt, StmtX,
-    Typ, Typs, UnaryOp, UnaryOpr, VirErr,
+    BinaryOp, Constant, Expr, ExprX, Function, Ident, Idents, IntRange, Mode, Params, Path,
+    PatternX, SeqOp, SpannedTyped, Stmt, StmtX, Typ, TypX, Typs, UnaryOp, UnaryOpr, VarAt, VirErr,
 };
 use crate::ast_util::{err_str, err_string};
 use crate::context::Ctx;
-use crate::def::Spanned;
+use crate::def::{check_decrease_int, prefix_pre_var, Spanned};
 use crate::sst::{Bnd, BndX, Dest, Exp, ExpX, LocalDecl, LocalDeclX, Stm, StmX, UniqueIdent};
-use crate::sst_visitor::{map_exp_visitor, map_stm_exp_visitor};
+use crate::sst_visitor::{map_exp_visitor, map_exp_visitor_bind, map_stm_exp_visitor};
 use crate::util::{vec_map, vec_map_result};
-use air::ast::{Binder, BinderX, Binders, Span};
+use air::ast::{Binder, BinderX, Binders, Quant, Span};
 use air::scope_map::ScopeMap;
 use std::collections::{HashMap, HashSet};
 use std::sync::Arc;
@@ -21,10 +21,17 @@ pub(crate) struct State {
     next_var: u64,
     // Collect all local variable declarations
     pub(crate) local_decls: Vec<LocalDecl>,
+    // Variable that holds the function's return value, assigned by return statements
+    pub(crate) ret_dest: Option<UniqueIdent>,
+    // Enclosing loops, innermost last: each loop's label, and whether a break targets the loop
+    loops: Vec<(Option<Ident>, bool)>,
+    // Translating spec or proof code, which needs no checks for exec-only failures like overflow
+    pub(crate) view_as_spec: bool,
     // Rename local variables when needed, using unique integers, to avoid collisions.
     // This is only needed for statement-level declarations (Some(unique_int)),
     // not for expression-level bindings (None).
-    rename_map: ScopeMap<Ident, Option<u64>>,
+    // Each variable's type is recorded too, for the free variables of choose.
+    rename_map: ScopeMap<Ident, (Option<u64>, Typ)>,
     // Next integer to use for renaming each variable
     rename_counters: HashMap<Ident, u64>,
     // Variables that we considered renaming, but ended up being Bind variables
@@ -39,6 +46,9 @@ pub fn new() -> Self {
         State {
             next_var: 0,
             local_decls: Vec::new(),
+            ret_dest: None,
+            loops: Vec::new(),
+            view_as_spec: false,
             rename_map,
             rename_counters: HashMap::new(),
             dont_rename: HashSet::new(),
@@ -62,17 +72,24 @@ pub(crate) fn pop_scope(&mut self) {
     pub(crate) fn get_var_unique_id(&self, x: &Ident) -> UniqueIdent {
         match self.rename_map.get(x) {
             None => panic!("internal error: variable not in rename_map: {}", x),
-            Some(id) => (x.clone(), *id),
+            Some((id, _)) => (x.clone(), *id),
         }
     }
 
-    pub(crate) fn new_statement_var(&mut self, x: &Ident) {
+    pub(crate) fn get_var_typ(&self, x: &Ident) -> Typ {
+        match self.rename_map.get(x) {
+            None => panic!("internal error: variable not in rename_map: {}", x),
+            Some((_, typ)) => typ.clone(),
+        }
+    }
+
+    pub(crate) fn new_statement_var(&mut self, x: &Ident, typ: &Typ) {
         self.rename_counters.insert(x.clone(), 0);
-        self.rename_map.insert(x.clone(), Some(0)).expect("new var");
+        self.rename_map.insert(x.clone(), (Some(0), typ.clone())).expect("new var");
     }
 
-    pub(crate) fn declare_expression_var(&mut self, x: &Ident) {
-        self.rename_map.insert(x.clone(), None).expect("declare var");
+    pub(crate) fn declare_expression_var(&mut self, x: &Ident, typ: &Typ) {
+        self.rename_map.insert(x.clone(), (None, typ.clone())).expect("declare var");
     }
 
     pub(crate) fn alloc_unique_var(&mut self, x: &Ident) -> UniqueIdent {
@@ -84,13 +101,13 @@ pub(crate) fn alloc_unique_var(&mut self, x: &Ident) -> UniqueIdent {
         (x.clone(), Some(i))
     }
 
-    pub(crate) fn insert_unique_var(&mut self, x: &UniqueIdent) {
-        self.rename_map.insert(x.0.clone(), x.1).expect("declare var");
+    pub(crate) fn insert_unique_var(&mut self, x: &UniqueIdent, typ: &Typ) {
+        self.rename_map.insert(x.0.clone(), (x.1, typ.clone())).expect("declare var");
     }
 
-    pub(crate) fn declare_binders<A: Clone>(&mut self, binders: &Binders<A>) {
+    pub(crate) fn declare_binders(&mut self, binders: &Binders<Typ>) {
         for binder in binders.iter() {
-            self.declare_expression_var(&binder.name);
+            self.declare_expression_var(&binder.name, &binder.a);
         }
     }
 
@@ -102,7 +119,7 @@ pub(crate) fn declare_new_var(
     ) -> UniqueIdent {
         let unique_ident = (ident.clone(), Some(0));
         let decl = LocalDeclX { ident: unique_ident.clone(), typ: typ.clone(), mutable };
-        self.new_statement_var(&ident);
+        self.new_statement_var(&ident, typ);
         self.local_decls.push(Arc::new(decl));
         unique_ident
     }
@@ -154,14 +171,39 @@ fn expr_get_call(
 ) -> Result<Option<(Vec<Stm>, Path, Typs, bool, Args)>, VirErr> {
     match &expr.x {
         ExprX::Call(x, typs, args) => {
+            let function = get_function(ctx, expr, x)?;
             let mut stms: Vec<Stm> = Vec::new();
             let mut exps: Vec<Arg> = Vec::new();
-            for arg in args.iter() {
-                let (mut stms0, e0) = expr_to_stm(ctx, state, arg)?;
-                stms.append(&mut stms0);
+            for (param, arg) in function.x.params.iter().zip(args.iter()) {
+                let e0 = match (param.x.is_mut, &arg.x) {
+                    (true, ExprX::Loc(loc)) => match &loc.x {
+                        ExprX::Var(x) => {
+                            let var = ExpX::Var(state.get_var_unique_id(&x));
+                            let var = Spanned::new(loc.span.clone(), var);
+                            Spanned::new(arg.span.clone(), ExpX::Loc(var))
+                        }
+                        _ => {
+                            return err_str(&loc.span, "&mut argument must be a local variable");
+                        }
+                    },
+                    (true, _) => {
+                        return err_str(&arg.span, "expected &mut argument for &mut parameter");
+                    }
+                    (false, _) => {
+                        // Arguments to spec parameters are spec code
+                        let view_as_spec = state.view_as_spec;
+                        state.view_as_spec |=
+                            function.x.mode != Mode::Exec || param.x.mode != Mode::Exec;
+                        let e0 = expr_to_stm(ctx, state, arg);
+                        state.view_as_spec = view_as_spec;
+                        let (mut stms0, e0) = e0?;
+                        stms.append(&mut stms0);
+                        e0
+                    }
+                };
                 exps.push((e0, arg.typ.clone()));
             }
-            let has_ret = get_function(ctx, expr, x)?.x.has_return();
+            let has_ret = function.x.has_return();
             Ok(Some((stms, x.clone(), typs.clone(), has_ret, Arc::new(exps))))
         }
         _ => Ok(None),
@@ -182,8 +224,53 @@ fn expr_must_be_call_stm(
     }
 }
 
+// Free variables of a choose body, with their types, in order of first appearance
+fn choose_captured_vars(state: &State, binder: &Ident, exp: &Exp) -> Arc<Vec<(Exp, Typ)>> {
+    let mut captured: Vec<(Exp, Typ)> = Vec::new();
+    let mut seen: HashSet<UniqueIdent> = HashSet::new();
+    let mut map: ScopeMap<Ident, bool> = ScopeMap::new();
+    map.push_scope(false);
+    map.insert(binder.clone(), true).expect("choose binder");
+    let mut f = |e: &Exp, map: &mut ScopeMap<Ident, bool>| {
+        match &e.x {
+            ExpX::Var(x) if !map.contains_key(&x.0) && seen.insert(x.clone()) => {
+                captured.push((e.clone(), state.get_var_typ(&x.0)));
+            }
+            _ => {}
+        }
+        Ok(e.clone())
+    };
+    map_exp_visitor_bind(exp, &mut map, &mut f).expect("choose_captured_vars");
+    map.pop_scope();
+    Arc::new(captured)
+}
+
+// Type parameters mentioned in expr's types, in order of first appearance
+fn expr_typ_params(expr: &Expr) -> Result<Idents, VirErr> {
+    let mut typ_params: Vec<Ident> = Vec::new();
+    crate::ast_visitor::map_expr_visitor_env(
+        expr,
+        &mut typ_params,
+        &|_, e| Ok(e.clone()),
+        &|_, s| Ok(vec![s.clone()]),
+        &|typ_params: &mut Vec<Ident>, t| {
+            if let TypX::TypParam(x) = &**t {
+                if !typ_params.contains(x) {
+                    typ_params.push(x.clone());
+                }
+            }
+            Ok(t.clone())
+        },
+    )?;
+    Ok(Arc::new(typ_params))
+}
+
 pub(crate) fn expr_to_exp_state(ctx: &Ctx, state: &mut State, expr: &Expr) -> Result<Exp, VirErr> {
-    let (stms, exp) = expr_to_stm(ctx, state, expr)?;
+    let view_as_spec = state.view_as_spec;
+    state.view_as_spec = true;
+    let result = expr_to_stm(ctx, state, expr);
+    state.view_as_spec = view_as_spec;
+    let (stms, exp) = result?;
     if stms.len() == 0 {
         Ok(exp)
     } else {
@@ -255,7 +342,7 @@ pub(crate) fn expr_to_one_stm_dest(
     match (dest, exp) {
         (None, _) => {}
         (Some(_), None) => {
-            panic!("internal error: missing return value")
+            // The body ends with a return statement, which has already assigned dest
         }
         (Some(dest), Some(exp)) => {
             stms.push(init_var(&expr.span, &dest, &exp));
@@ -268,8 +355,9 @@ fn is_small_exp(exp: &Exp) -> bool {
     match &exp.x {
         ExpX::Const(_) => true,
         ExpX::Var(..) => true,
+        ExpX::Loc(..) => true,
         ExpX::Old(..) => true,
-        ExpX::Unary(UnaryOp::Not | UnaryOp::Clip(_), e) => is_small_exp(e),
+        ExpX::Unary(UnaryOp::Not | UnaryOp::Clip { .. }, e) => is_small_exp(e),
         ExpX::UnaryOpr(UnaryOpr::Box(_) | UnaryOpr::Unbox(_), e) => is_small_exp(e),
         _ => false,
     }
@@ -302,22 +390,87 @@ fn stm_call(
     stms_to_one_stm(span, stms)
 }
 
+const OVERFLOW_ERROR: &str = "possible arithmetic overflow";
+const CAST_ERROR: &str = "possible truncation in cast (use truncate(e) to allow the cast to wrap)";
+const LITERAL_ERROR: &str = "integer literal out of range";
+
+// Exec arithmetic panics if the result doesn't fit in the result type,
+// and exec casts must be lossless unless written as truncate(e),
+// so check that clipping exp to range leaves it unchanged
+fn check_overflow(span: &Span, range: IntRange, exp: &Exp, description: &str) -> Stm {
+    let clip = ExpX::Unary(UnaryOp::Clip { range, truncate: false }, exp.clone());
+    let clip = Spanned::new(span.clone(), clip);
+    let eq = ExpX::Binary(BinaryOp::Eq(Mode::Spec), clip, exp.clone());
+    let eq = Spanned::new(span.clone(), eq);
+    let span = Span { description: Some(description.to_string()), ..span.clone() };
+    Spanned::new(span, StmX::Assert(eq))
+}
+
+// Rust panics on MIN % -1 for signed integers (and on MIN / -1, which check_overflow catches),
+// so check that the quotient fits in the result type
+fn check_mod_overflow(span: &Span, typ: &Typ, e1: &Exp, e2: &Exp) -> Stm {
+    let range = match &**typ {
+        TypX::Int(range) => *range,
+        _ => panic!("internal error: expected integer type for division"),
+    };
+    let div = ExpX::Binary(BinaryOp::TruncDiv, e1.clone(), e2.clone());
+    check_overflow(span, range, &Spanned::new(span.clone(), div), OVERFLOW_ERROR)
+}
+
+fn check_shift_amount(ctx: &Ctx, span: &Span, range: IntRange, amount: &Exp) -> Stm {
+    let bits = match range {
+        IntRange::U(bits) | IntRange::I(bits) => bits,
+        IntRange::USize | IntRange::ISize => ctx.global.arch_word_bits,
+        IntRange::Int | IntRange::Nat => panic!("internal error: shift of unbounded integer"),
+    };
+    let zero = Spanned::new(span.clone(), ExpX::Const(Constant::Nat(Arc::new("0".to_string()))));
+    let bits = Spanned::new(span.clone(), ExpX::Const(Constant::Nat(Arc::new(bits.to_string()))));
+    let lo = Spanned::new(span.clone(), ExpX::Binary(BinaryOp::Le, zero, amount.clone()));
+    let hi = Spanned::new(span.clone(), ExpX::Binary(BinaryOp::Lt, amount.clone(), bits));
+    let in_range = Spanned::new(span.clone(), ExpX::Binary(BinaryOp::And, lo, hi));
+    let span = Span { description: Some("possible shift overflow".to_string()), ..span.clone() };
+    Spanned::new(span, StmX::Assert(in_range))
+}
+
+fn check_div_by_zero(span: &Span, e2: &Exp) -> Stm {
+    let zero = Spanned::new(span.clone(), ExpX::Const(Constant::Nat(Arc::new("0".to_string()))));
+    let ne = Spanned::new(span.clone(), ExpX::Binary(BinaryOp::Ne, e2.clone(), zero));
+    let span = Span { description: Some("possible division by zero".to_string()), ..span.clone() };
+    Spanned::new(span, StmX::Assert(ne))
+}
+
+fn check_index_bounds(span: &Span, seq: &Exp, index: &Exp) -> Stm {
+    let zero = Spanned::new(span.clone(), ExpX::Const(Constant::Nat(Arc::new("0".to_string()))));
+    let len = Spanned::new(span.clone(), ExpX::SeqOp(SeqOp::Len, Arc::new(vec![seq.clone()])));
+    let lo = Spanned::new(span.clone(), ExpX::Binary(BinaryOp::Le, zero, index.clone()));
+    let hi = Spanned::new(span.clone(), ExpX::Binary(BinaryOp::Lt, index.clone(), len));
+    let in_bounds = Spanned::new(span.clone(), ExpX::Binary(BinaryOp::And, lo, hi));
+    let span =
+        Span { description: Some("possible index out of bounds".to_string()), ..span.clone() };
+    Spanned::new(span, StmX::Assert(in_bounds))
+}
+
 fn if_to_stm(
     state: &mut State,
     expr: &Expr,
     stms0: &mut Vec<Stm>,
     e0: Exp,
     mut stms1: Vec<Stm>,
-    e1: &Exp,
+    e1: Option<&Exp>,
     mut stms2: Vec<Stm>,
-    e2: &Exp,
+    e2: Option<&Exp>,
 ) -> Exp {
     // If statement, put results from e1/e2 in a temp variable, return temp variable
+    // (a branch with no result ends in a return statement, so it leaves temp unassigned)
     let (temp, temp_var) = state.next_temp(&expr.span);
     let temp_id = state.declare_new_var(&temp, &expr.typ, false);
     // if e0 { stms1; temp = e1; } else { stms2; temp = e2; }
-    stms1.push(init_var(&expr.span, &temp_id, &e1));
-    stms2.push(init_var(&expr.span, &temp_id, &e2));
+    if let Some(e1) = e1 {
+        stms1.push(init_var(&expr.span, &temp_id, e1));
+    }
+    if let Some(e2) = e2 {
+        stms2.push(init_var(&expr.span, &temp_id, e2));
+    }
     let stm1 = stms_to_one_stm(&expr.span, stms1);
     let stm2 = stms_to_one_stm(&expr.span, stms2);
     let if_stmt = StmX::If(e0, stm1, Some(stm2));
@@ -339,6 +492,20 @@ pub(crate) fn expr_to_stm_opt(
             let unique_id = state.get_var_unique_id(&x);
             Ok((vec![], Some(Spanned::new(expr.span.clone(), ExpX::Var(unique_id)))))
         }
+        ExprX::VarAt(x, VarAt::Pre) => {
+            // A &mut parameter's entry value is kept in a separate variable, if one is in scope;
+            // any other parameter (or a &mut parameter in a requires clause) still holds it
+            let pre = prefix_pre_var(x);
+            let unique_id = if state.rename_map.contains_key(&pre) {
+                (pre, Some(0))
+            } else {
+                (x.clone(), Some(0))
+            };
+            Ok((vec![], Some(Spanned::new(expr.span.clone(), ExpX::Var(unique_id)))))
+        }
+        ExprX::Loc(_) => {
+            err_str(&expr.span, "&mut borrows are only supported as arguments to &mut parameters")
+        }
         ExprX::Assign(expr1, expr2) => {
             let dest_x = match &expr1.x {
                 ExprX::Var(x) => Ok(state.get_var_unique_id(&x)),
@@ -393,9 +560,52 @@ pub(crate) fn expr_to_stm_opt(
             let ctor = ExpX::Ctor(p.clone(), i.clone(), Arc::new(args));
             Ok((stms, Some(Spanned::new(expr.span.clone(), ctor))))
         }
-        ExprX::Unary(op, expr) => {
-            let (stms, exp) = expr_to_stm(ctx, state, expr)?;
-            Ok((stms, Some(Spanned::new(expr.span.clone(), ExpX::Unary(*op, exp)))))
+        ExprX::Unary(op, e1) => {
+            let (mut stms, exp) = expr_to_stm(ctx, state, e1)?;
+            match (op, state.view_as_spec, &e1.x) {
+                (UnaryOp::Clip { range: IntRange::Int | IntRange::Nat, .. }, _, _)
+                | (UnaryOp::Clip { truncate: true, .. }, _, _)
+                | (UnaryOp::Clip { .. }, true, _) => {}
+                // Bitwise operators never overflow; they are clipped only to keep them in range
+                (UnaryOp::Clip { .. }, false, ExprX::Unary(UnaryOp::BitNot, _)) => {}
+                (
+                    UnaryOp::Clip { .. },
+                    false,
+                    ExprX::Binary(BinaryOp::BitAnd | BinaryOp::BitOr | BinaryOp::BitXor, _, _),
+                ) => {}
+                // Shifts panic if the shift amount is not less than the width of the lhs;
+                // otherwise, as in Rust, bits shifted out of the result are discarded (Shl wraps)
+                (
+                    UnaryOp::Clip { range, .. },
+                    false,
+                    ExprX::Binary(BinaryOp::Shl | BinaryOp::Shr, _, _),
+                ) => match &exp.x {
+                    ExpX::Binary(_, _, amount) => {
+                        stms.push(check_shift_amount(ctx, &expr.span, *range, amount));
+                    }
+                    _ => panic!("internal error: expected shift"),
+                },
+                (
+                    UnaryOp::Clip { range, .. },
+                    false,
+                    ExprX::Binary(
+                        BinaryOp::Add | BinaryOp::Sub | BinaryOp::Mul | BinaryOp::TruncDiv,
+                        _,
+                        _,
+                    ),
+                ) => {
+                    stms.push(check_overflow(&expr.span, *range, &exp, OVERFLOW_ERROR));
+                }
+                (UnaryOp::Clip { range, .. }, false, ExprX::Const(_)) => {
+                    stms.push(check_overflow(&expr.span, *range, &exp, LITERAL_ERROR));
+                }
+                // Casts
+                (UnaryOp::Clip { range, .. }, false, _) => {
+                    stms.push(check_overflow(&expr.span, *range, &exp, CAST_ERROR));
+                }
+                _ => {}
+            }
+            Ok((stms, Some(Spanned::new(e1.span.clone(), ExpX::Unary(*op, exp)))))
         }
         ExprX::UnaryOpr(op, expr) => {
             let (stms, exp) = expr_to_stm(ctx, state, expr)?;
@@ -421,22 +631,76 @@ pub(crate) fn expr_to_stm_opt(
                     let bx = ExpX::Const(Constant::Bool(other));
                     let b = Spanned::new(expr.span.clone(), bx);
                     if proceed_on {
-                        let temp_var =
-                            if_to_stm(state, expr, &mut stms1, e1, stms2, &e2, vec![], &b);
+                        let temp_var = if_to_stm(
+                            state,
+                            expr,
+                            &mut stms1,
+                            e1,
+                            stms2,
+                            Some(&e2),
+                            vec![],
+                            Some(&b),
+                        );
                         temp_var
                     } else {
-                        let temp_var =
-                            if_to_stm(state, expr, &mut stms1, e1, vec![], &b, stms2, &e2);
+                        let temp_var = if_to_stm(
+                            state,
+                            expr,
+                            &mut stms1,
+                            e1,
+                            vec![],
+                            Some(&b),
+                            stms2,
+                            Some(&e2),
+                        );
                         temp_var
                     }
                 }
                 _ => {
                     stms1.append(&mut stms2);
+                    if !state.view_as_spec {
+                        match op {
+                            BinaryOp::EuclideanDiv
+                            | BinaryOp::EuclideanMod
+                            | BinaryOp::TruncDiv
+                            | BinaryOp::TruncMod => {
+                                stms1.push(check_div_by_zero(&expr.span, &e2));
+                            }
+                            _ => {}
+                        }
+                        if let BinaryOp::TruncMod = op {
+                            stms1.push(check_mod_overflow(&expr.span, &expr.typ, &e1, &e2));
+                        }
+                    }
                     Spanned::new(expr.span.clone(), ExpX::Binary(*op, e1, e2))
                 }
             };
             Ok((stms1, Some(bin)))
         }
+        ExprX::SeqOp(op, es) => {
+            let mut stms: Vec<Stm> = Vec::new();
+            let mut exps: Vec<Exp> = Vec::new();
+            for e in es.iter() {
+                let (mut stms1, exp) = expr_to_stm(ctx, state, e)?;
+                stms.append(&mut stms1);
+                exps.push(exp);
+            }
+            if let (SeqOp::Index | SeqOp::Update, false) = (op, state.view_as_spec) {
+                stms.push(check_index_bounds(&expr.span, &exps[0], &exps[1]));
+            }
+            let seq_op = ExpX::SeqOp(*op, Arc::new(exps));
+            Ok((stms, Some(Spanned::new(expr.span.clone(), seq_op))))
+        }
+        ExprX::SetOp(_, es) | ExprX::MapOp(_, es) => {
+            // Set and map operations are spec only, so they have no statements
+            let exps = vec_map_result(es, |e| expr_to_exp_state(ctx, state, e))?;
+            let exp = match &expr.x {
+                ExprX::SetOp(op, _) => ExpX::SetOp(*op, Arc::new(exps)),
+                ExprX::MapOp(op, _) => ExpX::MapOp(*op, Arc::new(exps)),
+                _ => unreachable!(),
+            };
+            Ok((vec![], Some(Spanned::new(expr.span.clone(), exp))))
+        }
         ExprX::Quant(quant, binders, body) => {
             state.push_scope();
             state.declare_binders(binders);
@@ -447,6 +711,19 @@ pub(crate) fn expr_to_stm_opt(
             let bnd = Spanned::new(body.span.clone(), BndX::Quant(*quant, binders.clone(), trigs));
             Ok((vec![], Some(Spanned::new(expr.span.clone(), ExpX::Bind(bnd, exp)))))
         }
+        ExprX::Choose(binder, body) => {
+            state.push_scope();
+            state.declare_expression_var(&binder.name, &binder.a);
+            let exp = expr_to_exp_state(ctx, state, body)?;
+            state.pop_scope();
+            let vars = vec![binder.name.clone()];
+            let trigs = crate::triggers::build_triggers(ctx, &expr.span, &vars, &exp)?;
+            let captured = choose_captured_vars(state, &binder.name, &exp);
+            let typ_params = expr_typ_params(expr)?;
+            let bndx = BndX::Choose(binder.clone(), trigs, captured, typ_params);
+            let bnd = Spanned::new(body.span.clone(), bndx);
+            Ok((vec![], Some(Spanned::new(expr.span.clone(), ExpX::Bind(bnd, exp)))))
+        }
         ExprX::If(e0, e1, None) => {
             let (mut stms0, e0) = expr_to_stm(ctx, state, e0)?;
             let stms1 = expr_to_one_stm(ctx, state, e1)?;
@@ -462,43 +739,89 @@ pub(crate) fn expr_to_stm_opt(
                     // If expression
                     Ok((stms0, Some(Spanned::new(expr.span.clone(), ExpX::If(e0, e1, e2)))))
                 }
-                (Some(e1), Some(e2)) => {
-                    // If statement, put results from e1/e2 in a temp variable, return temp variable
-                    let temp_var = if_to_stm(state, expr, &mut stms0, e0, stms1, &e1, stms2, &e2);
-                    Ok((stms0, Some(temp_var)))
-                }
-                _ => {
+                (None, None) => {
                     // If statement, let expression be None
                     let stm1 = stms_to_one_stm(&expr1.span, stms1);
                     let stm2 = stms_to_one_stm(&expr2.span, stms2);
                     stms0.push(Spanned::new(expr.span.clone(), StmX::If(e0, stm1, Some(stm2))));
                     Ok((stms0, None))
                 }
+                (e1, e2) => {
+                    // If statement, put results from e1/e2 in a temp variable, return temp variable
+                    let (e1, e2) = (e1.as_ref(), e2.as_ref());
+                    let temp_var = if_to_stm(state, expr, &mut stms0, e0, stms1, e1, stms2, e2);
+                    Ok((stms0, Some(temp_var)))
+                }
             }
         }
         ExprX::Match(..) => {
             panic!("internal error: Match should have been simplified by ast_simplify")
         }
-        ExprX::While { cond, body, invs } => {
-            let (stms0, e0) = expr_to_stm(ctx, state, cond)?;
-            if stms0.len() != 0 {
-                // TODO:
-                return err_str(&cond.span, "not yet implemented: complex while loop conditions");
-            }
-            let (stms1, e1) = expr_to_stm_opt(ctx, state, body)?;
+        ExprX::Loop { label, cond, body, invs, enss, decrease } => {
+            let cond = match cond {
+                None => None,
+                Some(cond) => {
+                    let (stms0, e0) = expr_to_stm(ctx, state, cond)?;
+                    if stms0.len() != 0 {
+                        // TODO:
+                        return err_str(
+                            &cond.span,
+                            "not yet implemented: complex while loop conditions",
+                        );
+                    }
+                    Some(e0)
+                }
+            };
+            let mut stms1: Vec<Stm> = Vec::new();
+            let decrease = match decrease {
+                None => None,
+                Some(dec) => {
+                    if !matches!(&*dec.typ, TypX::Int(_)) {
+                        return err_str(&dec.span, "loop decreases must have integer type");
+                    }
+                    // Record the measure at the start of each iteration,
+                    // then check: 0 <= decrease_exp < decreases_at_start
+                    let exp = expr_to_exp_state(ctx, state, dec)?;
+                    let (temp, temp_var) = state.next_temp(&dec.span);
+                    let int_typ = Arc::new(TypX::Int(IntRange::Int));
+                    let temp_id = state.declare_new_var(&temp, &int_typ, false);
+                    stms1.push(init_var(&dec.span, &temp_id, &exp));
+                    let args = Arc::new(vec![exp, temp_var]);
+                    let call = ExpX::Call(check_decrease_int(), Arc::new(vec![]), args);
+                    Some(Spanned::new(dec.span.clone(), call))
+                }
+            };
+            state.loops.push((label.clone(), false));
+            let (mut stms_body, e1) = expr_to_stm_opt(ctx, state, body)?;
+            let (_, has_break) = state.loops.pop().expect("loops");
+            stms1.append(&mut stms_body);
             check_no_exp(&e1)?;
             let invs = Arc::new(vec_map_result(invs, |e| expr_to_exp_state(ctx, state, e))?);
-            let while_stm = Spanned::new(
+            let enss = Arc::new(vec_map_result(enss, |e| expr_to_exp_state(ctx, state, e))?);
+            let loop_stm = Spanned::new(
                 expr.span.clone(),
-                StmX::While {
-                    cond: e0,
+                StmX::Loop {
+                    label: label.clone(),
+                    cond,
                     body: stms_to_one_stm(&body.span, stms1),
                     invs,
+                    enss,
+                    has_break,
+                    decrease,
                     typ_inv_vars: Arc::new(vec![]),
                     modified_vars: Arc::new(vec![]),
                 },
             );
-            Ok((vec![while_stm], None))
+            Ok((vec![loop_stm], None))
+        }
+        ExprX::BreakOrContinue { label, is_break } => {
+            let target = state.loops.iter_mut().rev().find(|(l, _)| label.is_none() || l == label);
+            match target {
+                None => return err_str(&expr.span, "break/continue must be inside a loop"),
+                Some((_, has_break)) => *has_break = *has_break || *is_break,
+            }
+            let stm = StmX::BreakOrContinue { label: label.clone(), is_break: *is_break };
+            Ok((vec![Spanned::new(expr.span.clone(), stm)], None))
         }
         ExprX::Block(stmts, body_opt) => {
             let mut stms: Vec<Stm> = Vec::new();
@@ -511,7 +834,7 @@ pub(crate) fn expr_to_stm_opt(
                     Some((decl, bnd)) => {
                         state.push_scope();
                         local_decls.push(decl.clone());
-                        state.insert_unique_var(&decl.ident);
+                        state.insert_unique_var(&decl.ident, &decl.typ);
                         match bnd {
                             None => {
                                 is_pure_exp = false;
@@ -572,6 +895,84 @@ pub(crate) fn expr_to_stm_opt(
             let stm = Spanned::new(expr.span.clone(), StmX::Assume(exp));
             Ok((vec![stm], None))
         }
+        ExprX::AssertBV(e) => {
+            crate::bitvector_to_air::check_bit_vector_expr(e)?;
+            let exp = expr_to_exp_state(ctx, state, e)?;
+            let stm = Spanned::new(expr.span.clone(), StmX::AssertBV(exp));
+            Ok((vec![stm], None))
+        }
+        ExprX::AssertBy { vars, require, ensure, proof } => {
+            // Check the proof with fresh local variables standing for the forall vars
+            state.push_scope();
+            let mut unique_vars: Vec<(UniqueIdent, Typ)> = Vec::new();
+            for var in vars.iter() {
+                let x = state.alloc_unique_var(&var.name);
+                state.insert_unique_var(&x, &var.a);
+                let decl = LocalDeclX { ident: x.clone(), typ: var.a.clone(), mutable: false };
+                state.local_decls.push(Arc::new(decl));
+                unique_vars.push((x, var.a.clone()));
+            }
+            let require_exp = expr_to_exp_state(ctx, state, require)?;
+            let ensure_exp = expr_to_exp_state(ctx, state, ensure)?;
+            let view_as_spec = state.view_as_spec;
+            state.view_as_spec = true;
+            let proof_stm = expr_to_one_stm(ctx, state, proof);
+            state.view_as_spec = view_as_spec;
+            let proof_stm = proof_stm?;
+            state.pop_scope();
+            let assert_by = StmX::AssertBy {
+                vars: Arc::new(unique_vars),
+                require: require_exp,
+                ensure: ensure_exp,
+                proof: proof_stm,
+            };
+            let assert_by = Spanned::new(expr.span.clone(), assert_by);
+
+            // Afterwards, only forall vars. require ==> ensure is visible
+            let bool_typ = Arc::new(TypX::Bool);
+            let implyx = ExprX::Binary(BinaryOp::Implies, require.clone(), ensure.clone());
+            let mut export = SpannedTyped::new(&expr.span, &bool_typ, implyx);
+            if vars.len() > 0 {
+                let forallx = ExprX::Quant(Quant::Forall, vars.clone(), export);
+                export = SpannedTyped::new(&expr.span, &bool_typ, forallx);
+            }
+            let export = expr_to_exp_state(ctx, state, &export)?;
+            let assume = Spanned::new(expr.span.clone(), StmX::Assume(export));
+            Ok((vec![assert_by, assume], None))
+        }
+        ExprX::AssertNonlinear { requires, ensures, proof } => {
+            let requires = vec_map_result(requires, |e| expr_to_exp_state(ctx, state, e))?;
+            let ensures = vec_map_result(ensures, |e| expr_to_exp_state(ctx, state, e))?;
+            state.push_scope();
+            let view_as_spec = state.view_as_spec;
+            state.view_as_spec = true;
+            let proof = expr_to_one_stm(ctx, state, proof);
+            state.view_as_spec = view_as_spec;
+            let proof = proof?;
+            state.pop_scope();
+            let requires = Arc::new(requires);
+            let ensures = Arc::new(ensures);
+            let stm = StmX::AssertNonlinear { requires, ensures, proof };
+            Ok((vec![Spanned::new(expr.span.clone(), stm)], None))
+        }
+        ExprX::Return(e1) => {
+            let mut stms: Vec<Stm> = Vec::new();
+            match (e1, state.ret_dest.clone()) {
+                (None, _) => {}
+                (Some(e1), None) => {
+                    let (mut stms1, e1) = expr_to_stm_opt(ctx, state, e1)?;
+                    stms.append(&mut stms1);
+                    check_no_exp(&e1)?;
+                }
+                (Some(e1), Some(dest)) => {
+                    let (mut stms1, e1) = expr_to_stm(ctx, state, e1)?;
+                    stms.append(&mut stms1);
+                    stms.push(init_var(&expr.span, &dest, &e1));
+                }
+            }
+            stms.push(Spanned::new(expr.span.clone(), StmX::Return));
+            Ok((stms, None))
+        }
         _ => {
             todo!("{}", expr.span.as_string)
         }
@@ -39,6 +39,10 @@ pub fn types_equal(typ1: &Typ, typ2: &Typ) -> bool {
                 && typs1.len() == typs2.len()
                 && typs1.iter().zip(typs2.iter()).all(|(t1, t2)| types_equal(t1, t2))
         }
+        (TypX::Seq(t1), TypX::Seq(t2)) => types_equal(t1, t2),
+        (TypX::Array(t1, len1), TypX::Array(t2, len2)) => len1 == len2 && types_equal(t1, t2),
+        (TypX::Set(t1), TypX::Set(t2)) => types_equal(t1, t2),
+        (TypX::Map(k1, v1), TypX::Map(k2, v2)) => types_equal(k1, k2) && types_equal(v1, v2),
         (TypX::Boxed(t1), TypX::Boxed(t2)) => types_equal(t1, t2),
         (TypX::TypParam(x1), TypX::TypParam(x2)) => x1 == x2,
         _ => false,
@@ -22,6 +22,23 @@ pub(crate) fn map_typ_visitor_env<E, FT>(typ: &Typ, env: &mut E, ft: &FT) -> Res
             let ts = vec_map_result(&**ts, |t| (map_typ_visitor_env(t, env, ft)))?;
             ft(env, &Arc::new(TypX::Datatype(path.clone(), Arc::new(ts))))
         }
+        TypX::Seq(t) => {
+            let t = map_typ_visitor_env(t, env, ft)?;
+            ft(env, &Arc::new(TypX::Seq(t)))
+        }
+        TypX::Array(t, len) => {
+            let t = map_typ_visitor_env(t, env, ft)?;
+            ft(env, &Arc::new(TypX::Array(t, *len)))
+        }
+        TypX::Set(t) => {
+            let t = map_typ_visitor_env(t, env, ft)?;
+            ft(env, &Arc::new(TypX::Set(t)))
+        }
+        TypX::Map(tk, tv) => {
+            let tk = map_typ_visitor_env(tk, env, ft)?;
+            let tv = map_typ_visitor_env(tv, env, ft)?;
+            ft(env, &Arc::new(TypX::Map(tk, tv)))
+        }
         TypX::Boxed(t) => {
             let t = map_typ_visitor_env(t, env, ft)?;
             ft(env, &Arc::new(TypX::Boxed(t)))
@@ -69,6 +86,11 @@ pub(crate) fn map_expr_visitor_env<E, FE, FS, FT>(
     let exprx = match &expr.x {
         ExprX::Const(c) => ExprX::Const(c.clone()),
         ExprX::Var(x) => ExprX::Var(x.clone()),
+        ExprX::VarAt(x, at) => ExprX::VarAt(x.clone(), *at),
+        ExprX::Loc(e1) => {
+            let expr1 = map_expr_visitor_env(e1, env, fe, fs, ft)?;
+            ExprX::Loc(expr1)
+        }
         ExprX::Call(x, typs, es) => {
             let typs = vec_map_result(&**typs, |t| (map_typ_visitor_env(t, env, ft)))?;
             let mut exprs: Vec<Expr> = Vec::new();
@@ -109,12 +131,38 @@ pub(crate) fn map_expr_visitor_env<E, FE, FS, FT>(
             let expr2 = map_expr_visitor_env(e2, env, fe, fs, ft)?;
             ExprX::Binary(*op, expr1, expr2)
         }
+        ExprX::SeqOp(op, es) => {
+            let mut exprs: Vec<Expr> = Vec::new();
+            for e in es.iter() {
+                exprs.push(map_expr_visitor_env(e, env, fe, fs, ft)?);
+            }
+            ExprX::SeqOp(*op, Arc::new(exprs))
+        }
+        ExprX::SetOp(op, es) => {
+            let mut exprs: Vec<Expr> = Vec::new();
+            for e in es.iter() {
+                exprs.push(map_expr_visitor_env(e, env, fe, fs, ft)?);
+            }
+            ExprX::SetOp(*op, Arc::new(exprs))
+        }
+        ExprX::MapOp(op, es) => {
+            let mut exprs: Vec<Expr> = Vec::new();
+            for e in es.iter() {
+                exprs.push(map_expr_visitor_env(e, env, fe, fs, ft)?);
+            }
+            ExprX::MapOp(*op, Arc::new(exprs))
+        }
         ExprX::Quant(quant, binders, e1) => {
             let binders =
                 vec_map_result(&**binders, |b| b.map_result(|t| map_typ_visitor_env(t, env, ft)))?;
             let expr1 = map_expr_visitor_env(e1, env, fe, fs, ft)?;
             ExprX::Quant(*quant, Arc::new(binders), expr1)
         }
+        ExprX::Choose(binder, e1) => {
+            let binder = binder.map_result(|t| map_typ_visitor_env(t, env, ft))?;
+            let expr1 = map_expr_visitor_env(e1, env, fe, fs, ft)?;
+            ExprX::Choose(binder, expr1)
+        }
         ExprX::Assign(e1, e2) => {
             let expr1 = map_expr_visitor_env(e1, env, fe, fs, ft)?;
             let expr2 = map_expr_visitor_env(e2, env, fe, fs, ft)?;
@@ -123,6 +171,25 @@ pub(crate) fn map_expr_visitor_env<E, FE, FS, FT>(
         ExprX::Fuel(path, fuel) => ExprX::Fuel(path.clone(), *fuel),
         ExprX::Header(_) => panic!("internal error: Header shouldn't exist here"),
         ExprX::Admit => ExprX::Admit,
+        ExprX::AssertBV(e1) => ExprX::AssertBV(map_expr_visitor_env(e1, env, fe, fs, ft)?),
+        ExprX::AssertBy { vars, require, ensure, proof } => {
+            let vars =
+                vec_map_result(&**vars, |b| b.map_result(|t| map_typ_visitor_env(t, env, ft)))?;
+            let require = map_expr_visitor_env(require, env, fe, fs, ft)?;
+            let ensure = map_expr_visitor_env(ensure, env, fe, fs, ft)?;
+            let proof = map_expr_visitor_env(proof, env, fe, fs, ft)?;
+            ExprX::AssertBy { vars: Arc::new(vars), require, ensure, proof }
+        }
+        ExprX::AssertNonlinear { requires, ensures, proof } => {
+            let requires = vec_map_result(requires, |e| map_expr_visitor_env(e, env, fe, fs, ft))?;
+            let ensures = vec_map_result(ensures, |e| map_expr_visitor_env(e, env, fe, fs, ft))?;
+            let proof = map_expr_visitor_env(proof, env, fe, fs, ft)?;
+            ExprX::AssertNonlinear {
+                requires: Arc::new(requires),
+                ensures: Arc::new(ensures),
+                proof,
+            }
+        }
         ExprX::If(e1, e2, e3) => {
             let expr1 = map_expr_visitor_env(e1, env, fe, fs, ft)?;
             let expr2 = map_expr_visitor_env(e2, env, fe, fs, ft)?;
@@ -140,12 +207,20 @@ pub(crate) fn map_expr_visitor_env<E, FE, FS, FT>(
             });
             ExprX::Match(expr1, Arc::new(arms?))
         }
-        ExprX::While { cond, body, invs } => {
-            let cond = map_expr_visitor_env(cond, env, fe, fs, ft)?;
+        ExprX::Loop { label, cond, body, invs, enss, decrease } => {
+            let cond =
+                cond.as_ref().map(|e| map_expr_visitor_env(e, env, fe, fs, ft)).transpose()?;
             let body = map_expr_visitor_env(body, env, fe, fs, ft)?;
             let invs =
                 Arc::new(vec_map_result(invs, |e| map_expr_visitor_env(e, env, fe, fs, ft))?);
-            ExprX::While { cond, body, invs }
+            let enss =
+                Arc::new(vec_map_result(enss, |e| map_expr_visitor_env(e, env, fe, fs, ft))?);
+            let decrease =
+                decrease.as_ref().map(|e| map_expr_visitor_env(e, env, fe, fs, ft)).transpose()?;
+            ExprX::Loop { label: label.clone(), cond, body, invs, enss, decrease }
+        }
+        ExprX::BreakOrContinue { label, is_break } => {
+            ExprX::BreakOrContinue { label: label.clone(), is_break: *is_break }
         }
         ExprX::Block(ss, e1) => {
             let mut stmts: Vec<Stmt> = Vec::new();
@@ -158,6 +233,11 @@ pub(crate) fn map_expr_visitor_env<E, FE, FS, FT>(
             };
             ExprX::Block(Arc::new(stmts), expr1)
         }
+        ExprX::Return(e1) => {
+            let expr1 =
+                e1.as_ref().map(|e| map_expr_visitor_env(e, env, fe, fs, ft)).transpose()?;
+            ExprX::Return(expr1)
+        }
     };
     let expr = SpannedTyped::new(&expr.span, &map_typ_visitor_env(&expr.typ, env, ft)?, exprx);
     fe(env, &expr)
@@ -202,7 +282,8 @@ pub(crate) fn map_param_visitor<E, FT>(param: &Param, env: &mut E, ft: &FT) -> R
     FT: Fn(&mut E, &Typ) -> Result<Typ, VirErr>,
 {
     let typ = map_typ_visitor_env(&param.x.typ, env, ft)?;
-    let paramx = ParamX { name: param.x.name.clone(), typ, mode: param.x.mode };
+    let paramx =
+        ParamX { name: param.x.name.clone(), typ, mode: param.x.mode, is_mut: param.x.is_mut };
     Ok(Spanned::new(param.span.clone(), paramx))
 }
 
@@ -220,6 +301,7 @@ pub(crate) fn map_function_visitor_env<E, FE, FS, FT>(
 {
     let FunctionX {
         path,
+        kind,
         visibility,
         mode,
         fuel,
@@ -232,9 +314,15 @@ pub(crate) fn map_function_visitor_env<E, FE, FS, FT>(
         custom_req_err,
         hidden,
         is_abstract,
+        bit_vector,
+        nonlinear,
+        broadcast,
+        rlimit,
+        smt_options,
         body,
     } = &function.x;
     let path = path.clone();
+    let kind = kind.clone();
     let visibility = visibility.clone();
     let mode = *mode;
     let fuel = *fuel;
@@ -244,13 +332,19 @@ pub(crate) fn map_function_visitor_env<E, FE, FS, FT>(
     let require = Arc::new(vec_map_result(require, |e| map_expr_visitor_env(e, env, fe, fs, ft))?);
     let ensure = Arc::new(vec_map_result(ensure, |e| map_expr_visitor_env(e, env, fe, fs, ft))?);
     let decrease =
-        decrease.as_ref().map(|e| map_expr_visitor_env(e, env, fe, fs, ft)).transpose()?;
+        Arc::new(vec_map_result(decrease, |e| map_expr_visitor_env(e, env, fe, fs, ft))?);
     let custom_req_err = custom_req_err.clone();
     let hidden = hidden.clone();
     let is_abstract = *is_abstract;
+    let bit_vector = *bit_vector;
+    let nonlinear = *nonlinear;
+    let broadcast = *broadcast;
+    let rlimit = *rlimit;
+    let smt_options = smt_options.clone();
     let body = body.as_ref().map(|e| map_expr_visitor_env(e, env, fe, fs, ft)).transpose()?;
     let functionx = FunctionX {
         path,
+        kind,
         visibility,
         mode,
         fuel,
@@ -263,6 +357,11 @@ pub(crate) fn map_function_visitor_env<E, FE, FS, FT>(
         custom_req_err,
         hidden,
         is_abstract,
+        bit_vector,
+        nonlinear,
+        broadcast,
+        rlimit,
+        smt_options,
         body,
     };
     Ok(Spanned::new(function.span.clone(), functionx))
@@ -0,0 +1,294 @@
+//! Translate boolean SST expressions over bool and fixed-width unsigned integers
+//! into AIR bit-vector expressions, so that they can be proven in a separate query
+//! using the SMT solver's bit-vector theory.
+//!
+//! In the ordinary (integer) encoding, the bitwise operators are uninterpreted functions;
+//! facts proven with bit-vector reasoning are then assumed in the integer encoding.
+//! This is sound as long as the bit-vector expression means the same thing as the integer
+//! expression, so check_bit_vector_expr only allows expressions where this is the case:
+//! unsigned integer arithmetic must be clipped to its type's width,
+//! and division and casts between widths are not supported.
+
+use crate::ast::{BinaryOp, Constant, Expr, ExprX, IntRange, Typ, TypX, UnaryOp, VirErr};
+use crate::ast_util::{err_str, err_string, types_equal};
+use crate::def::suffix_local_unique_id;
+use crate::sst::{Exp, ExpX, UniqueIdent};
+use air::ast::{
+    BinaryOp as AirBinaryOp, Command, CommandX, Constant as AirConstant, Decl, DeclX,
+    ExprX as AirExprX, QueryX, Span, Stmt, StmtX, TypX as AirTypX, UnaryOp as AirUnaryOp,
+};
+use air::ast_util::{bool_typ, mk_and, mk_implies, mk_ite, mk_not, mk_or, string_var};
+use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
+
+type AirExpr = air::ast::Expr;
+
+enum BvTyp {
+    Bool,
+    Bits(u32),
+}
+
+fn typ_to_bv(span: &Span, typ: &Typ) -> Result<BvTyp, VirErr> {
+    match &**typ {
+        TypX::Bool => Ok(BvTyp::Bool),
+        TypX::Int(IntRange::U(w)) => Ok(BvTyp::Bits(*w)),
+        _ => err_str(
+            span,
+            "bit-vector reasoning only supports bool and fixed-width unsigned integer types (u8, u16, u32, u64, u128)",
+        ),
+    }
+}
+
+fn check_same_width(expr: &Expr, w: u32) -> Result<(), VirErr> {
+    match typ_to_bv(&expr.span, &expr.typ)? {
+        BvTyp::Bits(we) if we == w => Ok(()),
+        _ => err_str(&expr.span, "bit-vector reasoning does not support casts between widths"),
+    }
+}
+
+fn check_int_operands(e1: &Expr, e2: &Expr) -> Result<Option<u32>, VirErr> {
+    let w1 = check_expr(e1)?;
+    let w2 = check_expr(e2)?;
+    Ok(w1.or(w2))
+}
+
+fn check_comparison(span: &Span, e1: &Expr, e2: &Expr) -> Result<Option<u32>, VirErr> {
+    let w = check_int_operands(e1, e2)?;
+    match (typ_to_bv(&e1.span, &e1.typ)?, w) {
+        (BvTyp::Bits(_), None) => err_str(
+            span,
+            "bit-vector reasoning cannot infer the width of a comparison of constants",
+        ),
+        _ => Ok(None),
+    }
+}
+
+// Returns the width of the expression if the SST translation can infer it
+// (constants get their width from the surrounding expression)
+fn check_expr(expr: &Expr) -> Result<Option<u32>, VirErr> {
+    let bv_typ = typ_to_bv(&expr.span, &expr.typ)?;
+    match &expr.x {
+        ExprX::Const(Constant::Bool(_)) => Ok(None),
+        ExprX::Const(Constant::Nat(s)) => match (bv_typ, s.parse::<u128>()) {
+            (BvTyp::Bits(w), Ok(n)) if w >= 128 || n < (1u128 << w) => Ok(None),
+            _ => err_str(&expr.span, "constant out of range for bit-vector reasoning"),
+        },
+        ExprX::Var(_) => match bv_typ {
+            BvTyp::Bool => Ok(None),
+            BvTyp::Bits(w) => Ok(Some(w)),
+        },
+        ExprX::Unary(UnaryOp::Not, e) => {
+            check_expr(e)?;
+            Ok(None)
+        }
+        ExprX::Unary(UnaryOp::BitNot, e) => check_expr(e),
+        ExprX::Unary(UnaryOp::Clip { range: IntRange::U(w), .. }, e) => {
+            check_same_width(e, *w)?;
+            match &e.x {
+                ExprX::Binary(BinaryOp::Add | BinaryOp::Sub | BinaryOp::Mul, e1, e2) => {
+                    check_int_operands(e1, e2)?;
+                }
+                _ => {
+                    check_expr(e)?;
+                }
+            }
+            Ok(Some(*w))
+        }
+        ExprX::Binary(op, e1, e2) => match op {
+            BinaryOp::And | BinaryOp::Or | BinaryOp::Implies => {
+                check_expr(e1)?;
+                check_expr(e2)?;
+                Ok(None)
+            }
+            BinaryOp::Eq(_)
+            | BinaryOp::Ne
+            | BinaryOp::Le
+            | BinaryOp::Ge
+            | BinaryOp::Lt
+            | BinaryOp::Gt => check_comparison(&expr.span, e1, e2),
+            BinaryOp::BitAnd | BinaryOp::BitOr | BinaryOp::BitXor => check_int_operands(e1, e2),
+            BinaryOp::Shl | BinaryOp::Shr => {
+                if !types_equal(&e1.typ, &e2.typ) {
+                    return err_str(
+                        &expr.span,
+                        "bit-vector reasoning requires both sides of a shift to have the same type",
+                    );
+                }
+                check_int_operands(e1, e2)
+            }
+            BinaryOp::Add | BinaryOp::Sub | BinaryOp::Mul => {
+                err_str(&expr.span, "bit-vector arithmetic must be on fixed-width types")
+            }
+            BinaryOp::EuclideanDiv
+            | BinaryOp::EuclideanMod
+            | BinaryOp::TruncDiv
+            | BinaryOp::TruncMod => {
+                err_str(&expr.span, "bit-vector reasoning does not support division")
+            }
+        },
+        ExprX::If(e1, e2, Some(e3)) => {
+            check_expr(e1)?;
+            check_int_operands(e2, e3)
+        }
+        _ => err_string(
+            &expr.span,
+            format!("unsupported expression for bit-vector reasoning: {:?}", expr.x),
+        ),
+    }
+}
+
+/// Check that expr can be translated into a bit-vector expression with the same meaning
+pub(crate) fn check_bit_vector_expr(expr: &Expr) -> Result<(), VirErr> {
+    match typ_to_bv(&expr.span, &expr.typ)? {
+        BvTyp::Bool => {
+            check_expr(expr)?;
+            Ok(())
+        }
+        BvTyp::Bits(_) => err_str(&expr.span, "bit-vector assertion must have type bool"),
+    }
+}
+
+struct State<'a> {
+    typs: &'a HashMap<UniqueIdent, Typ>,
+    declared: HashSet<UniqueIdent>,
+    decls: Vec<Decl>,
+}
+
+fn var_width(state: &State, x: &UniqueIdent) -> Option<u32> {
+    match &*state.typs[x] {
+        TypX::Int(IntRange::U(w)) => Some(*w),
+        _ => None,
+    }
+}
+
+fn exp_width(state: &State, exp: &Exp) -> Option<u32> {
+    match &exp.x {
+        ExpX::Var(x) => var_width(state, x),
+        ExpX::Unary(UnaryOp::Clip { range: IntRange::U(w), .. }, _) => Some(*w),
+        ExpX::Unary(UnaryOp::BitNot, e) => exp_width(state, e),
+        ExpX::Binary(
+            BinaryOp::BitAnd | BinaryOp::BitOr | BinaryOp::BitXor | BinaryOp::Shl | BinaryOp::Shr,
+            e1,
+            e2,
+        ) => exp_width(state, e1).or(exp_width(state, e2)),
+        ExpX::If(_, e2, e3) => exp_width(state, e2).or(exp_width(state, e3)),
+        _ => None,
+    }
+}
+
+fn mk_bv_binary(op: AirBinaryOp, e1: AirExpr, e2: AirExpr) -> AirExpr {
+    Arc::new(AirExprX::Binary(op, e1, e2))
+}
+
+// width is the width of exp if exp is an integer, None if exp is a bool
+fn exp_to_bv_expr(state: &mut State, exp: &Exp, width: Option<u32>) -> AirExpr {
+    match &exp.x {
+        ExpX::Const(Constant::Bool(b)) => Arc::new(AirExprX::Const(AirConstant::Bool(*b))),
+        ExpX::Const(Constant::Nat(s)) => {
+            let w = width.expect("internal error: bit-vector constant width");
+            Arc::new(AirExprX::Const(AirConstant::BitVec(s.clone(), w)))
+        }
+        ExpX::Var(x) => {
+            if !state.declared.contains(x) {
+                state.declared.insert(x.clone());
+                let typ = match var_width(state, x) {
+                    None => bool_typ(),
+                    Some(w) => Arc::new(AirTypX::BitVec(w)),
+                };
+                state.decls.push(Arc::new(DeclX::Const(suffix_local_unique_id(x), typ)));
+            }
+            string_var(&suffix_local_unique_id(x))
+        }
+        ExpX::Unary(UnaryOp::Not, e) => mk_not(&exp_to_bv_expr(state, e, None)),
+        ExpX::Unary(UnaryOp::BitNot, e) => {
+            let expr = exp_to_bv_expr(state, e, width);
+            Arc::new(AirExprX::Unary(AirUnaryOp::BitNot, expr))
+        }
+        ExpX::Unary(UnaryOp::Clip { range, .. }, e) => match &e.x {
+            ExpX::Binary(op @ (BinaryOp::Add | BinaryOp::Sub | BinaryOp::Mul), e1, e2) => {
+                let w = match range {
+                    IntRange::U(w) => Some(*w),
+                    _ => panic!("internal error: unexpected bit-vector clip"),
+                };
+                let lh = exp_to_bv_expr(state, e1, w);
+                let rh = exp_to_bv_expr(state, e2, w);
+                let aop = match op {
+                    BinaryOp::Add => AirBinaryOp::BitAdd,
+                    BinaryOp::Sub => AirBinaryOp::BitSub,
+                    _ => AirBinaryOp::BitMul,
+                };
+                mk_bv_binary(aop, lh, rh)
+            }
+            // clipping a bit-vector to its own width has no effect
+            _ => exp_to_bv_expr(state, e, width),
+        },
+        ExpX::Binary(op, e1, e2) => match op {
+            BinaryOp::And | BinaryOp::Or | BinaryOp::Implies => {
+                let lh = exp_to_bv_expr(state, e1, None);
+                let rh = exp_to_bv_expr(state, e2, None);
+                match op {
+                    BinaryOp::And => mk_and(&vec![lh, rh]),
+                    BinaryOp::Or => mk_or(&vec![lh, rh]),
+                    _ => mk_implies(&lh, &rh),
+                }
+            }
+            _ => {
+                let w = match op {
+                    BinaryOp::Eq(_)
+                    | BinaryOp::Ne
+                    | BinaryOp::Le
+                    | BinaryOp::Ge
+                    | BinaryOp::Lt
+                    | BinaryOp::Gt => exp_width(state, e1).or(exp_width(state, e2)),
+                    _ => width,
+                };
+                let lh = exp_to_bv_expr(state, e1, w);
+                let rh = exp_to_bv_expr(state, e2, w);
+                let aop = match op {
+                    BinaryOp::Eq(_) => AirBinaryOp::Eq,
+                    BinaryOp::Ne => return mk_not(&mk_bv_binary(AirBinaryOp::Eq, lh, rh)),
+                    BinaryOp::Le => AirBinaryOp::BitULe,
+                    BinaryOp::Ge => AirBinaryOp::BitUGe,
+                    BinaryOp::Lt => AirBinaryOp::BitULt,
+                    BinaryOp::Gt => AirBinaryOp::BitUGt,
+                    BinaryOp::BitAnd => AirBinaryOp::BitAnd,
+                    BinaryOp::BitOr => AirBinaryOp::BitOr,
+                    BinaryOp::BitXor => AirBinaryOp::BitXor,
+                    BinaryOp::Shl => AirBinaryOp::BitShl,
+                    BinaryOp::Shr => AirBinaryOp::BitLShr,
+                    _ => panic!("internal error: unexpected bit-vector operator {:?}", op),
+                };
+                mk_bv_binary(aop, lh, rh)
+            }
+        },
+        ExpX::If(e1, e2, e3) => {
+            let w = width.or(exp_width(state, e2).or(exp_width(state, e3)));
+            let cond = exp_to_bv_expr(state, e1, None);
+            mk_ite(&cond, &exp_to_bv_expr(state, e2, w), &exp_to_bv_expr(state, e3, w))
+        }
+        _ => panic!("internal error: unexpected bit-vector expression {:?}", exp.x),
+    }
+}
+
+/// Build a query that assumes reqs and asserts each of asserts, using bit-vector reasoning.
+/// typs gives the types of the variables appearing in the expressions.
+pub(crate) fn bit_vector_query(
+    typs: &HashMap<UniqueIdent, Typ>,
+    reqs: &[Exp],
+    asserts: &[(Span, Exp)],
+) -> Command {
+    let mut state = State { typs, declared: HashSet::new(), decls: Vec::new() };
+    let mut axioms: Vec<Decl> = Vec::new();
+    for req in reqs {
+        axioms.push(Arc::new(DeclX::Axiom(exp_to_bv_expr(&mut state, req, None))));
+    }
+    let mut stmts: Vec<Stmt> = Vec::new();
+    for (span, exp) in asserts {
+        let expr = exp_to_bv_expr(&mut state, exp, None);
+        stmts.push(Arc::new(StmtX::Assert(Arc::new(Some(span.clone())), expr)));
+    }
+    let mut local = state.decls;
+    local.append(&mut axioms);
+    let assertion = Arc::new(StmtX::Block(Arc::new(stmts)));
+    Arc::new(CommandX::CheckValid(Arc::new(QueryX { local: Arc::new(local), assertion })))
+}
@@ -3,7 +3,7 @@
 use crate::def::FUEL_ID;
 use crate::scc::Graph;
 use crate::sst_to_air::path_to_air_ident;
-use air::ast::{Command, CommandX, Commands, DeclX, MultiOp, Span};
+use air::ast::{Command, CommandX, Commands, DeclX, Ident, MultiOp, Span};
 use air::ast_util::str_typ;
 use std::collections::{HashMap, HashSet};
 use std::sync::Arc;
@@ -14,6 +14,8 @@ pub struct GlobalCtx {
     pub(crate) datatypes: HashMap<Path, Variants>,
     // Used for synthesized AST nodes that have no relation to any location in the original code:
     pub(crate) no_span: Span,
+    // Number of bits in usize/isize
+    pub(crate) arch_word_bits: u32,
 }
 
 // Context for verifying one module
@@ -24,8 +26,16 @@ pub struct Ctx {
     pub(crate) func_map: HashMap<Path, Function>,
     pub(crate) func_call_graph: Graph<Path>,
     pub(crate) funcs_with_ensure_predicate: HashSet<Path>,
+    // Broadcast functions, and all functions they (transitively) call;
+    // these are verified without the broadcast axioms, so that no proof depends on itself
+    pub(crate) broadcast_funcs: Vec<Path>,
+    pub(crate) broadcast_callees: HashSet<Path>,
     pub(crate) debug: bool,
     pub(crate) global: GlobalCtx,
+    // Skolem functions for choose expressions, keyed by their AIR definition,
+    // and declarations for them that haven't been emitted yet
+    pub(crate) choose_fns: std::cell::RefCell<HashMap<String, Ident>>,
+    pub(crate) choose_decls: std::cell::RefCell<Vec<Command>>,
 }
 
 fn datatypes_inv_visit(
@@ -56,7 +66,12 @@ fn datatypes_invs(module: &Path, datatypes: &Vec<Datatype>) -> HashSet<Path> {
                     match &*field.a.0 {
                         // Should be kept in sync with vir::sst_to_air::typ_invariant
                         TypX::Int(IntRange::Int) => {}
-                        TypX::Int(_) | TypX::TypParam(_) => {
+                        TypX::Int(_)
+                        | TypX::TypParam(_)
+                        | TypX::Seq(_)
+                        | TypX::Array(..)
+                        | TypX::Set(_)
+                        | TypX::Map(..) => {
                             roots.insert(container_path.clone());
                         }
                         TypX::Datatype(field_path, _) => {
@@ -78,12 +93,12 @@ fn datatypes_invs(module: &Path, datatypes: &Vec<Datatype>) -> HashSet<Path> {
 }
 
 impl GlobalCtx {
-    pub fn new(krate: &Krate, no_span: Span) -> Self {
+    pub fn new(krate: &Krate, no_span: Span, arch_word_bits: u32) -> Self {
         let chosen_triggers: std::cell::RefCell<Vec<(Span, Vec<Vec<String>>)>> =
             std::cell::RefCell::new(Vec::new());
         let datatypes: HashMap<Path, Variants> =
             krate.datatypes.iter().map(|d| (d.x.path.clone(), d.x.variants.clone())).collect();
-        GlobalCtx { chosen_triggers, datatypes, no_span }
+        GlobalCtx { chosen_triggers, datatypes, no_span, arch_word_bits }
     }
 
     // Report chosen triggers as strings for printing diagnostics
@@ -110,6 +125,11 @@ pub fn new(
             functions.push(function.clone());
         }
         func_call_graph.compute_sccs();
+        let broadcast_funcs: Vec<Path> =
+            functions.iter().filter(|f| f.x.broadcast).map(|f| f.x.path.clone()).collect();
+        let mut broadcast_callees: HashSet<Path> =
+            func_call_graph.get_reachable(&broadcast_funcs).into_iter().collect();
+        broadcast_callees.extend(broadcast_funcs.iter().cloned());
         Ok(Ctx {
             module,
             datatypes_with_invariant,
@@ -117,8 +137,12 @@ pub fn new(
             func_map,
             func_call_graph,
             funcs_with_ensure_predicate,
+            broadcast_funcs,
+            broadcast_callees,
             debug,
             global,
+            choose_fns: std::cell::RefCell::new(HashMap::new()),
+            choose_decls: std::cell::RefCell::new(Vec::new()),
         })
     }
 
@@ -126,11 +150,16 @@ pub fn free(self) -> GlobalCtx {
         self.global
     }
 
-    pub fn prelude() -> Commands {
-        let nodes = crate::prelude::prelude_nodes();
+    pub fn prelude(arch_word_bits: u32) -> Commands {
+        let nodes = crate::prelude::prelude_nodes(arch_word_bits);
         air::parser::nodes_to_commands(&nodes).expect("internal error: malformed prelude")
     }
 
+    // Declarations of choose skolem functions introduced since the last call
+    pub(crate) fn take_choose_decls(&self) -> Vec<Command> {
+        std::mem::take(&mut *self.choose_decls.borrow_mut())
+    }
+
     pub fn module(&self) -> Path {
         self.module.clone()
     }
@@ -139,15 +168,18 @@ pub fn fuel(&self) -> Commands {
         let mut ids: Vec<air::ast::Expr> = Vec::new();
         let mut commands: Vec<Command> = Vec::new();
         for function in &self.functions {
-            match (function.x.mode, function.x.body.as_ref()) {
-                (Mode::Spec, Some(_)) => {
-                    let id = crate::def::prefix_fuel_id(&path_to_air_ident(&function.x.path));
-                    ids.push(air::ast_util::ident_var(&id));
-                    let typ_fuel_id = str_typ(&FUEL_ID);
-                    let decl = Arc::new(DeclX::Const(id, typ_fuel_id));
-                    commands.push(Arc::new(CommandX::Global(decl)));
-                }
-                _ => {}
+            let has_fuel = match (function.x.mode, function.x.body.as_ref()) {
+                (Mode::Spec, Some(_)) => true,
+                // fuel also switches the axioms of broadcast functions on and off
+                (Mode::Proof, _) => function.x.broadcast,
+                _ => false,
+            };
+            if has_fuel {
+                let id = crate::def::prefix_fuel_id(&path_to_air_ident(&function.x.path));
+                ids.push(air::ast_util::ident_var(&id));
+                let typ_fuel_id = str_typ(&FUEL_ID);
+                let decl = Arc::new(DeclX::Const(id, typ_fuel_id));
+                commands.push(Arc::new(CommandX::Global(decl)));
             }
         }
         let distinct = Arc::new(air::ast::ExprX::Multi(MultiOp::Distinct, Arc::new(ids)));
@@ -39,7 +39,8 @@ pub fn is_datatype_transparent(source_module: &Path, datatype: &crate::ast::Data
 }
 
 fn field_to_param(span: &Span, f: &Field) -> Param {
-    Spanned::new(span.clone(), ParamX { name: f.name.clone(), typ: f.a.0.clone(), mode: f.a.1 })
+    let paramx = ParamX { name: f.name.clone(), typ: f.a.0.clone(), mode: f.a.1, is_mut: false };
+    Spanned::new(span.clone(), paramx)
 }
 
 pub fn datatypes_to_air(ctx: &Ctx, datatypes: &crate::ast::Datatypes) -> Commands {
@@ -149,7 +150,12 @@ pub fn datatypes_to_air(ctx: &Ctx, datatypes: &crate::ast::Datatypes) -> Command
                             // trigger on x.f, inv(typs, x)
                             let dtyp =
                                 Arc::new(TypX::Datatype(datatype.x.path.clone(), Arc::new(vec![])));
-                            let paramx = ParamX { name: x.clone(), typ: dtyp, mode: Mode::Spec };
+                            let paramx = ParamX {
+                                name: x.clone(),
+                                typ: dtyp,
+                                mode: Mode::Spec,
+                                is_mut: false,
+                            };
                             let param = Spanned::new(datatype.span.clone(), paramx);
                             let mut inv_args =
                                 func_def_args(&datatype.x.typ_params, &Arc::new(vec![]));
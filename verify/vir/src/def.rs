@@ -43,8 +43,10 @@
 const PREFIX_REQUIRES: &str = "req%";
 const PREFIX_ENSURES: &str = "ens%";
 const PREFIX_RECURSIVE: &str = "rec%";
+const PREFIX_PRE_VAR: &str = "pre%";
 const PREFIX_SIMPLIFY_TEMP_VAR: &str = "tmp%%";
 const PREFIX_TEMP_VAR: &str = "tmp%";
+const PREFIX_CHOOSE: &str = "choose%";
 const PREFIX_BOX: &str = "Poly%";
 const PREFIX_UNBOX: &str = "%Poly%";
 const PREFIX_TYPE_ID: &str = "TYPE%";
@@ -72,26 +74,71 @@
 pub const U_CLIP: &str = "uClip";
 pub const I_CLIP: &str = "iClip";
 pub const NAT_CLIP: &str = "nClip";
+pub const U_TRUNC: &str = "uTrunc";
+pub const I_TRUNC: &str = "iTrunc";
 pub const U_INV: &str = "uInv";
 pub const I_INV: &str = "iInv";
+pub const TRUNC_DIV: &str = "truncDiv";
+pub const TRUNC_MOD: &str = "truncMod";
+pub const BIT_NOT: &str = "bitNot";
+pub const BIT_AND: &str = "bitAnd";
+pub const BIT_OR: &str = "bitOr";
+pub const BIT_XOR: &str = "bitXor";
+pub const BIT_SHR: &str = "bitShr";
+pub const BIT_SHL: &str = "bitShl";
 pub const ARCH_SIZE: &str = "SZ";
+pub const SEQ: &str = "Seq";
+pub const SEQ_EMPTY: &str = "seqEmpty";
+pub const SEQ_PUSH: &str = "seqPush";
+pub const SEQ_LEN: &str = "seqLen";
+pub const SEQ_INDEX: &str = "seqIndex";
+pub const SEQ_UPDATE: &str = "seqUpdate";
+pub const SEQ_SUBRANGE: &str = "seqSubrange";
+pub const SEQ_EXT_EQUAL: &str = "seqExtEqual";
+pub const SET: &str = "Set";
+pub const SET_EMPTY: &str = "setEmpty";
+pub const SET_CONTAINS: &str = "setContains";
+pub const SET_INSERT: &str = "setInsert";
+pub const SET_REMOVE: &str = "setRemove";
+pub const SET_EXT_EQUAL: &str = "setExtEqual";
+pub const MAP: &str = "Map";
+pub const MAP_EMPTY: &str = "mapEmpty";
+pub const MAP_DOM: &str = "mapDom";
+pub const MAP_INDEX: &str = "mapIndex";
+pub const MAP_INSERT: &str = "mapInsert";
+pub const MAP_REMOVE: &str = "mapRemove";
+pub const MAP_EXT_EQUAL: &str = "mapExtEqual";
 pub const DECREASE_AT_ENTRY: &str = "decrease%init";
 pub const SNAPSHOT_CALL: &str = "CALL";
+pub const TRAIT_SELF_TYPE_PARAM: &str = "Self";
 pub const POLY: &str = "Poly";
 pub const BOX_INT: &str = "I";
 pub const BOX_BOOL: &str = "B";
+pub const BOX_SEQ: &str = "S";
+pub const BOX_SET: &str = "T";
+pub const BOX_MAP: &str = "M";
 pub const UNBOX_INT: &str = "%I";
 pub const UNBOX_BOOL: &str = "%B";
+pub const UNBOX_SEQ: &str = "%S";
+pub const UNBOX_SET: &str = "%T";
+pub const UNBOX_MAP: &str = "%M";
 pub const TYPE: &str = "Type";
 pub const TYPE_ID_BOOL: &str = "BOOL";
 pub const TYPE_ID_INT: &str = "INT";
 pub const TYPE_ID_NAT: &str = "NAT";
 pub const TYPE_ID_UINT: &str = "UINT";
 pub const TYPE_ID_SINT: &str = "SINT";
+pub const TYPE_ID_SEQ: &str = "SEQ";
+pub const TYPE_ID_ARRAY: &str = "ARRAY";
+pub const TYPE_ID_SET: &str = "SET";
+pub const TYPE_ID_MAP: &str = "MAP";
 pub const HAS_TYPE: &str = "has_type";
 const CHECK_DECREASE_INT: &str = "check_decrease_int";
 const HEIGHT: &str = "height";
 
+// SMT solver option that enables nonlinear arithmetic
+pub const Z3_ARITH_NL: &str = "smt.arith.nl";
+
 // We assume that usize is at least ARCH_SIZE_MIN_BITS wide
 pub const ARCH_SIZE_MIN_BITS: u32 = 32;
 
@@ -159,6 +206,10 @@ pub fn suffix_rename(ident: &Ident) -> Ident {
     Arc::new(ident.to_string() + SUFFIX_RENAME)
 }
 
+pub fn decrease_at_entry(i: usize) -> Ident {
+    Arc::new(format!("{}{}", DECREASE_AT_ENTRY, i))
+}
+
 pub fn prefix_type_id(ident: &Ident) -> Ident {
     Arc::new(PREFIX_TYPE_ID.to_string() + ident)
 }
@@ -220,6 +271,11 @@ pub fn prefix_recursive(path: &Path) -> Path {
     prefix_path(PREFIX_RECURSIVE.to_string(), path)
 }
 
+// Value of a &mut parameter at function entry, as seen by the ensures predicate
+pub fn prefix_pre_var(ident: &Ident) -> Ident {
+    Arc::new(PREFIX_PRE_VAR.to_string() + ident)
+}
+
 pub fn prefix_temp_var(n: u64) -> Ident {
     Arc::new(PREFIX_TEMP_VAR.to_string() + &n.to_string())
 }
@@ -233,6 +289,11 @@ pub fn prefix_simplify_temp_var(n: u64) -> Ident {
     Arc::new(PREFIX_SIMPLIFY_TEMP_VAR.to_string() + &n.to_string())
 }
 
+// Skolem function for the n-th distinct choose expression in a module
+pub fn prefix_choose(n: usize) -> Ident {
+    Arc::new(PREFIX_CHOOSE.to_string() + &n.to_string())
+}
+
 pub fn variant_ident(datatype: &Path, variant: &str) -> Ident {
     Arc::new(format!("{}{}{}", path_to_string(datatype), VARIANT_SEPARATOR, variant))
 }
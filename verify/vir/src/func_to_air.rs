@@ -1,10 +1,15 @@
-use crate::ast::{Function, Ident, Idents, Mode, ParamX, Params, VirErr};
+use crate::ast::{
+    Function, FunctionKind, Ident, Mode, Param, ParamX, Params, Path, Typ, TypParams, TypX,
+    UnaryOpr, VirErr,
+};
+use crate::ast_util::is_visible_to;
 use crate::context::Ctx;
 use crate::def::{
-    prefix_ensures, prefix_fuel_id, prefix_fuel_nat, prefix_recursive, prefix_requires,
-    suffix_global_id, suffix_local_stmt_id, suffix_typ_param_id, SnapPos, FUEL_BOOL,
-    FUEL_BOOL_DEFAULT, FUEL_LOCAL, FUEL_TYPE, SUCC, ZERO,
+    prefix_ensures, prefix_fuel_id, prefix_fuel_nat, prefix_pre_var, prefix_recursive,
+    prefix_requires, suffix_global_id, suffix_local_stmt_id, suffix_typ_param_id, SnapPos, Spanned,
+    FUEL_BOOL, FUEL_BOOL_DEFAULT, FUEL_LOCAL, FUEL_TYPE, SUCC, ZERO,
 };
+use crate::sst::{Exp, ExpX, Stm, StmX, UniqueIdent};
 use crate::sst_to_air::{exp_to_expr, path_to_air_ident, typ_invariant, typ_to_air};
 use crate::util::{vec_map, vec_map_result};
 use air::ast::{
@@ -15,18 +20,19 @@
     bool_typ, ident_apply, ident_binder, ident_var, mk_and, mk_bind_expr, mk_eq, mk_implies,
     str_apply, str_ident, str_typ, str_var, string_apply,
 };
+use std::collections::HashMap;
 use std::sync::Arc;
 
 // binder for forall (typ_params params)
 pub(crate) fn func_bind_trig(
     ctx: &Ctx,
-    typ_params: &Idents,
+    typ_params: &TypParams,
     params: &Params,
     trig_exprs: &Vec<Expr>,
     add_fuel: bool,
 ) -> Bind {
     let mut binders: Vec<air::ast::Binder<air::ast::Typ>> = Vec::new();
-    for typ_param in typ_params.iter() {
+    for (typ_param, _) in typ_params.iter() {
         binders.push(ident_binder(&suffix_typ_param_id(&typ_param), &str_typ(crate::def::TYPE)));
     }
     for param in params.iter() {
@@ -46,7 +52,7 @@ pub(crate) fn func_bind_trig(
 // binder for forall (typ_params params)
 pub(crate) fn func_bind(
     ctx: &Ctx,
-    typ_params: &Idents,
+    typ_params: &TypParams,
     params: &Params,
     trig_expr: &Expr,
     add_fuel: bool,
@@ -55,9 +61,9 @@ pub(crate) fn func_bind(
 }
 
 // arguments for function call f(typ_params, params)
-pub(crate) fn func_def_args(typ_params: &Idents, params: &Params) -> Vec<Expr> {
+pub(crate) fn func_def_args(typ_params: &TypParams, params: &Params) -> Vec<Expr> {
     let mut f_args: Vec<Expr> = Vec::new();
-    for typ_param in typ_params.iter() {
+    for (typ_param, _) in typ_params.iter() {
         f_args.push(ident_var(&suffix_typ_param_id(&typ_param)));
     }
     for param in params.iter() {
@@ -66,11 +72,19 @@ pub(crate) fn func_def_args(typ_params: &Idents, params: &Params) -> Vec<Expr> {
     f_args
 }
 
+// entry value of a &mut parameter, named pre%x
+fn pre_param(param: &Param) -> Param {
+    let ParamX { name, typ, mode, is_mut } = &param.x;
+    let paramx =
+        ParamX { name: prefix_pre_var(name), typ: typ.clone(), mode: *mode, is_mut: *is_mut };
+    Spanned::new(param.span.clone(), paramx)
+}
+
 // (forall (...) (= (f ...) body))
 fn func_def_quant(
     ctx: &Ctx,
     name: &Ident,
-    typ_params: &Idents,
+    typ_params: &TypParams,
     params: &Params,
     body: Expr,
 ) -> Result<Expr, VirErr> {
@@ -80,6 +94,38 @@ fn func_def_quant(
     Ok(mk_bind_expr(&func_bind(ctx, typ_params, params, &f_app, false), &f_eq))
 }
 
+// Connect a spec trait method declaration, applied to the impl's Self type, to the impl:
+//   (axiom (forall (...) (= (Trait::f S ... (box x) ...) (box (S::f ... x ...)))))
+// (boxing only the values whose type is Self in the declaration)
+fn trait_method_impl_axiom(ctx: &Ctx, function: &Function, method: &Path) -> air::ast::Decl {
+    let decl = &ctx.func_map[method];
+    let span = &function.span;
+    let boxed = |decl_typ: &Typ, typ: &Typ, exp: Exp| match &**decl_typ {
+        TypX::TypParam(_) => {
+            Spanned::new(span.clone(), ExpX::UnaryOpr(UnaryOpr::Box(typ.clone()), exp))
+        }
+        _ => exp,
+    };
+    let typ_args: Vec<Typ> =
+        vec_map(&function.x.typ_params, |(x, _)| Arc::new(TypX::TypParam(x.clone())));
+    let mut decl_typ_args = typ_args.clone();
+    decl_typ_args.insert(0, function.x.params[0].x.typ.clone());
+    let mut args: Vec<Exp> = Vec::new();
+    let mut decl_args: Vec<Exp> = Vec::new();
+    for (param, decl_param) in function.x.params.iter().zip(decl.x.params.iter()) {
+        let var = Spanned::new(span.clone(), ExpX::Var((param.x.name.clone(), Some(0))));
+        args.push(var.clone());
+        decl_args.push(boxed(&decl_param.x.typ, &param.x.typ, var));
+    }
+    let call = ExpX::Call(function.x.path.clone(), Arc::new(typ_args), Arc::new(args));
+    let call = boxed(&decl.x.ret.x.typ, &function.x.ret.x.typ, Spanned::new(span.clone(), call));
+    let decl_call = ExpX::Call(method.clone(), Arc::new(decl_typ_args), Arc::new(decl_args));
+    let decl_call = exp_to_expr(ctx, &Spanned::new(span.clone(), decl_call));
+    let eq = mk_eq(&decl_call, &exp_to_expr(ctx, &call));
+    let bind = func_bind(ctx, &function.x.typ_params, &function.x.params, &decl_call, false);
+    Arc::new(DeclX::Axiom(mk_bind_expr(&bind, &eq)))
+}
+
 fn func_body_to_air(
     ctx: &Ctx,
     decl_commands: &mut Vec<Command>,
@@ -167,7 +213,7 @@ pub fn req_ens_to_air(
     params: &Params,
     typing_invs: &Vec<Expr>,
     specs: &Vec<crate::ast::Expr>,
-    typ_params: &Idents,
+    typ_params: &TypParams,
     typs: &air::ast::Typs,
     name: &Ident,
     msg: &Option<String>,
@@ -206,6 +252,58 @@ pub fn req_ens_to_air(
     }
 }
 
+// For a broadcast function f with type parameters A and parameters x:
+//   (axiom (fuel_bool_default fuel%f))
+//   (axiom (=> (fuel_bool fuel%f) (forall (A x) (=> (and requires) (and ensures)))))
+// using the triggers chosen for forall x. requires ==> ensures
+fn broadcast_to_air(ctx: &Ctx, function: &Function) -> Result<Vec<Command>, VirErr> {
+    use crate::ast::{BinaryOp as VBinaryOp, Constant, ExprX as VExprX, SpannedTyped};
+    let span = &function.span;
+    let bool_typ = Arc::new(TypX::Bool);
+    let conjoin = |es: &crate::ast::Exprs| {
+        let mut conj = SpannedTyped::new(span, &bool_typ, VExprX::Const(Constant::Bool(true)));
+        for e in es.iter() {
+            conj = SpannedTyped::new(
+                &e.span,
+                &bool_typ,
+                VExprX::Binary(VBinaryOp::And, conj, e.clone()),
+            );
+        }
+        conj
+    };
+    let imply = VExprX::Binary(
+        VBinaryOp::Implies,
+        conjoin(&function.x.require),
+        conjoin(&function.x.ensure),
+    );
+    let binders = vec_map(&function.x.params, |p| {
+        Arc::new(air::ast::BinderX { name: p.x.name.clone(), a: p.x.typ.clone() })
+    });
+    let forall =
+        VExprX::Quant(Quant::Forall, Arc::new(binders), SpannedTyped::new(span, &bool_typ, imply));
+    let forall = SpannedTyped::new(span, &bool_typ, forall);
+    let exp = crate::ast_to_sst::expr_to_exp(ctx, &Arc::new(vec![]), &forall)?;
+    let mut expr = exp_to_expr(ctx, &exp);
+    if function.x.typ_params.len() > 0 {
+        // Quantify over the type parameters too
+        if let ExprX::Bind(bind, body) = &*expr {
+            if let BindX::Quant(quant, binders, triggers) = &**bind {
+                let mut all_binders = vec_map(&function.x.typ_params, |(x, _)| {
+                    ident_binder(&suffix_typ_param_id(x), &str_typ(crate::def::TYPE))
+                });
+                all_binders.extend(binders.iter().cloned());
+                let bind = BindX::Quant(*quant, Arc::new(all_binders), triggers.clone());
+                expr = mk_bind_expr(&Arc::new(bind), body);
+            }
+        }
+    }
+    let id_fuel = prefix_fuel_id(&path_to_air_ident(&function.x.path));
+    let fuel_default = str_apply(&FUEL_BOOL_DEFAULT, &vec![ident_var(&id_fuel)]);
+    let fuel_bool = str_apply(FUEL_BOOL, &vec![ident_var(&id_fuel)]);
+    let axioms = vec![fuel_default, mk_implies(&fuel_bool, &expr)];
+    Ok(vec_map(&axioms, |e| Arc::new(CommandX::Global(Arc::new(DeclX::Axiom(e.clone()))))))
+}
+
 /// Returns vector of commands that declare the function symbol itself,
 /// as well as any related functions symbols (e.g., recursive versions),
 /// if the function is a spec function.
@@ -260,12 +358,19 @@ pub fn func_decl_to_air(
             // Body
             if let Some(body) = &function.x.body {
                 func_body_to_air(ctx, &mut decl_commands, &mut check_commands, function, body)?;
+                if let FunctionKind::TraitMethodImpl { method, .. } = &function.x.kind {
+                    // (the trait method declaration may not be visible in this module)
+                    if is_visible_to(&ctx.func_map[method].x.visibility, &ctx.module) {
+                        let axiom = trait_method_impl_axiom(ctx, function, method);
+                        decl_commands.push(Arc::new(CommandX::Global(axiom)));
+                    }
+                }
             }
 
             // Return typing invariant
             let mut f_args: Vec<Expr> = Vec::new();
             let mut f_pre: Vec<Expr> = Vec::new();
-            for typ_param in function.x.typ_params.iter() {
+            for (typ_param, _) in function.x.typ_params.iter() {
                 f_args.push(ident_var(&suffix_typ_param_id(&typ_param.clone())));
             }
             for param in function.x.params.iter() {
@@ -304,9 +409,24 @@ pub fn func_decl_to_air(
                 &prefix_requires(&path_to_air_ident(&function.x.path)),
                 &msg,
             )?;
+            // ens% takes the entry values of all params, then the final values of &mut params
             let mut ens_typs = (*param_typs).clone();
-            let mut ens_params = (*function.x.params).clone();
+            let mut ens_params: Vec<Param> = vec_map(&function.x.params, |param| {
+                if param.x.is_mut {
+                    pre_param(param)
+                } else {
+                    param.clone()
+                }
+            });
             let mut ens_typing_invs: Vec<Expr> = Vec::new();
+            for param in function.x.params.iter().filter(|p| p.x.is_mut) {
+                ens_typs.push(typ_to_air(ctx, &param.x.typ));
+                ens_params.push(param.clone());
+                let arg = ident_var(&suffix_local_stmt_id(&param.x.name));
+                if let Some(expr) = typ_invariant(ctx, &param.x.typ, &arg) {
+                    ens_typing_invs.push(expr);
+                }
+            }
             if function.x.has_return() {
                 let ParamX { name, typ, .. } = &function.x.ret.x;
                 ens_typs.push(typ_to_air(ctx, &typ));
@@ -331,19 +451,69 @@ pub fn func_decl_to_air(
             if has_ens_pred {
                 ctx.funcs_with_ensure_predicate.insert(function.x.path.clone());
             }
+            if function.x.broadcast {
+                decl_commands.extend(broadcast_to_air(ctx, function)?);
+            }
         }
     }
-    Ok((Arc::new(decl_commands), Arc::new(check_commands)))
+    // Skolem functions for any choose expressions must be declared before they are used
+    let mut commands = ctx.take_choose_decls();
+    commands.extend(decl_commands);
+    Ok((Arc::new(commands), Arc::new(check_commands)))
+}
+
+// Prove the ensures from the requires using bit-vector reasoning, without looking at the body
+fn bit_vector_func_def_to_air(ctx: &Ctx, function: &Function) -> Result<Commands, VirErr> {
+    let mut ens_params = (*function.x.params).clone();
+    let mut typs: HashMap<UniqueIdent, Typ> = HashMap::new();
+    for param in function.x.params.iter() {
+        typs.insert((param.x.name.clone(), Some(0)), param.x.typ.clone());
+    }
+    if function.x.has_return() {
+        ens_params.push(function.x.ret.clone());
+        typs.insert((function.x.ret.x.name.clone(), Some(0)), function.x.ret.x.typ.clone());
+    }
+    let ens_params = Arc::new(ens_params);
+    let mut reqs: Vec<Exp> = Vec::new();
+    for e in function.x.require.iter() {
+        crate::bitvector_to_air::check_bit_vector_expr(e)?;
+        reqs.push(crate::ast_to_sst::expr_to_exp(ctx, &function.x.params, e)?);
+    }
+    let mut enss: Vec<(Span, Exp)> = Vec::new();
+    for e in function.x.ensure.iter() {
+        crate::bitvector_to_air::check_bit_vector_expr(e)?;
+        let description = Some("postcondition not satisfied".to_string());
+        let span = Span { description, ..e.span.clone() };
+        enss.push((span, crate::ast_to_sst::expr_to_exp(ctx, &ens_params, e)?));
+    }
+    let query = crate::bitvector_to_air::bit_vector_query(&typs, &reqs, &enss);
+    Ok(Arc::new(vec![query]))
 }
 
 pub fn func_def_to_air(
     ctx: &Ctx,
     function: &Function,
 ) -> Result<(Commands, Vec<(Span, SnapPos)>), VirErr> {
+    if function.x.bit_vector {
+        return Ok((bit_vector_func_def_to_air(ctx, function)?, vec![]));
+    }
     match (function.x.mode, function.x.ret.as_ref(), function.x.body.as_ref()) {
         (Mode::Exec, _, Some(body)) | (Mode::Proof, _, Some(body)) => {
             let mut state = crate::ast_to_sst::State::new();
             let mut ens_params = (*function.x.params).clone();
+            let mut entry_assumes: Vec<Stm> = Vec::new();
+            for param in function.x.params.iter().filter(|p| p.x.is_mut) {
+                // old(x) refers to pre%x, which is pinned to x's value at entry
+                let ParamX { name, typ, .. } = &param.x;
+                let pre = state.declare_new_var(&prefix_pre_var(name), typ, false);
+                ens_params.push(pre_param(param));
+                let span = &param.span;
+                let pre_var = Spanned::new(span.clone(), ExpX::Var(pre));
+                let x_var = Spanned::new(span.clone(), ExpX::Var((name.clone(), Some(0))));
+                let eq = ExpX::Binary(crate::ast::BinaryOp::Eq(Mode::Spec), pre_var, x_var);
+                let assume = StmX::Assume(Spanned::new(span.clone(), eq));
+                entry_assumes.push(Spanned::new(span.clone(), assume));
+            }
             let dest = if function.x.has_return() {
                 let ParamX { name, typ, .. } = &function.x.ret.x;
                 ens_params.push(function.x.ret.clone());
@@ -360,27 +530,43 @@ pub fn func_def_to_air(
                 crate::ast_to_sst::expr_to_exp(ctx, &ens_params, e)
             })?;
             for param in function.x.params.iter() {
-                state.declare_new_var(&param.x.name, &param.x.typ, false);
+                state.declare_new_var(&param.x.name, &param.x.typ, param.x.is_mut);
             }
+            state.ret_dest = dest.clone();
+            state.view_as_spec = function.x.mode != Mode::Exec;
             let stm = crate::ast_to_sst::expr_to_one_stm_dest(&ctx, &mut state, &body, &dest)?;
+            entry_assumes.push(stm);
+            let stm = crate::ast_to_sst::stms_to_one_stm(&body.span, entry_assumes);
             let stm = state.finalize_stm(&stm);
             let (decls, stm) = crate::recursion::check_termination_stm(ctx, function, &stm)?;
             for decl in decls {
-                state.new_statement_var(&decl.ident.0);
+                state.new_statement_var(&decl.ident.0, &decl.typ);
                 state.local_decls.push(decl.clone());
             }
+            let mut hidden = (*function.x.hidden).clone();
+            if ctx.broadcast_callees.contains(&function.x.path) {
+                hidden.extend(ctx.broadcast_funcs.iter().cloned());
+            }
             let (commands, snap_map) = crate::sst_to_air::body_stm_to_air(
                 ctx,
                 &function.x.typ_params,
                 &function.x.params,
                 &state.local_decls,
-                &function.x.hidden,
+                &hidden,
                 &reqs,
                 &enss,
                 &stm,
+                function.x.nonlinear,
             );
             state.finalize();
-            Ok((commands, snap_map))
+            let mut choose_commands = ctx.take_choose_decls();
+            if function.x.nonlinear {
+                let commands = (*commands).clone();
+                choose_commands.append(&mut crate::sst_to_air::nonlinear_commands(commands));
+            } else {
+                choose_commands.extend(commands.iter().cloned());
+            }
+            Ok((Arc::new(choose_commands), snap_map))
         }
         _ => Ok((Arc::new(vec![]), vec![])),
     }
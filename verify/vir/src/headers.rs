@@ -9,7 +9,9 @@ pub struct Header {
     pub ensure_id_typ: Option<(Ident, Typ)>,
     pub ensure: Exprs,
     pub invariant: Exprs,
-    pub decrease: Option<Expr>,
+    pub decrease: Exprs,
+    /// The body is no_method_body(), marking a method declared in a trait
+    pub no_method_body: bool,
 }
 
 fn read_header_block(block: &mut Vec<Stmt>) -> Result<Header, VirErr> {
@@ -17,7 +19,8 @@ fn read_header_block(block: &mut Vec<Stmt>) -> Result<Header, VirErr> {
     let mut require: Option<Exprs> = None;
     let mut ensure: Option<(Option<(Ident, Typ)>, Exprs)> = None;
     let mut invariant: Option<Exprs> = None;
-    let mut decrease: Option<Expr> = None;
+    let mut decrease: Option<Exprs> = None;
+    let mut no_method_body = false;
     let mut n = 0;
     for stmt in block.iter() {
         match &stmt.x {
@@ -50,18 +53,21 @@ fn read_header_block(block: &mut Vec<Stmt>) -> Result<Header, VirErr> {
                         }
                         invariant = Some(es.clone());
                     }
-                    HeaderExprX::Decreases(e) => {
+                    HeaderExprX::Decreases(es) => {
                         if decrease.is_some() {
                             return err_str(
                                 &stmt.span,
-                                "only one decreases expression currently supported",
+                                "only one call to decreases allowed (use decreases((e1, ..., en)) for lexicographic order)",
                             );
                         }
-                        decrease = Some(e.clone());
+                        decrease = Some(es.clone());
                     }
                     HeaderExprX::Hide(x) => {
                         hidden.push(x.clone());
                     }
+                    HeaderExprX::NoMethodBody => {
+                        no_method_body = true;
+                    }
                 },
                 _ => break,
             },
@@ -76,15 +82,31 @@ fn read_header_block(block: &mut Vec<Stmt>) -> Result<Header, VirErr> {
         Some((id_typ, es)) => (id_typ, es),
     };
     let invariant = invariant.unwrap_or(Arc::new(vec![]));
-    Ok(Header { hidden, require, ensure_id_typ, ensure, invariant, decrease })
+    let decrease = decrease.unwrap_or(Arc::new(vec![]));
+    Ok(Header { hidden, require, ensure_id_typ, ensure, invariant, decrease, no_method_body })
 }
 
 pub fn read_header(body: &mut Expr) -> Result<Header, VirErr> {
     match &body.x {
         ExprX::Block(stmts, expr) => {
             let mut block: Vec<Stmt> = (**stmts).clone();
-            let header = read_header_block(&mut block)?;
-            *body = body.new_x(ExprX::Block(Arc::new(block), expr.clone()));
+            let mut header = read_header_block(&mut block)?;
+            let mut expr = expr.clone();
+            if let Some(e) = &expr {
+                if let ExprX::Header(h) = &e.x {
+                    if let HeaderExprX::NoMethodBody = &**h {
+                        header.no_method_body = true;
+                        expr = None;
+                    }
+                }
+            }
+            if header.no_method_body && (block.len() > 0 || expr.is_some()) {
+                return err_str(
+                    &body.span,
+                    "no_method_body() must be the only statement after the header",
+                );
+            }
+            *body = body.new_x(ExprX::Block(Arc::new(block), expr));
             Ok(header)
         }
         _ => read_header_block(&mut vec![]),
@@ -33,6 +33,7 @@
 mod ast_to_sst;
 pub mod ast_util;
 mod ast_visitor;
+mod bitvector_to_air;
 pub mod context;
 pub mod datatype_to_air;
 pub mod def;
@@ -47,6 +48,7 @@
 mod sst_to_air;
 mod sst_vars;
 mod sst_visitor;
+mod traits;
 mod triggers;
 mod triggers_auto;
 mod util;
@@ -1,6 +1,6 @@
 use crate::ast::{
-    BinaryOp, Datatype, Expr, ExprX, Function, Ident, Krate, Mode, Path, Pattern, PatternX, Stmt,
-    StmtX, TypX, UnaryOpr, VirErr,
+    BinaryOp, Datatype, Expr, ExprX, Function, Ident, Krate, Mode, Path, Pattern, PatternX, SeqOp,
+    Stmt, StmtX, TypX, UnaryOpr, VirErr,
 };
 use crate::ast_util::{err_str, err_string};
 use crate::util::vec_map_result;
@@ -42,6 +42,11 @@ struct Typing {
     pub(crate) datatypes: HashMap<Path, Datatype>,
     pub(crate) vars: ScopeMap<Ident, Mode>,
     pub(crate) erasure_modes: ErasureModes,
+    // Mode of the function being checked, and of its return value (if any)
+    pub(crate) fun_mode: Mode,
+    pub(crate) ret_mode: Option<Mode>,
+    // Enclosing loops, innermost last, with their labels and modes
+    pub(crate) loops: Vec<(Option<Ident>, Mode)>,
 }
 
 impl Typing {
@@ -108,6 +113,8 @@ fn check_expr(typing: &mut Typing, outer_mode: Mode, expr: &Expr) -> Result<Mode
             typing.erasure_modes.var_modes.push((expr.span.clone(), mode));
             Ok(mode)
         }
+        ExprX::VarAt(..) => Ok(Mode::Spec),
+        ExprX::Loc(e) => check_expr(typing, outer_mode, e),
         ExprX::Call(x, _, es) => {
             let function = match typing.funs.get(x) {
                 None => {
@@ -123,12 +130,37 @@ fn check_expr(typing: &mut Typing, outer_mode: Mode, expr: &Expr) -> Result<Mode
                 );
             }
             for (param, arg) in function.x.params.iter().zip(es.iter()) {
-                check_expr_has_mode(
-                    typing,
-                    mode_join(outer_mode, param.x.mode),
-                    arg,
-                    param.x.mode,
-                )?;
+                if param.x.is_mut {
+                    // The callee both reads and writes the location, so the modes must match
+                    check_expr(typing, outer_mode, arg)?;
+                    let arg_mode = match &arg.x {
+                        ExprX::Loc(loc) => match &loc.x {
+                            ExprX::Var(x) => typing.get(x),
+                            _ => {
+                                return err_str(&arg.span, "&mut argument must be a local variable")
+                            }
+                        },
+                        _ => {
+                            return err_str(&arg.span, "expected &mut argument for &mut parameter")
+                        }
+                    };
+                    if arg_mode != param.x.mode {
+                        return err_string(
+                            &arg.span,
+                            format!(
+                                "&mut argument has mode {}, expected mode {}",
+                                arg_mode, param.x.mode
+                            ),
+                        );
+                    }
+                } else {
+                    check_expr_has_mode(
+                        typing,
+                        mode_join(outer_mode, param.x.mode),
+                        arg,
+                        param.x.mode,
+                    )?;
+                }
             }
             Ok(function.x.ret.x.mode)
         }
@@ -182,6 +214,23 @@ fn check_expr(typing: &mut Typing, outer_mode: Mode, expr: &Expr) -> Result<Mode
             let mode2 = check_expr(typing, outer_mode, e2)?;
             Ok(mode_join(op_mode, mode_join(mode1, mode2)))
         }
+        ExprX::SeqOp(op, es) => {
+            // Operations on arrays and slices can be exec; operations on sequences are spec
+            let seq_typ = if let Some(e) = es.first() { &e.typ } else { &expr.typ };
+            let op_mode = match (op, &**seq_typ) {
+                (SeqOp::View | SeqOp::Subrange | SeqOp::ExtEqual, _) => Mode::Spec,
+                (_, TypX::Array(..)) => Mode::Exec,
+                _ => Mode::Spec,
+            };
+            let modes = vec_map_result(es, |e| check_expr(typing, outer_mode, e))?;
+            Ok(modes.into_iter().fold(mode_join(outer_mode, op_mode), mode_join))
+        }
+        ExprX::SetOp(_, es) | ExprX::MapOp(_, es) => {
+            for e in es.iter() {
+                check_expr(typing, outer_mode, e)?;
+            }
+            Ok(Mode::Spec)
+        }
         ExprX::Quant(_, binders, e1) => {
             typing.vars.push_scope(true);
             for binder in binders.iter() {
@@ -191,6 +240,13 @@ fn check_expr(typing: &mut Typing, outer_mode: Mode, expr: &Expr) -> Result<Mode
             typing.vars.pop_scope();
             Ok(Mode::Spec)
         }
+        ExprX::Choose(binder, e1) => {
+            typing.vars.push_scope(true);
+            typing.insert(&expr.span, &binder.name, Mode::Spec);
+            check_expr_has_mode(typing, Mode::Spec, e1, Mode::Spec)?;
+            typing.vars.pop_scope();
+            Ok(Mode::Spec)
+        }
         ExprX::Assign(lhs, rhs) => match &lhs.x {
             ExprX::Var(x) => {
                 let x_mode = typing.get(x);
@@ -203,6 +259,34 @@ fn check_expr(typing: &mut Typing, outer_mode: Mode, expr: &Expr) -> Result<Mode
         ExprX::Fuel(_, _) => Ok(outer_mode),
         ExprX::Header(_) => panic!("internal error: Header shouldn't exist here"),
         ExprX::Admit => Ok(outer_mode),
+        ExprX::AssertBV(e) => {
+            check_expr_has_mode(typing, Mode::Spec, e, Mode::Spec)?;
+            Ok(outer_mode)
+        }
+        ExprX::AssertBy { vars, require, ensure, proof } => {
+            if outer_mode == Mode::Spec {
+                return err_str(&expr.span, "assert_by is not allowed in spec code");
+            }
+            typing.vars.push_scope(true);
+            for var in vars.iter() {
+                typing.insert(&expr.span, &var.name, Mode::Proof);
+            }
+            check_expr_has_mode(typing, Mode::Spec, require, Mode::Spec)?;
+            check_expr_has_mode(typing, Mode::Spec, ensure, Mode::Spec)?;
+            check_expr_has_mode(typing, Mode::Proof, proof, Mode::Proof)?;
+            typing.vars.pop_scope();
+            Ok(outer_mode)
+        }
+        ExprX::AssertNonlinear { requires, ensures, proof } => {
+            if outer_mode == Mode::Spec {
+                return err_str(&expr.span, "assert_nonlinear_by is not allowed in spec code");
+            }
+            for e in requires.iter().chain(ensures.iter()) {
+                check_expr_has_mode(typing, Mode::Spec, e, Mode::Spec)?;
+            }
+            check_expr_has_mode(typing, Mode::Proof, proof, Mode::Proof)?;
+            Ok(outer_mode)
+        }
         ExprX::If(e1, e2, e3) => {
             let mode1 = check_expr(typing, outer_mode, e1)?;
             typing.erasure_modes.condition_modes.push((expr.span.clone(), mode1));
@@ -248,14 +332,45 @@ fn check_expr(typing: &mut Typing, outer_mode: Mode, expr: &Expr) -> Result<Mode
             }
             Ok(final_mode)
         }
-        ExprX::While { cond, body, invs } => {
-            // We could also allow this for proof, if we check it for termination
-            check_expr_has_mode(typing, outer_mode, cond, Mode::Exec)?;
-            check_expr_has_mode(typing, outer_mode, body, Mode::Exec)?;
+        ExprX::Loop { label, cond, body, invs, enss, decrease } => {
+            // Proof code must terminate, so proof loops need a decreases clause
+            let loop_mode = match (outer_mode, decrease) {
+                (Mode::Exec, _) => Mode::Exec,
+                (Mode::Spec, _) => {
+                    return err_str(&expr.span, "loops are not allowed in spec code")
+                }
+                (Mode::Proof, None) => {
+                    return err_str(&expr.span, "loops in proof code must have a decreases clause");
+                }
+                (Mode::Proof, Some(_)) => Mode::Proof,
+            };
+            if let Some(cond) = cond {
+                check_expr_has_mode(typing, outer_mode, cond, loop_mode)?;
+            }
+            typing.loops.push((label.clone(), loop_mode));
+            check_expr_has_mode(typing, outer_mode, body, loop_mode)?;
+            typing.loops.pop();
             for inv in invs.iter() {
                 check_expr_has_mode(typing, Mode::Spec, inv, Mode::Spec)?;
             }
-            Ok(Mode::Exec)
+            for ens in enss.iter() {
+                check_expr_has_mode(typing, Mode::Spec, ens, Mode::Spec)?;
+            }
+            if let Some(dec) = decrease {
+                check_expr_has_mode(typing, Mode::Spec, dec, Mode::Spec)?;
+            }
+            Ok(loop_mode)
+        }
+        ExprX::BreakOrContinue { label, is_break: _ } => {
+            let target = typing.loops.iter().rev().find(|(l, _)| label.is_none() || l == label);
+            let loop_mode = target.expect("internal error: break/continue target").1;
+            if outer_mode != loop_mode {
+                return err_string(
+                    &expr.span,
+                    format!("cannot break or continue from {} code", outer_mode),
+                );
+            }
+            Ok(outer_mode)
         }
         ExprX::Block(ss, e1) => {
             for stmt in ss.iter() {
@@ -271,6 +386,30 @@ fn check_expr(typing: &mut Typing, outer_mode: Mode, expr: &Expr) -> Result<Mode
             }
             Ok(mode)
         }
+        ExprX::Return(e1) => {
+            if typing.fun_mode == Mode::Spec {
+                return err_str(&expr.span, "return is not allowed in spec functions");
+            }
+            if outer_mode != typing.fun_mode {
+                return err_string(
+                    &expr.span,
+                    format!(
+                        "cannot return from {} code in a function with mode {}",
+                        outer_mode, typing.fun_mode
+                    ),
+                );
+            }
+            match (e1, typing.ret_mode) {
+                (None, _) => {}
+                (Some(e1), None) => {
+                    check_expr(typing, outer_mode, e1)?;
+                }
+                (Some(e1), Some(ret_mode)) => {
+                    check_expr_has_mode(typing, outer_mode, e1, ret_mode)?;
+                }
+            }
+            Ok(outer_mode)
+        }
     }
 }
 
@@ -316,6 +455,8 @@ fn check_function(typing: &mut Typing, function: &Function) -> Result<(), VirErr
             );
         }
     }
+    typing.fun_mode = function.x.mode;
+    typing.ret_mode = if function.x.has_return() { Some(function.x.ret.x.mode) } else { None };
     if let Some(body) = &function.x.body {
         check_expr(typing, function.x.mode, body)?;
     }
@@ -334,7 +475,15 @@ pub fn check_crate(krate: &Krate) -> Result<ErasureModes, VirErr> {
         datatypes.insert(datatype.x.path.clone(), datatype.clone());
     }
     let erasure_modes = ErasureModes { condition_modes: vec![], var_modes: vec![] };
-    let mut typing = Typing { funs, datatypes, vars: ScopeMap::new(), erasure_modes };
+    let mut typing = Typing {
+        funs,
+        datatypes,
+        vars: ScopeMap::new(),
+        erasure_modes,
+        fun_mode: Mode::Exec,
+        ret_mode: None,
+        loops: Vec::new(),
+    };
     for function in krate.functions.iter() {
         check_function(&mut typing, function)?;
     }
@@ -5,7 +5,7 @@
 use air::{node, nodes_vec};
 use sise::Node;
 
-pub(crate) fn prelude_nodes() -> Vec<Node> {
+pub(crate) fn prelude_nodes(arch_word_bits: u32) -> Vec<Node> {
     #[allow(non_snake_case)]
     let FuelId = str_to_node(FUEL_ID);
     #[allow(non_snake_case)]
@@ -21,8 +21,18 @@ pub(crate) fn prelude_nodes() -> Vec<Node> {
     let u_clip = str_to_node(U_CLIP);
     let i_clip = str_to_node(I_CLIP);
     let nat_clip = str_to_node(NAT_CLIP);
+    let u_trunc = str_to_node(U_TRUNC);
+    let i_trunc = str_to_node(I_TRUNC);
     let u_inv = str_to_node(U_INV);
     let i_inv = str_to_node(I_INV);
+    let trunc_div = str_to_node(TRUNC_DIV);
+    let trunc_mod = str_to_node(TRUNC_MOD);
+    let bit_not = str_to_node(BIT_NOT);
+    let bit_and = str_to_node(BIT_AND);
+    let bit_or = str_to_node(BIT_OR);
+    let bit_xor = str_to_node(BIT_XOR);
+    let bit_shr = str_to_node(BIT_SHR);
+    let bit_shl = str_to_node(BIT_SHL);
     let arch_size = str_to_node(ARCH_SIZE);
     let check_decrease_int =
         str_to_node(&suffix_global_id(&path_to_air_ident(&check_decrease_int())));
@@ -40,6 +50,40 @@ pub(crate) fn prelude_nodes() -> Vec<Node> {
     let type_id_uint = str_to_node(TYPE_ID_UINT);
     let type_id_sint = str_to_node(TYPE_ID_SINT);
     let has_type = str_to_node(HAS_TYPE);
+    #[allow(non_snake_case)]
+    let Seq = str_to_node(SEQ);
+    let box_seq = str_to_node(BOX_SEQ);
+    let unbox_seq = str_to_node(UNBOX_SEQ);
+    let type_id_seq = str_to_node(TYPE_ID_SEQ);
+    let type_id_array = str_to_node(TYPE_ID_ARRAY);
+    let seq_empty = str_to_node(SEQ_EMPTY);
+    let seq_push = str_to_node(SEQ_PUSH);
+    let seq_len = str_to_node(SEQ_LEN);
+    let seq_index = str_to_node(SEQ_INDEX);
+    let seq_update = str_to_node(SEQ_UPDATE);
+    let seq_subrange = str_to_node(SEQ_SUBRANGE);
+    let seq_ext_equal = str_to_node(SEQ_EXT_EQUAL);
+    #[allow(non_snake_case)]
+    let Set = str_to_node(SET);
+    let box_set = str_to_node(BOX_SET);
+    let unbox_set = str_to_node(UNBOX_SET);
+    let type_id_set = str_to_node(TYPE_ID_SET);
+    let set_empty = str_to_node(SET_EMPTY);
+    let set_contains = str_to_node(SET_CONTAINS);
+    let set_insert = str_to_node(SET_INSERT);
+    let set_remove = str_to_node(SET_REMOVE);
+    let set_ext_equal = str_to_node(SET_EXT_EQUAL);
+    #[allow(non_snake_case)]
+    let Map = str_to_node(MAP);
+    let box_map = str_to_node(BOX_MAP);
+    let unbox_map = str_to_node(UNBOX_MAP);
+    let type_id_map = str_to_node(TYPE_ID_MAP);
+    let map_empty = str_to_node(MAP_EMPTY);
+    let map_dom = str_to_node(MAP_DOM);
+    let map_index = str_to_node(MAP_INDEX);
+    let map_insert = str_to_node(MAP_INSERT);
+    let map_remove = str_to_node(MAP_REMOVE);
+    let map_ext_equal = str_to_node(MAP_EXT_EQUAL);
 
     nodes_vec!(
         // Fuel
@@ -115,9 +159,8 @@ pub(crate) fn prelude_nodes() -> Vec<Node> {
         )))
 
         // Integers
-        // TODO: make this more configurable via options or HeaderExpr directives
         (declare-const [arch_size] Int) // number of bits for usize/isize
-        (axiom (or (= [arch_size] {str_to_node(&ARCH_SIZE_MIN_BITS.to_string())}) (= [arch_size] 64)))
+        (axiom (= [arch_size] {str_to_node(&arch_word_bits.to_string())}))
         (declare-fun [u_hi] (Int) Int) // \
         (declare-fun [i_lo] (Int) Int) // - convert number of bits to integer ranges
         (declare-fun [i_hi] (Int) Int) // /
@@ -167,6 +210,19 @@ pub(crate) fn prelude_nodes() -> Vec<Node> {
             )
             :pattern (([i_clip] bits i))
         )))
+        // truncate functions f(num_bits, value), which wrap like Rust's as casts
+        (declare-fun [u_trunc] (Int Int) Int)
+        (declare-fun [i_trunc] (Int Int) Int)
+        (axiom (forall ((bits Int) (i Int)) (!
+            (= ([u_trunc] bits i) (mod i ([u_hi] bits)))
+            :pattern (([u_trunc] bits i))
+        )))
+        (axiom (forall ((bits Int) (i Int)) (!
+            (= ([i_trunc] bits i)
+                (+ ([i_lo] bits) (mod (- i ([i_lo] bits)) ([u_hi] bits)))
+            )
+            :pattern (([i_trunc] bits i))
+        )))
         // type invariants inv(num_bits, value)
         (declare-fun [u_inv] (Int Int) Bool)
         (declare-fun [i_inv] (Int Int) Bool)
@@ -204,6 +260,350 @@ pub(crate) fn prelude_nodes() -> Vec<Node> {
             :pattern (([has_type] x ([type_id_sint] bits)))
         )))
 
+        // Truncating division and remainder, defined in terms of Euclidean div and mod
+        (declare-fun [trunc_div] (Int Int) Int)
+        (declare-fun [trunc_mod] (Int Int) Int)
+        (axiom (forall ((x Int) (y Int)) (!
+            (= ([trunc_div] x y)
+                (ite (or (<= 0 x) (= (mod x y) 0))
+                    (div x y)
+                    (ite (< 0 y) (+ (div x y) 1) (- (div x y) 1))
+                )
+            )
+            :pattern (([trunc_div] x y))
+        )))
+        (axiom (forall ((x Int) (y Int)) (!
+            (= ([trunc_mod] x y)
+                (ite (or (<= 0 x) (= (mod x y) 0))
+                    (mod x y)
+                    (ite (< 0 y) (- (mod x y) y) (+ (mod x y) y))
+                )
+            )
+            :pattern (([trunc_mod] x y))
+        )))
+
+        // Bitwise operators (only interpreted in bit-vector queries)
+        (declare-fun [bit_not] (Int) Int)
+        (declare-fun [bit_and] (Int Int) Int)
+        (declare-fun [bit_or] (Int Int) Int)
+        (declare-fun [bit_xor] (Int Int) Int)
+        (declare-fun [bit_shr] (Int Int) Int)
+        (declare-fun [bit_shl] (Int Int) Int)
+
+        // Sequences, which also represent arrays and slices; elements are boxed as Poly
+        (declare-sort [Seq])
+        (declare-fun [box_seq] ([Seq]) [Poly])
+        (declare-fun [unbox_seq] ([Poly]) [Seq])
+        (declare-fun [type_id_seq] ([typ]) [typ])
+        (declare-fun [type_id_array] ([typ] Int) [typ]) // element type, length
+        (axiom (forall ((x [Seq])) (!
+            (= x ([unbox_seq] ([box_seq] x)))
+            :pattern (([box_seq] x))
+        )))
+        (axiom (forall ((t [typ]) (x [Poly])) (!
+            (=>
+                ([has_type] x ([type_id_seq] t))
+                (= x ([box_seq] ([unbox_seq] x)))
+            )
+            :pattern (([has_type] x ([type_id_seq] t)))
+        )))
+        (axiom (forall ((t [typ]) (n Int) (x [Poly])) (!
+            (=>
+                ([has_type] x ([type_id_array] t n))
+                (and
+                    ([has_type] x ([type_id_seq] t))
+                    (= ([seq_len] ([unbox_seq] x)) n)
+                )
+            )
+            :pattern (([has_type] x ([type_id_array] t n)))
+        )))
+        (declare-const [seq_empty] [Seq])
+        (declare-fun [seq_push] ([Seq] [Poly]) [Seq])
+        (declare-fun [seq_len] ([Seq]) Int)
+        (declare-fun [seq_index] ([Seq] Int) [Poly])
+        (declare-fun [seq_update] ([Seq] Int [Poly]) [Seq])
+        (axiom (forall ((s [Seq])) (!
+            (<= 0 ([seq_len] s))
+            :pattern (([seq_len] s))
+        )))
+        (axiom (= ([seq_len] [seq_empty]) 0))
+        (axiom (forall ((s [Seq]) (a [Poly])) (!
+            (= ([seq_len] ([seq_push] s a)) (+ ([seq_len] s) 1))
+            :pattern (([seq_push] s a))
+        )))
+        (axiom (forall ((s [Seq]) (a [Poly]) (i Int)) (!
+            (=>
+                (and (<= 0 i) (<= i ([seq_len] s)))
+                (= ([seq_index] ([seq_push] s a) i) (ite (= i ([seq_len] s)) a ([seq_index] s i)))
+            )
+            :pattern (([seq_index] ([seq_push] s a) i))
+        )))
+        (axiom (forall ((s [Seq]) (i Int) (a [Poly])) (!
+            (= ([seq_len] ([seq_update] s i a)) ([seq_len] s))
+            :pattern (([seq_update] s i a))
+        )))
+        (axiom (forall ((s [Seq]) (i Int) (a [Poly]) (j Int)) (!
+            (=>
+                (and (<= 0 j) (< j ([seq_len] s)))
+                (= ([seq_index] ([seq_update] s i a) j) (ite (= i j) a ([seq_index] s j)))
+            )
+            :pattern (([seq_index] ([seq_update] s i a) j))
+        )))
+        (axiom (forall ((t [typ]) (s [Seq]) (i Int)) (!
+            (=>
+                (and ([has_type] ([box_seq] s) ([type_id_seq] t)) (<= 0 i) (< i ([seq_len] s)))
+                ([has_type] ([seq_index] s i) t)
+            )
+            :pattern (([has_type] ([box_seq] s) ([type_id_seq] t)) ([seq_index] s i))
+        )))
+        (axiom (forall ((t [typ])) (!
+            ([has_type] ([box_seq] [seq_empty]) ([type_id_seq] t))
+            :pattern (([has_type] ([box_seq] [seq_empty]) ([type_id_seq] t)))
+        )))
+        (axiom (forall ((t [typ]) (s [Seq]) (a [Poly])) (!
+            (=>
+                (and ([has_type] ([box_seq] s) ([type_id_seq] t)) ([has_type] a t))
+                ([has_type] ([box_seq] ([seq_push] s a)) ([type_id_seq] t))
+            )
+            :pattern (([has_type] ([box_seq] ([seq_push] s a)) ([type_id_seq] t)))
+        )))
+        (axiom (forall ((t [typ]) (s [Seq]) (i Int) (a [Poly])) (!
+            (=>
+                (and ([has_type] ([box_seq] s) ([type_id_seq] t)) ([has_type] a t))
+                ([has_type] ([box_seq] ([seq_update] s i a)) ([type_id_seq] t))
+            )
+            :pattern (([has_type] ([box_seq] ([seq_update] s i a)) ([type_id_seq] t)))
+        )))
+        (declare-fun [seq_subrange] ([Seq] Int Int) [Seq])
+        (axiom (forall ((s [Seq]) (i Int) (j Int)) (!
+            (=>
+                (and (<= 0 i) (<= i j) (<= j ([seq_len] s)))
+                (= ([seq_len] ([seq_subrange] s i j)) (- j i))
+            )
+            :pattern (([seq_subrange] s i j))
+        )))
+        (axiom (forall ((s [Seq]) (i Int) (j Int) (k Int)) (!
+            (=>
+                (and (<= 0 i) (<= i j) (<= j ([seq_len] s)) (<= 0 k) (< k (- j i)))
+                (= ([seq_index] ([seq_subrange] s i j) k) ([seq_index] s (+ i k)))
+            )
+            :pattern (([seq_index] ([seq_subrange] s i j) k))
+        )))
+        (axiom (forall ((t [typ]) (s [Seq]) (i Int) (j Int)) (!
+            (=>
+                (and
+                    ([has_type] ([box_seq] s) ([type_id_seq] t))
+                    (<= 0 i) (<= i j) (<= j ([seq_len] s))
+                )
+                ([has_type] ([box_seq] ([seq_subrange] s i j)) ([type_id_seq] t))
+            )
+            :pattern (([has_type] ([box_seq] ([seq_subrange] s i j)) ([type_id_seq] t)))
+        )))
+        (declare-fun [seq_ext_equal] ([Seq] [Seq]) Bool)
+        (axiom (forall ((s1 [Seq]) (s2 [Seq])) (!
+            (= ([seq_ext_equal] s1 s2)
+                (and
+                    (= ([seq_len] s1) ([seq_len] s2))
+                    (forall ((i Int)) (!
+                        (=>
+                            (and (<= 0 i) (< i ([seq_len] s1)))
+                            (= ([seq_index] s1 i) ([seq_index] s2 i))
+                        )
+                        :pattern (([seq_index] s1 i))
+                        :pattern (([seq_index] s2 i))
+                    ))
+                )
+            )
+            :pattern (([seq_ext_equal] s1 s2))
+        )))
+        (axiom (forall ((s1 [Seq]) (s2 [Seq])) (!
+            (=> ([seq_ext_equal] s1 s2) (= s1 s2))
+            :pattern (([seq_ext_equal] s1 s2))
+        )))
+
+        // Sets; elements are boxed as Poly
+        (declare-sort [Set])
+        (declare-fun [box_set] ([Set]) [Poly])
+        (declare-fun [unbox_set] ([Poly]) [Set])
+        (declare-fun [type_id_set] ([typ]) [typ])
+        (axiom (forall ((x [Set])) (!
+            (= x ([unbox_set] ([box_set] x)))
+            :pattern (([box_set] x))
+        )))
+        (axiom (forall ((t [typ]) (x [Poly])) (!
+            (=>
+                ([has_type] x ([type_id_set] t))
+                (= x ([box_set] ([unbox_set] x)))
+            )
+            :pattern (([has_type] x ([type_id_set] t)))
+        )))
+        (declare-const [set_empty] [Set])
+        (declare-fun [set_contains] ([Set] [Poly]) Bool)
+        (declare-fun [set_insert] ([Set] [Poly]) [Set])
+        (declare-fun [set_remove] ([Set] [Poly]) [Set])
+        (axiom (forall ((a [Poly])) (!
+            (not ([set_contains] [set_empty] a))
+            :pattern (([set_contains] [set_empty] a))
+        )))
+        (axiom (forall ((s [Set]) (a [Poly])) (!
+            ([set_contains] ([set_insert] s a) a)
+            :pattern (([set_insert] s a))
+        )))
+        (axiom (forall ((s [Set]) (a [Poly]) (b [Poly])) (!
+            (= ([set_contains] ([set_insert] s a) b) (or (= a b) ([set_contains] s b)))
+            :pattern (([set_contains] ([set_insert] s a) b))
+        )))
+        (axiom (forall ((s [Set]) (a [Poly])) (!
+            (not ([set_contains] ([set_remove] s a) a))
+            :pattern (([set_remove] s a))
+        )))
+        (axiom (forall ((s [Set]) (a [Poly]) (b [Poly])) (!
+            (= ([set_contains] ([set_remove] s a) b) (and (not (= a b)) ([set_contains] s b)))
+            :pattern (([set_contains] ([set_remove] s a) b))
+        )))
+        (axiom (forall ((t [typ]) (s [Set]) (a [Poly])) (!
+            (=>
+                (and ([has_type] ([box_set] s) ([type_id_set] t)) ([set_contains] s a))
+                ([has_type] a t)
+            )
+            :pattern (([has_type] ([box_set] s) ([type_id_set] t)) ([set_contains] s a))
+        )))
+        (axiom (forall ((t [typ])) (!
+            ([has_type] ([box_set] [set_empty]) ([type_id_set] t))
+            :pattern (([has_type] ([box_set] [set_empty]) ([type_id_set] t)))
+        )))
+        (axiom (forall ((t [typ]) (s [Set]) (a [Poly])) (!
+            (=>
+                (and ([has_type] ([box_set] s) ([type_id_set] t)) ([has_type] a t))
+                ([has_type] ([box_set] ([set_insert] s a)) ([type_id_set] t))
+            )
+            :pattern (([has_type] ([box_set] ([set_insert] s a)) ([type_id_set] t)))
+        )))
+        (axiom (forall ((t [typ]) (s [Set]) (a [Poly])) (!
+            (=>
+                ([has_type] ([box_set] s) ([type_id_set] t))
+                ([has_type] ([box_set] ([set_remove] s a)) ([type_id_set] t))
+            )
+            :pattern (([has_type] ([box_set] ([set_remove] s a)) ([type_id_set] t)))
+        )))
+        (declare-fun [set_ext_equal] ([Set] [Set]) Bool)
+        (axiom (forall ((s1 [Set]) (s2 [Set])) (!
+            (= ([set_ext_equal] s1 s2)
+                (forall ((a [Poly])) (!
+                    (= ([set_contains] s1 a) ([set_contains] s2 a))
+                    :pattern (([set_contains] s1 a))
+                    :pattern (([set_contains] s2 a))
+                ))
+            )
+            :pattern (([set_ext_equal] s1 s2))
+        )))
+        (axiom (forall ((s1 [Set]) (s2 [Set])) (!
+            (=> ([set_ext_equal] s1 s2) (= s1 s2))
+            :pattern (([set_ext_equal] s1 s2))
+        )))
+
+        // Maps; keys and values are boxed as Poly
+        (declare-sort [Map])
+        (declare-fun [box_map] ([Map]) [Poly])
+        (declare-fun [unbox_map] ([Poly]) [Map])
+        (declare-fun [type_id_map] ([typ] [typ]) [typ]) // key type, value type
+        (axiom (forall ((x [Map])) (!
+            (= x ([unbox_map] ([box_map] x)))
+            :pattern (([box_map] x))
+        )))
+        (axiom (forall ((tk [typ]) (tv [typ]) (x [Poly])) (!
+            (=>
+                ([has_type] x ([type_id_map] tk tv))
+                (= x ([box_map] ([unbox_map] x)))
+            )
+            :pattern (([has_type] x ([type_id_map] tk tv)))
+        )))
+        (declare-const [map_empty] [Map])
+        (declare-fun [map_dom] ([Map]) [Set])
+        (declare-fun [map_index] ([Map] [Poly]) [Poly])
+        (declare-fun [map_insert] ([Map] [Poly] [Poly]) [Map])
+        (declare-fun [map_remove] ([Map] [Poly]) [Map])
+        (axiom (= ([map_dom] [map_empty]) [set_empty]))
+        (axiom (forall ((m [Map]) (k [Poly]) (v [Poly])) (!
+            (= ([map_dom] ([map_insert] m k v)) ([set_insert] ([map_dom] m) k))
+            :pattern (([map_dom] ([map_insert] m k v)))
+        )))
+        (axiom (forall ((m [Map]) (k [Poly]) (v [Poly]) (j [Poly])) (!
+            (= ([map_index] ([map_insert] m k v) j) (ite (= k j) v ([map_index] m j)))
+            :pattern (([map_index] ([map_insert] m k v) j))
+        )))
+        (axiom (forall ((m [Map]) (k [Poly])) (!
+            (= ([map_dom] ([map_remove] m k)) ([set_remove] ([map_dom] m) k))
+            :pattern (([map_dom] ([map_remove] m k)))
+        )))
+        (axiom (forall ((m [Map]) (k [Poly]) (j [Poly])) (!
+            (=>
+                (not (= k j))
+                (= ([map_index] ([map_remove] m k) j) ([map_index] m j))
+            )
+            :pattern (([map_index] ([map_remove] m k) j))
+        )))
+        (axiom (forall ((tk [typ]) (tv [typ]) (m [Map])) (!
+            (=>
+                ([has_type] ([box_map] m) ([type_id_map] tk tv))
+                ([has_type] ([box_set] ([map_dom] m)) ([type_id_set] tk))
+            )
+            :pattern (([has_type] ([box_map] m) ([type_id_map] tk tv)) ([map_dom] m))
+        )))
+        (axiom (forall ((tk [typ]) (tv [typ]) (m [Map]) (k [Poly])) (!
+            (=>
+                (and
+                    ([has_type] ([box_map] m) ([type_id_map] tk tv))
+                    ([set_contains] ([map_dom] m) k)
+                )
+                ([has_type] ([map_index] m k) tv)
+            )
+            :pattern (([has_type] ([box_map] m) ([type_id_map] tk tv)) ([map_index] m k))
+        )))
+        (axiom (forall ((tk [typ]) (tv [typ])) (!
+            ([has_type] ([box_map] [map_empty]) ([type_id_map] tk tv))
+            :pattern (([has_type] ([box_map] [map_empty]) ([type_id_map] tk tv)))
+        )))
+        (axiom (forall ((tk [typ]) (tv [typ]) (m [Map]) (k [Poly]) (v [Poly])) (!
+            (=>
+                (and
+                    ([has_type] ([box_map] m) ([type_id_map] tk tv))
+                    ([has_type] k tk)
+                    ([has_type] v tv)
+                )
+                ([has_type] ([box_map] ([map_insert] m k v)) ([type_id_map] tk tv))
+            )
+            :pattern (([has_type] ([box_map] ([map_insert] m k v)) ([type_id_map] tk tv)))
+        )))
+        (axiom (forall ((tk [typ]) (tv [typ]) (m [Map]) (k [Poly])) (!
+            (=>
+                ([has_type] ([box_map] m) ([type_id_map] tk tv))
+                ([has_type] ([box_map] ([map_remove] m k)) ([type_id_map] tk tv))
+            )
+            :pattern (([has_type] ([box_map] ([map_remove] m k)) ([type_id_map] tk tv)))
+        )))
+        (declare-fun [map_ext_equal] ([Map] [Map]) Bool)
+        (axiom (forall ((m1 [Map]) (m2 [Map])) (!
+            (= ([map_ext_equal] m1 m2)
+                (and
+                    ([set_ext_equal] ([map_dom] m1) ([map_dom] m2))
+                    (forall ((k [Poly])) (!
+                        (=>
+                            ([set_contains] ([map_dom] m1) k)
+                            (= ([map_index] m1 k) ([map_index] m2 k))
+                        )
+                        :pattern (([map_index] m1 k))
+                        :pattern (([map_index] m2 k))
+                    ))
+                )
+            )
+            :pattern (([map_ext_equal] m1 m2))
+        )))
+        (axiom (forall ((m1 [Map]) (m2 [Map])) (!
+            (=> ([map_ext_equal] m1 m2) (= m1 m2))
+            :pattern (([map_ext_equal] m1 m2))
+        )))
+
         // Decreases
         (declare-fun [check_decrease_int] (Int Int) Bool)
         (axiom (forall ((cur Int) (prev Int)) (!
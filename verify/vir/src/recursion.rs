@@ -1,12 +1,12 @@
 use crate::ast::{
-    BinaryOp, Constant, Function, Ident, IntRange, Params, Path, Typ, TypX, UnaryOp, UnaryOpr,
-    VirErr,
+    BinaryOp, Constant, Function, Ident, IntRange, Mode, Params, Path, Typ, TypX, UnaryOp,
+    UnaryOpr, VirErr,
 };
 use crate::ast_util::err_str;
 use crate::ast_visitor::map_expr_visitor;
 use crate::context::Ctx;
 use crate::def::{
-    check_decrease_int, height, prefix_recursive, suffix_rename, Spanned, DECREASE_AT_ENTRY,
+    check_decrease_int, decrease_at_entry, height, prefix_recursive, suffix_rename, Spanned,
     FUEL_PARAM,
 };
 use crate::scc::Graph;
@@ -21,37 +21,70 @@
 struct Ctxt<'a> {
     recursive_function_path: Path,
     params: Params,
-    decreases_at_entry: Ident,
-    decreases_exp: Exp,
-    decreases_typ: Typ,
+    // One entry per component of a lexicographic decreases clause
+    decreases_at_entry: Vec<Ident>,
+    decreases_exps: Vec<Exp>,
+    decreases_typs: Vec<Typ>,
     scc_rep: Path,
     ctx: &'a Ctx,
 }
 
-fn height_of_exp(ctxt: &Ctxt, exp: &Exp) -> Exp {
-    match &*ctxt.decreases_typ {
-        TypX::Int(_) => exp.clone(),
+fn height_of_exp(typ: &Typ, exp: &Exp) -> Result<Exp, VirErr> {
+    match &**typ {
+        TypX::Int(_) => Ok(exp.clone()),
         TypX::Datatype(..) => {
-            let op = UnaryOpr::Box(ctxt.decreases_typ.clone());
+            let op = UnaryOpr::Box(typ.clone());
             let arg = Spanned::new(exp.span.clone(), ExpX::UnaryOpr(op, exp.clone()));
             let call = ExpX::Call(height(), Arc::new(vec![]), Arc::new(vec![arg]));
-            Spanned::new(exp.span.clone(), call)
+            Ok(Spanned::new(exp.span.clone(), call))
         }
-        // TODO: non-panic error message
-        _ => panic!("internal error: unsupported type for decreases {:?}", ctxt.decreases_typ),
+        _ => err_str(&exp.span, "decreases must have integer or datatype type"),
     }
 }
 
-fn check_decrease(ctxt: &Ctxt, exp: &Exp) -> Exp {
-    let decreases_at_entry =
-        Spanned::new(exp.span.clone(), ExpX::Var((ctxt.decreases_at_entry.clone(), Some(0))));
-    // 0 <= decreases_exp < decreases_at_entry
-    let args = vec![height_of_exp(ctxt, exp), decreases_at_entry];
-    let call = ExpX::Call(check_decrease_int(), Arc::new(vec![]), Arc::new(args));
-    Spanned::new(exp.span.clone(), call)
+fn mk_binary(span: &Span, op: BinaryOp, e1: Exp, e2: Exp) -> Exp {
+    Spanned::new(span.clone(), ExpX::Binary(op, e1, e2))
 }
 
-fn check_decrease_rename(ctxt: &Ctxt, span: &Span, args: &Exps) -> Exp {
+// Lexicographic check of components 0..=level:
+//   dec_0 || (cur_0 == prev_0 && (dec_1 || (cur_1 == prev_1 && ... dec_level)))
+// where dec_i is 0 <= cur_i < prev_i.
+// For level < n - 1, the innermost component may also stay equal,
+// so that a failure can be attributed to the first component that does not decrease.
+fn check_decrease(ctxt: &Ctxt, span: &Span, exps: &[Exp], level: usize) -> Result<Exp, VirErr> {
+    let strict = level + 1 == exps.len();
+    let mut e: Option<Exp> = None;
+    for i in (0..=level).rev() {
+        let exp = &exps[i];
+        let cur = height_of_exp(&ctxt.decreases_typs[i], exp)?;
+        let prev = Spanned::new(
+            exp.span.clone(),
+            ExpX::Var((ctxt.decreases_at_entry[i].clone(), Some(0))),
+        );
+        // 0 <= cur < prev
+        let args = Arc::new(vec![cur.clone(), prev.clone()]);
+        let dec = Spanned::new(
+            exp.span.clone(),
+            ExpX::Call(check_decrease_int(), Arc::new(vec![]), args),
+        );
+        let eq = mk_binary(span, BinaryOp::Eq(Mode::Spec), cur, prev);
+        e = Some(match e {
+            None if strict => dec,
+            None => mk_binary(span, BinaryOp::Or, dec, eq),
+            Some(inner) => {
+                mk_binary(span, BinaryOp::Or, dec, mk_binary(span, BinaryOp::And, eq, inner))
+            }
+        });
+    }
+    Ok(e.expect("decreases clause must have at least one component"))
+}
+
+fn check_decrease_rename(
+    ctxt: &Ctxt,
+    span: &Span,
+    args: &Exps,
+    level: usize,
+) -> Result<Exp, VirErr> {
     // check_decrease(let params = args in decreases_exp, decreases_at_entry)
     let binders: Vec<Binder<Exp>> = ctxt
         .params
@@ -59,34 +92,49 @@ fn check_decrease_rename(ctxt: &Ctxt, span: &Span, args: &Exps) -> Exp {
         .zip(args.iter())
         .map(|(param, arg)| ident_binder(&suffix_rename(&param.x.name), &arg.clone()))
         .collect();
+    let binders = Arc::new(binders);
     let renames: HashMap<UniqueIdent, UniqueIdent> = ctxt
         .params
         .iter()
         .map(|param| ((param.x.name.clone(), Some(0)), (suffix_rename(&param.x.name), None)))
         .collect();
-    let dec_exp = exp_rename_vars(&ctxt.decreases_exp, &renames);
-    let e_dec = Spanned::new(
-        span.clone(),
-        ExpX::Bind(Spanned::new(span.clone(), BndX::Let(Arc::new(binders))), dec_exp),
-    );
-    check_decrease(ctxt, &e_dec)
+    let e_decs: Vec<Exp> = ctxt
+        .decreases_exps
+        .iter()
+        .map(|dec_exp| {
+            let dec_exp = exp_rename_vars(dec_exp, &renames);
+            Spanned::new(
+                span.clone(),
+                ExpX::Bind(Spanned::new(span.clone(), BndX::Let(binders.clone())), dec_exp),
+            )
+        })
+        .collect();
+    check_decrease(ctxt, span, &e_decs, level)
 }
 
-fn update_decreases_exp<'a>(ctxt: &'a Ctxt, name: &Path) -> Result<Ctxt<'a>, VirErr> {
+fn update_decreases_exp<'a>(ctxt: &'a Ctxt, span: &Span, name: &Path) -> Result<Ctxt<'a>, VirErr> {
     let function = ctxt.ctx.func_map.get(name).expect("func_map should hold all functions");
-    let new_decreases_expr = function
-        .x
-        .decrease
-        .as_ref()
-        .expect("shouldn't call update_decreases_exp on a function without a decreases clause")
-        .clone();
-    let new_decreases_exp =
-        crate::ast_to_sst::expr_to_exp(ctxt.ctx, &function.x.params, &new_decreases_expr)?;
-    Ok(Ctxt { decreases_exp: new_decreases_exp, ..ctxt.clone() })
+    if function.x.decrease.len() == 0 {
+        return err_str(&function.span, "recursive function must call decreases(...)");
+    }
+    if function.x.decrease.len() != ctxt.decreases_exps.len() {
+        return err_str(
+            span,
+            "mutually recursive functions must have decreases clauses with the same number of components",
+        );
+    }
+    let mut decreases_exps: Vec<Exp> = Vec::new();
+    let mut decreases_typs: Vec<Typ> = Vec::new();
+    for dec in function.x.decrease.iter() {
+        decreases_exps.push(crate::ast_to_sst::expr_to_exp(ctxt.ctx, &function.x.params, dec)?);
+        decreases_typs.push(dec.typ.clone());
+    }
+    Ok(Ctxt { decreases_exps, decreases_typs, ..ctxt.clone() })
 }
 
 // Check that exp terminates
-fn terminates(ctxt: &Ctxt, exp: &Exp) -> Result<Exp, VirErr> {
+// (considering components 0..=level of the decreases clause; see check_decrease)
+fn terminates(ctxt: &Ctxt, level: usize, exp: &Exp) -> Result<Exp, VirErr> {
     match &exp.x {
         ExpX::Const(_) | ExpX::Var(..) | ExpX::Old(..) => {
             Ok(Spanned::new(exp.span.clone(), ExpX::Const(Constant::Bool(true))))
@@ -95,13 +143,13 @@ fn terminates(ctxt: &Ctxt, exp: &Exp) -> Result<Exp, VirErr> {
             let mut e = if *x == ctxt.recursive_function_path
                 || ctxt.ctx.func_call_graph.get_scc_rep(x) == ctxt.scc_rep
             {
-                let new_ctxt = update_decreases_exp(&ctxt, x)?;
-                check_decrease_rename(&new_ctxt, &exp.span, args)
+                let new_ctxt = update_decreases_exp(&ctxt, &exp.span, x)?;
+                check_decrease_rename(&new_ctxt, &exp.span, args, level)?
             } else {
                 Spanned::new(exp.span.clone(), ExpX::Const(Constant::Bool(true)))
             };
             for arg in args.iter().rev() {
-                let e_arg = terminates(ctxt, arg)?;
+                let e_arg = terminates(ctxt, level, arg)?;
                 e = Spanned::new(exp.span.clone(), ExpX::Binary(BinaryOp::And, e_arg, e));
             }
             Ok(e)
@@ -109,41 +157,50 @@ fn terminates(ctxt: &Ctxt, exp: &Exp) -> Result<Exp, VirErr> {
         ExpX::Ctor(_path, _ident, binders) => {
             let mut e = Spanned::new(exp.span.clone(), ExpX::Const(Constant::Bool(true)));
             for binder in binders.iter().rev() {
-                let e_binder = terminates(ctxt, &binder.a)?;
+                let e_binder = terminates(ctxt, level, &binder.a)?;
                 e = Spanned::new(exp.span.clone(), ExpX::Binary(BinaryOp::And, e_binder, e));
             }
             Ok(e)
         }
-        ExpX::Unary(_, e1) => terminates(ctxt, e1),
-        ExpX::UnaryOpr(_, e1) => terminates(ctxt, e1),
+        ExpX::Loc(e1) => terminates(ctxt, level, e1),
+        ExpX::Unary(_, e1) => terminates(ctxt, level, e1),
+        ExpX::UnaryOpr(_, e1) => terminates(ctxt, level, e1),
         ExpX::Binary(BinaryOp::And, e1, e2) | ExpX::Binary(BinaryOp::Implies, e1, e2) => {
-            let t_e1 = terminates(ctxt, e1)?;
-            let t_e2 = terminates(ctxt, e2)?;
+            let t_e1 = terminates(ctxt, level, e1)?;
+            let t_e2 = terminates(ctxt, level, e2)?;
             let imply =
                 Spanned::new(exp.span.clone(), ExpX::Binary(BinaryOp::Implies, e1.clone(), t_e2));
             Ok(Spanned::new(exp.span.clone(), ExpX::Binary(BinaryOp::And, t_e1, imply)))
         }
         ExpX::Binary(BinaryOp::Or, e1, e2) => {
-            let t_e1 = terminates(ctxt, e1)?;
-            let t_e2 = terminates(ctxt, e2)?;
+            let t_e1 = terminates(ctxt, level, e1)?;
+            let t_e2 = terminates(ctxt, level, e2)?;
             let not = Spanned::new(exp.span.clone(), ExpX::Unary(UnaryOp::Not, e1.clone()));
             let imply = Spanned::new(exp.span.clone(), ExpX::Binary(BinaryOp::Implies, not, t_e2));
             Ok(Spanned::new(exp.span.clone(), ExpX::Binary(BinaryOp::And, t_e1, imply)))
         }
         ExpX::Binary(_, e1, e2) => {
-            let e1 = terminates(ctxt, e1)?;
-            let e2 = terminates(ctxt, e2)?;
+            let e1 = terminates(ctxt, level, e1)?;
+            let e2 = terminates(ctxt, level, e2)?;
             Ok(Spanned::new(exp.span.clone(), ExpX::Binary(BinaryOp::And, e1, e2)))
         }
+        ExpX::SeqOp(_, args) | ExpX::SetOp(_, args) | ExpX::MapOp(_, args) => {
+            let mut e = Spanned::new(exp.span.clone(), ExpX::Const(Constant::Bool(true)));
+            for arg in args.iter().rev() {
+                let e_arg = terminates(ctxt, level, arg)?;
+                e = Spanned::new(exp.span.clone(), ExpX::Binary(BinaryOp::And, e_arg, e));
+            }
+            Ok(e)
+        }
         ExpX::If(e1, e2, e3) => {
-            let t_e1 = terminates(ctxt, e1)?;
-            let t_e2 = terminates(ctxt, e2)?;
-            let t_e3 = terminates(ctxt, e3)?;
+            let t_e1 = terminates(ctxt, level, e1)?;
+            let t_e2 = terminates(ctxt, level, e2)?;
+            let t_e3 = terminates(ctxt, level, e3)?;
             let e_if = Spanned::new(exp.span.clone(), ExpX::If(e1.clone(), t_e2, t_e3));
             Ok(Spanned::new(exp.span.clone(), ExpX::Binary(BinaryOp::And, t_e1, e_if)))
         }
         ExpX::Bind(bnd, e1) => {
-            let t_e1 = terminates(ctxt, e1)?;
+            let t_e1 = terminates(ctxt, level, e1)?;
             match &bnd.x {
                 BndX::Let(binders) => {
                     let mut e_bind = Spanned::new(
@@ -154,7 +211,7 @@ fn terminates(ctxt: &Ctxt, exp: &Exp) -> Result<Exp, VirErr> {
                         ),
                     );
                     for binder in binders.iter().rev() {
-                        let e_binder = terminates(ctxt, &binder.a)?;
+                        let e_binder = terminates(ctxt, level, &binder.a)?;
                         e_bind = Spanned::new(
                             exp.span.clone(),
                             ExpX::Binary(BinaryOp::And, e_binder, e_bind),
@@ -172,6 +229,20 @@ fn terminates(ctxt: &Ctxt, exp: &Exp) -> Result<Exp, VirErr> {
                         t_e1,
                     ),
                 )),
+                BndX::Choose(binder, triggers, _, _) => Ok(Spanned::new(
+                    exp.span.clone(),
+                    ExpX::Bind(
+                        Spanned::new(
+                            bnd.span.clone(),
+                            BndX::Quant(
+                                Quant::Forall,
+                                Arc::new(vec![binder.clone()]),
+                                triggers.clone(),
+                            ),
+                        ),
+                        t_e1,
+                    ),
+                )),
             }
         }
     }
@@ -214,21 +285,61 @@ pub(crate) fn is_recursive_stm(ctx: &Ctx, name: &Path, body: &Stm) -> bool {
     }
 }
 
-fn mk_decreases_at_entry(ctxt: &Ctxt, span: &Span) -> (LocalDecl, Stm) {
-    let decl = Arc::new(LocalDeclX {
-        ident: (ctxt.decreases_at_entry.clone(), Some(0)),
-        typ: Arc::new(TypX::Int(IntRange::Int)),
-        mutable: false,
-    });
-    let stm_assign = Spanned::new(
-        span.clone(),
-        StmX::Assign {
-            lhs: (ctxt.decreases_at_entry.clone(), Some(0)),
-            rhs: height_of_exp(ctxt, &ctxt.decreases_exp),
-            is_init: true,
-        },
-    );
-    (decl, stm_assign)
+fn mk_decreases_at_entry(ctxt: &Ctxt, span: &Span) -> Result<(Vec<LocalDecl>, Vec<Stm>), VirErr> {
+    let mut decls: Vec<LocalDecl> = Vec::new();
+    let mut stm_assigns: Vec<Stm> = Vec::new();
+    for (i, exp) in ctxt.decreases_exps.iter().enumerate() {
+        let decl = Arc::new(LocalDeclX {
+            ident: (ctxt.decreases_at_entry[i].clone(), Some(0)),
+            typ: Arc::new(TypX::Int(IntRange::Int)),
+            mutable: false,
+        });
+        let stm_assign = Spanned::new(
+            span.clone(),
+            StmX::Assign {
+                lhs: (ctxt.decreases_at_entry[i].clone(), Some(0)),
+                rhs: height_of_exp(&ctxt.decreases_typs[i], exp)?,
+                is_init: true,
+            },
+        );
+        decls.push(decl);
+        stm_assigns.push(stm_assign);
+    }
+    Ok((decls, stm_assigns))
+}
+
+fn mk_ctxt<'a>(ctx: &'a Ctx, function: &Function) -> Result<Ctxt<'a>, VirErr> {
+    if function.x.decrease.len() == 0 {
+        return err_str(&function.span, "recursive function must call decreases(...)");
+    }
+    let mut decreases_at_entry: Vec<Ident> = Vec::new();
+    let mut decreases_exps: Vec<Exp> = Vec::new();
+    let mut decreases_typs: Vec<Typ> = Vec::new();
+    for (i, dec) in function.x.decrease.iter().enumerate() {
+        decreases_at_entry.push(decrease_at_entry(i));
+        decreases_exps.push(crate::ast_to_sst::expr_to_exp(ctx, &function.x.params, dec)?);
+        decreases_typs.push(dec.typ.clone());
+    }
+    Ok(Ctxt {
+        recursive_function_path: function.x.path.clone(),
+        params: function.x.params.clone(),
+        decreases_at_entry,
+        decreases_exps,
+        decreases_typs,
+        scc_rep: ctx.func_call_graph.get_scc_rep(&function.x.path),
+        ctx,
+    })
+}
+
+fn termination_description(ctxt: &Ctxt, level: usize) -> Option<String> {
+    if ctxt.decreases_exps.len() == 1 {
+        Some("could not prove termination".to_string())
+    } else {
+        Some(format!(
+            "could not prove termination (lexicographic decreases failed at component {})",
+            level
+        ))
+    }
 }
 
 pub(crate) fn check_termination_exp(
@@ -241,37 +352,21 @@ pub(crate) fn check_termination_exp(
         return Ok((false, Arc::new(vec![]), body.clone()));
     }
 
-    let decreases_expr = match &function.x.decrease {
-        None => {
-            return err_str(&function.span, "recursive function must call decreases(...)");
-        }
-        Some(dec) => dec.clone(),
-    };
-    let decreases_typ = decreases_expr.typ.clone();
-    let decreases_exp = crate::ast_to_sst::expr_to_exp(ctx, &function.x.params, &decreases_expr)?;
-    let decreases_at_entry = str_ident(DECREASE_AT_ENTRY);
-    let scc_rep = ctx.func_call_graph.get_scc_rep(&function.x.path);
-    let scc_rep_clone = scc_rep.clone();
-    let ctxt = Ctxt {
-        recursive_function_path: function.x.path.clone(),
-        params: function.x.params.clone(),
-        decreases_at_entry,
-        decreases_exp,
-        decreases_typ,
-        scc_rep,
-        ctx,
-    };
-    let check = terminates(&ctxt, &body)?;
-    let (decl, stm_assign) = mk_decreases_at_entry(&ctxt, &body.span);
-    let span =
-        Span { description: Some("could not prove termination".to_string()), ..body.span.clone() };
-    let stm_assert = Spanned::new(span, StmX::Assert(check));
-    let stm_block =
-        Spanned::new(body.span.clone(), StmX::Block(Arc::new(vec![stm_assign, stm_assert])));
+    let ctxt = mk_ctxt(ctx, function)?;
+    let scc_rep_clone = ctxt.scc_rep.clone();
+    let (decls, mut stms) = mk_decreases_at_entry(&ctxt, &body.span)?;
+    // Check each prefix of the lexicographic order in turn,
+    // so that the first failure identifies the component that does not decrease
+    for level in 0..ctxt.decreases_exps.len() {
+        let check = terminates(&ctxt, level, &body)?;
+        let span = Span { description: termination_description(&ctxt, level), ..body.span.clone() };
+        stms.push(Spanned::new(span, StmX::Assert(check)));
+    }
+    let stm_block = Spanned::new(body.span.clone(), StmX::Block(Arc::new(stms)));
 
     // TODO: If we decide to support debugging decreases failures, we should plumb _snap_map
     // up to the VIR model
-    local_decls.push(decl);
+    local_decls.extend(decls);
     let (commands, _snap_map) = crate::sst_to_air::body_stm_to_air(
         ctx,
         &function.x.typ_params,
@@ -281,6 +376,7 @@ pub(crate) fn check_termination_exp(
         &Arc::new(vec![]),
         &Arc::new(vec![]),
         &stm_block,
+        false,
     );
 
     // New body: substitute rec%f(args, fuel) for f(args)
@@ -308,46 +404,28 @@ pub(crate) fn check_termination_stm(
         return Ok((vec![], body.clone()));
     }
 
-    let decreases_expr = match &function.x.decrease {
-        None => {
-            return err_str(&function.span, "recursive function must call decreases(...)");
-        }
-        Some(dec) => dec.clone(),
-    };
-    let decreases_typ = decreases_expr.typ.clone();
-    let decreases_exp = crate::ast_to_sst::expr_to_exp(ctx, &function.x.params, &decreases_expr)?;
-    let decreases_at_entry = str_ident(DECREASE_AT_ENTRY);
-    let scc_rep = ctx.func_call_graph.get_scc_rep(&function.x.path);
-    let ctxt = Ctxt {
-        recursive_function_path: function.x.path.clone(),
-        params: function.x.params.clone(),
-        decreases_at_entry,
-        decreases_exp,
-        decreases_typ,
-        scc_rep,
-        ctx,
-    };
+    let ctxt = mk_ctxt(ctx, function)?;
     let stm = map_stm_visitor(body, &mut |s| match &s.x {
         StmX::Call(x, _, args, _)
             if *x == function.x.path || ctx.func_call_graph.get_scc_rep(x) == ctxt.scc_rep =>
         {
-            let new_ctxt = update_decreases_exp(&ctxt, x)?;
-            let check = check_decrease_rename(&new_ctxt, &s.span, &args);
-            let span = Span {
-                description: Some("could not prove termination".to_string()),
-                ..s.span.clone()
-            };
-            let stm_assert = Spanned::new(span, StmX::Assert(check));
-            let stm_block =
-                Spanned::new(s.span.clone(), StmX::Block(Arc::new(vec![stm_assert, s.clone()])));
-            Ok(stm_block)
+            let new_ctxt = update_decreases_exp(&ctxt, &s.span, x)?;
+            let mut stms: Vec<Stm> = Vec::new();
+            for level in 0..ctxt.decreases_exps.len() {
+                let check = check_decrease_rename(&new_ctxt, &s.span, &args, level)?;
+                let span =
+                    Span { description: termination_description(&ctxt, level), ..s.span.clone() };
+                stms.push(Spanned::new(span, StmX::Assert(check)));
+            }
+            stms.push(s.clone());
+            Ok(Spanned::new(s.span.clone(), StmX::Block(Arc::new(stms))))
         }
         _ => Ok(s.clone()),
     })?;
-    let (decl, stm_assign) = mk_decreases_at_entry(&ctxt, &stm.span);
-    let stm_block =
-        Spanned::new(stm.span.clone(), StmX::Block(Arc::new(vec![stm_assign, stm.clone()])));
-    Ok((vec![decl], stm_block))
+    let (decls, mut stms) = mk_decreases_at_entry(&ctxt, &stm.span)?;
+    stms.push(stm.clone());
+    let stm_block = Spanned::new(stm.span.clone(), StmX::Block(Arc::new(stms)));
+    Ok((decls, stm_block))
 }
 
 fn add_call_graph_edges(
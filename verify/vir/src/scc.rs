@@ -135,6 +135,21 @@ fn strongconnect(&mut self, v: usize) {
         }
     }
 
+    // All values reachable from the roots, including the roots that are nodes
+    pub fn get_reachable(&self, roots: &Vec<T>) -> Vec<T> {
+        let mut visited: Vec<bool> = vec![false; self.nodes.len()];
+        let mut stack: Vec<usize> = roots.iter().filter_map(|t| self.h.get(t).copied()).collect();
+        let mut reachable: Vec<T> = Vec::new();
+        while let Some(v) = stack.pop() {
+            if !visited[v] {
+                visited[v] = true;
+                reachable.push(self.nodes[v].t.clone());
+                stack.extend(self.nodes[v].edges.iter().copied());
+            }
+        }
+        reachable
+    }
+
     pub fn compute_sccs(&mut self) {
         assert!(!self.has_run);
         self.has_run = true;
@@ -6,9 +6,11 @@
 //! SST expressions cannot contain statments.
 //! SST is designed to make the translation to AIR as straightforward as possible.
 
-use crate::ast::{BinaryOp, Constant, Path, Typ, Typs, UnaryOp, UnaryOpr};
+use crate::ast::{
+    BinaryOp, Constant, Idents, MapOp, Path, SeqOp, SetOp, Typ, Typs, UnaryOp, UnaryOpr,
+};
 use crate::def::Spanned;
-use air::ast::{Binders, Ident, Quant};
+use air::ast::{Binder, Binders, Ident, Quant};
 use std::sync::Arc;
 
 pub type Trig = Exps;
@@ -19,6 +21,10 @@
 pub enum BndX {
     Let(Binders<Exp>),
     Quant(Quant, Binders<Typ>, Trigs),
+    // Choose a value for the binder that satisfies the body.
+    // The body's free variables (with their types) and type parameters are listed explicitly,
+    // since the choice is made by a function of them.
+    Choose(Binder<Typ>, Trigs, Arc<Vec<(Exp, Typ)>>, Idents),
 }
 
 // variable name with optional unique id for renaming (equal to unique_id in LocalDeclX)
@@ -30,14 +36,19 @@ pub enum BndX {
 pub enum ExpX {
     Const(Constant),
     Var(UniqueIdent),
+    // &mut argument to a call; appears only as a direct argument of StmX::Call
+    Loc(Exp),
     // used only during sst_to_air to generate AIR Old
-    Old(Ident, Ident),
+    Old(Ident, UniqueIdent),
     // call to spec function
     Call(Path, Typs, Exps),
     Ctor(Path, Ident, Binders<Exp>),
     Unary(UnaryOp, Exp),
     UnaryOpr(UnaryOpr, Exp),
     Binary(BinaryOp, Exp, Exp),
+    SeqOp(SeqOp, Exps),
+    SetOp(SetOp, Exps),
+    MapOp(MapOp, Exps),
     If(Exp, Exp, Exp),
     Bind(Bnd, Exp),
 }
@@ -54,6 +65,23 @@ pub struct Dest {
 pub enum StmX {
     Call(Path, Typs, Exps, Option<Dest>), // call to exec/proof function
     Assert(Exp),
+    // Assert using bit-vector reasoning (in a separate query), then assume
+    AssertBV(Exp),
+    // Check that the proof establishes ensure from require, for arbitrary values of vars,
+    // in a separate query that sees nothing else from the surrounding code
+    AssertBy {
+        vars: Arc<Vec<(UniqueIdent, Typ)>>,
+        require: Exp,
+        ensure: Exp,
+        proof: Stm,
+    },
+    // Check that the proof establishes ensures from requires, in a separate query
+    // with nonlinear arithmetic enabled; here, assert requires and assume ensures
+    AssertNonlinear {
+        requires: Exps,
+        ensures: Exps,
+        proof: Stm,
+    },
     Assume(Exp),
     Assign {
         lhs: UniqueIdent,
@@ -62,14 +90,27 @@ pub enum StmX {
     },
     Fuel(Path, u32),
     If(Exp, Stm, Option<Stm>),
-    While {
-        cond: Exp,
+    Loop {
+        label: Option<Ident>,
+        cond: Option<Exp>,
         body: Stm,
         invs: Exps,
+        enss: Exps,
+        // true if the body contains a break that targets this loop
+        has_break: bool,
+        // Checked at the end of each iteration: 0 <= decreases < decreases at start of iteration
+        decrease: Option<Exp>,
         typ_inv_vars: Arc<Vec<(UniqueIdent, Typ)>>,
         modified_vars: Arc<Vec<UniqueIdent>>,
     },
     Block(Stms),
+    // Check the postconditions and leave the function; the return value has already been assigned
+    Return,
+    // Leave (break) or restart (continue) the loop with the given label (or the innermost loop)
+    BreakOrContinue {
+        label: Option<Ident>,
+        is_break: bool,
+    },
 }
 
 pub type LocalDecl = Arc<LocalDeclX>;
@@ -1,5 +1,6 @@
 use crate::ast::{
-    BinaryOp, Ident, Idents, IntRange, Mode, Params, Path, Typ, TypX, UnaryOp, UnaryOpr,
+    BinaryOp, Ident, IntRange, MapOp, Mode, Params, Path, SeqOp, SetOp, Typ, TypParams, TypX,
+    UnaryOp, UnaryOpr,
 };
 use crate::context::Ctx;
 use crate::def::{
@@ -7,6 +8,7 @@
     suffix_global_id, suffix_local_expr_id, suffix_local_stmt_id, suffix_local_unique_id,
     suffix_typ_param_id, variant_field_ident, variant_ident, SnapPos, Spanned, FUEL_BOOL,
     FUEL_BOOL_DEFAULT, FUEL_DEFAULTS, FUEL_ID, FUEL_PARAM, FUEL_TYPE, POLY, SNAPSHOT_CALL, SUCC,
+    Z3_ARITH_NL,
 };
 use crate::sst::{BndX, Dest, Exp, ExpX, LocalDecl, Stm, StmX, UniqueIdent};
 use crate::util::vec_map;
@@ -16,8 +18,8 @@
 };
 use air::ast_util::{
     bool_typ, ident_apply, ident_binder, ident_typ, ident_var, int_typ, mk_and, mk_bind_expr,
-    mk_eq, mk_exists, mk_implies, mk_ite, mk_not, mk_or, str_apply, str_ident, str_typ, str_var,
-    string_var,
+    mk_eq, mk_exists, mk_false, mk_implies, mk_ite, mk_not, mk_or, str_apply, str_ident, str_typ,
+    str_var, string_var,
 };
 use std::collections::{HashMap, HashSet};
 use std::sync::Arc;
@@ -48,6 +50,9 @@ pub(crate) fn typ_to_air(_ctx: &Ctx, typ: &Typ) -> air::ast::Typ {
         TypX::Bool => bool_typ(),
         TypX::Tuple(_) => panic!("internal error: Tuple should have been removed by ast_simplify"),
         TypX::Datatype(path, _) => ident_typ(&path_to_air_ident(path)),
+        TypX::Seq(_) | TypX::Array(..) => str_typ(crate::def::SEQ),
+        TypX::Set(_) => str_typ(crate::def::SET),
+        TypX::Map(..) => str_typ(crate::def::MAP),
         TypX::Boxed(_) => str_typ(POLY),
         TypX::TypParam(_) => str_typ(POLY),
     }
@@ -72,6 +77,17 @@ pub fn typ_to_id(typ: &Typ) -> Expr {
         TypX::Bool => str_var(crate::def::TYPE_ID_BOOL),
         TypX::Tuple(_) => panic!("internal error: Tuple should have been removed by ast_simplify"),
         TypX::Datatype(path, _) => string_var(&prefix_type_id(&Arc::new(path_to_string(&path)))),
+        TypX::Seq(t) | TypX::Array(t, None) => {
+            str_apply(crate::def::TYPE_ID_SEQ, &vec![typ_to_id(t)])
+        }
+        TypX::Array(t, Some(n)) => {
+            let n = Arc::new(ExprX::Const(Constant::Nat(Arc::new(n.to_string()))));
+            str_apply(crate::def::TYPE_ID_ARRAY, &vec![typ_to_id(t), n])
+        }
+        TypX::Set(t) => str_apply(crate::def::TYPE_ID_SET, &vec![typ_to_id(t)]),
+        TypX::Map(tk, tv) => {
+            str_apply(crate::def::TYPE_ID_MAP, &vec![typ_to_id(tk), typ_to_id(tv)])
+        }
         TypX::Boxed(_) => panic!("internal error: type arguments should be unboxed"),
         TypX::TypParam(x) => ident_var(&suffix_typ_param_id(x)),
     }
@@ -105,6 +121,26 @@ pub(crate) fn typ_invariant(ctx: &Ctx, typ: &Typ, expr: &Expr) -> Option<Expr> {
             args.push(expr.clone());
             Some(str_apply(&f_name, &args))
         }
+        TypX::Seq(_) | TypX::Array(..) => {
+            let boxed = str_apply(crate::def::BOX_SEQ, &vec![expr.clone()]);
+            let has_type = str_apply(crate::def::HAS_TYPE, &vec![boxed, typ_to_id(typ)]);
+            if let TypX::Array(_, None) = &**typ {
+                // the length of a slice fits in a usize
+                let len = str_apply(crate::def::SEQ_LEN, &vec![expr.clone()]);
+                let len_inv = apply_range_fun(crate::def::U_INV, &IntRange::USize, vec![len]);
+                Some(mk_and(&vec![has_type, len_inv]))
+            } else {
+                Some(has_type)
+            }
+        }
+        TypX::Set(_) => {
+            let boxed = str_apply(crate::def::BOX_SET, &vec![expr.clone()]);
+            Some(str_apply(crate::def::HAS_TYPE, &vec![boxed, typ_to_id(typ)]))
+        }
+        TypX::Map(..) => {
+            let boxed = str_apply(crate::def::BOX_MAP, &vec![expr.clone()]);
+            Some(str_apply(crate::def::HAS_TYPE, &vec![boxed, typ_to_id(typ)]))
+        }
         TypX::TypParam(x) => Some(str_apply(
             crate::def::HAS_TYPE,
             &vec![expr.clone(), ident_var(&suffix_typ_param_id(&x))],
@@ -149,7 +185,9 @@ pub(crate) fn exp_to_expr(ctx: &Ctx, exp: &Exp) -> Expr {
             expr
         }
         ExpX::Var(x) => string_var(&suffix_local_unique_id(x)),
-        ExpX::Old(span, x) => Arc::new(ExprX::Old(span.clone(), suffix_local_stmt_id(x))),
+        // In an expression context, a location evaluates to its current value
+        ExpX::Loc(e) => exp_to_expr(ctx, e),
+        ExpX::Old(span, x) => Arc::new(ExprX::Old(span.clone(), suffix_local_unique_id(x))),
         ExpX::Call(x, typs, args) => {
             let name = suffix_global_id(&path_to_air_ident(&x));
             let mut exprs: Vec<Expr> = vec_map(typs, typ_to_id);
@@ -165,15 +203,18 @@ pub(crate) fn exp_to_expr(ctx: &Ctx, exp: &Exp) -> Expr {
         }
         ExpX::Unary(op, exp) => match op {
             UnaryOp::Not => mk_not(&exp_to_expr(ctx, exp)),
+            UnaryOp::BitNot => str_apply(crate::def::BIT_NOT, &vec![exp_to_expr(ctx, exp)]),
             UnaryOp::Trigger(_) => exp_to_expr(ctx, exp),
-            UnaryOp::Clip(IntRange::Int) => exp_to_expr(ctx, exp),
-            UnaryOp::Clip(range) => {
+            UnaryOp::Clip { range: IntRange::Int, .. } => exp_to_expr(ctx, exp),
+            UnaryOp::Clip { range, truncate } => {
                 let expr = exp_to_expr(ctx, exp);
-                let f_name = match range {
-                    IntRange::Int => panic!("internal error: Int"),
-                    IntRange::Nat => crate::def::NAT_CLIP,
-                    IntRange::U(_) | IntRange::USize => crate::def::U_CLIP,
-                    IntRange::I(_) | IntRange::ISize => crate::def::I_CLIP,
+                let f_name = match (range, truncate) {
+                    (IntRange::Int, _) => panic!("internal error: Int"),
+                    (IntRange::Nat, _) => crate::def::NAT_CLIP,
+                    (IntRange::U(_) | IntRange::USize, false) => crate::def::U_CLIP,
+                    (IntRange::I(_) | IntRange::ISize, false) => crate::def::I_CLIP,
+                    (IntRange::U(_) | IntRange::USize, true) => crate::def::U_TRUNC,
+                    (IntRange::I(_) | IntRange::ISize, true) => crate::def::I_TRUNC,
                 };
                 apply_range_fun(&f_name, &range, vec![expr])
             }
@@ -187,6 +228,9 @@ pub(crate) fn exp_to_expr(ctx: &Ctx, exp: &Exp) -> Expr {
                     TypX::Datatype(path, _) => {
                         crate::def::prefix_box(&Arc::new(path_to_string(&path)))
                     }
+                    TypX::Seq(_) | TypX::Array(..) => str_ident(crate::def::BOX_SEQ),
+                    TypX::Set(_) => str_ident(crate::def::BOX_SET),
+                    TypX::Map(..) => str_ident(crate::def::BOX_MAP),
                     TypX::Tuple(_) => panic!("internal error: Box(Tuple)"),
                     TypX::Boxed(_) => panic!("internal error: Box(Boxed)"),
                     TypX::TypParam(_) => panic!("internal error: Box(TypParam)"),
@@ -201,6 +245,9 @@ pub(crate) fn exp_to_expr(ctx: &Ctx, exp: &Exp) -> Expr {
                     TypX::Datatype(path, _) => {
                         crate::def::prefix_unbox(&Arc::new(path_to_string(&path)))
                     }
+                    TypX::Seq(_) | TypX::Array(..) => str_ident(crate::def::UNBOX_SEQ),
+                    TypX::Set(_) => str_ident(crate::def::UNBOX_SET),
+                    TypX::Map(..) => str_ident(crate::def::UNBOX_MAP),
                     TypX::Tuple(_) => panic!("internal error: Box(Tuple)"),
                     TypX::Boxed(_) => panic!("internal error: Unbox(Boxed)"),
                     TypX::TypParam(_) => panic!("internal error: Unbox(TypParam)"),
@@ -240,6 +287,18 @@ pub(crate) fn exp_to_expr(ctx: &Ctx, exp: &Exp) -> Expr {
                 BinaryOp::Add => ExprX::Multi(MultiOp::Add, Arc::new(vec![lh, rh])),
                 BinaryOp::Sub => ExprX::Multi(MultiOp::Sub, Arc::new(vec![lh, rh])),
                 BinaryOp::Mul => ExprX::Multi(MultiOp::Mul, Arc::new(vec![lh, rh])),
+                BinaryOp::TruncDiv => {
+                    return str_apply(crate::def::TRUNC_DIV, &vec![lh, rh]);
+                }
+                BinaryOp::TruncMod => {
+                    return str_apply(crate::def::TRUNC_MOD, &vec![lh, rh]);
+                }
+                // Bitwise operators are uninterpreted, except in bit-vector queries
+                BinaryOp::BitAnd => return str_apply(crate::def::BIT_AND, &vec![lh, rh]),
+                BinaryOp::BitOr => return str_apply(crate::def::BIT_OR, &vec![lh, rh]),
+                BinaryOp::BitXor => return str_apply(crate::def::BIT_XOR, &vec![lh, rh]),
+                BinaryOp::Shr => return str_apply(crate::def::BIT_SHR, &vec![lh, rh]),
+                BinaryOp::Shl => return str_apply(crate::def::BIT_SHL, &vec![lh, rh]),
                 BinaryOp::Ne => {
                     let eq = ExprX::Binary(air::ast::BinaryOp::Eq, lh, rh);
                     ExprX::Unary(air::ast::UnaryOp::Not, Arc::new(eq))
@@ -260,12 +319,57 @@ pub(crate) fn exp_to_expr(ctx: &Ctx, exp: &Exp) -> Expr {
                         BinaryOp::Mul => panic!("internal error"),
                         BinaryOp::EuclideanDiv => air::ast::BinaryOp::EuclideanDiv,
                         BinaryOp::EuclideanMod => air::ast::BinaryOp::EuclideanMod,
+                        BinaryOp::TruncDiv => panic!("internal error"),
+                        BinaryOp::TruncMod => panic!("internal error"),
+                        BinaryOp::BitAnd
+                        | BinaryOp::BitOr
+                        | BinaryOp::BitXor
+                        | BinaryOp::Shr
+                        | BinaryOp::Shl => panic!("internal error"),
                     };
                     ExprX::Binary(aop, lh, rh)
                 }
             };
             Arc::new(expx)
         }
+        ExpX::SeqOp(op, es) => {
+            let args = vec_map(es, |e| exp_to_expr(ctx, e));
+            let f_name = match op {
+                // Arrays and slices are already represented as sequences
+                SeqOp::View => return args[0].clone(),
+                SeqOp::Empty => return str_var(crate::def::SEQ_EMPTY),
+                SeqOp::Push => crate::def::SEQ_PUSH,
+                SeqOp::Len => crate::def::SEQ_LEN,
+                SeqOp::Index => crate::def::SEQ_INDEX,
+                SeqOp::Update => crate::def::SEQ_UPDATE,
+                SeqOp::Subrange => crate::def::SEQ_SUBRANGE,
+                SeqOp::ExtEqual => crate::def::SEQ_EXT_EQUAL,
+            };
+            str_apply(f_name, &args)
+        }
+        ExpX::SetOp(op, es) => {
+            let args = vec_map(es, |e| exp_to_expr(ctx, e));
+            let f_name = match op {
+                SetOp::Empty => return str_var(crate::def::SET_EMPTY),
+                SetOp::Contains => crate::def::SET_CONTAINS,
+                SetOp::Insert => crate::def::SET_INSERT,
+                SetOp::Remove => crate::def::SET_REMOVE,
+                SetOp::ExtEqual => crate::def::SET_EXT_EQUAL,
+            };
+            str_apply(f_name, &args)
+        }
+        ExpX::MapOp(op, es) => {
+            let args = vec_map(es, |e| exp_to_expr(ctx, e));
+            let f_name = match op {
+                MapOp::Empty => return str_var(crate::def::MAP_EMPTY),
+                MapOp::Dom => crate::def::MAP_DOM,
+                MapOp::Index => crate::def::MAP_INDEX,
+                MapOp::Insert => crate::def::MAP_INSERT,
+                MapOp::Remove => crate::def::MAP_REMOVE,
+                MapOp::ExtEqual => crate::def::MAP_EXT_EQUAL,
+            };
+            str_apply(f_name, &args)
+        }
         ExpX::If(e1, e2, e3) => {
             mk_ite(&exp_to_expr(ctx, e1), &exp_to_expr(ctx, e2), &exp_to_expr(ctx, e3))
         }
@@ -308,16 +412,116 @@ pub(crate) fn exp_to_expr(ctx: &Ctx, exp: &Exp) -> Expr {
                     vec_map(&*trigs, |trig| Arc::new(vec_map(trig, |x| exp_to_expr(ctx, x))));
                 air::ast_util::mk_quantifier(*quant, &binders, &triggers, &expr)
             }
+            BndX::Choose(binder, trigs, captured, typ_params) => {
+                choose_to_expr(ctx, binder, trigs, captured, typ_params, exp)
+            }
         },
     }
 }
 
+// Translate choose to an application of a skolem function of the body's free variables:
+//   (declare-fun choose%n (typ_params captured) binder_typ)
+//   (axiom (forall (typ_params captured) (has_type (choose%n ...) binder_typ)))
+//   (axiom (forall (typ_params captured)
+//     (=> (exists (x) (and inv body)) (let ((x (choose%n ...))) body))))
+// Identical choose expressions share a single skolem function.
+fn choose_to_expr(
+    ctx: &Ctx,
+    binder: &air::ast::Binder<Typ>,
+    trigs: &crate::sst::Trigs,
+    captured: &Vec<(Exp, Typ)>,
+    typ_params: &crate::ast::Idents,
+    body: &Exp,
+) -> Expr {
+    let mut params: Vec<air::ast::Binder<air::ast::Typ>> = Vec::new();
+    for x in typ_params.iter() {
+        params.push(ident_binder(&suffix_typ_param_id(x), &str_typ(crate::def::TYPE)));
+    }
+    for (e, typ) in captured.iter() {
+        let name = match &e.x {
+            ExpX::Var(x) => suffix_local_unique_id(x),
+            _ => panic!("internal error: choose captured a non-variable"),
+        };
+        params.push(ident_binder(&name, &typ_to_air(ctx, typ)));
+    }
+    let args: Vec<Expr> = params.iter().map(|p| ident_var(&p.name)).collect();
+    let x = suffix_local_expr_id(&binder.name);
+    let x_typ = typ_to_air(ctx, &binder.a);
+    let inv = typ_invariant(ctx, &binder.a, &ident_var(&x));
+    let expr = exp_to_expr(ctx, body);
+    let triggers = vec_map(&*trigs, |trig| Arc::new(vec_map(trig, |t| exp_to_expr(ctx, t))));
+
+    let key = format!("{:?}", (&params, &x, &x_typ, &inv, &expr, &triggers));
+    let mut choose_fns = ctx.choose_fns.borrow_mut();
+    if let Some(name) = choose_fns.get(&key) {
+        return ident_apply(name, &args);
+    }
+    let name = crate::def::prefix_choose(choose_fns.len());
+    choose_fns.insert(key, name.clone());
+    let app = ident_apply(&name, &args);
+
+    let mut decls: Vec<Decl> = Vec::new();
+    let param_typs = Arc::new(vec_map(&params, |p| p.a.clone()));
+    decls.push(Arc::new(DeclX::Fun(name.clone(), param_typs, x_typ.clone())));
+    let app_trigs = vec![Arc::new(vec![app.clone()])];
+    if let Some(app_inv) = typ_invariant(ctx, &binder.a, &app) {
+        let forall = air::ast_util::mk_quantifier(Quant::Forall, &params, &app_trigs, &app_inv);
+        decls.push(Arc::new(DeclX::Axiom(forall)));
+    }
+    let x_binders = vec![ident_binder(&x, &x_typ)];
+    let inv_body = match &inv {
+        None => expr.clone(),
+        Some(inv) => mk_and(&vec![inv.clone(), expr.clone()]),
+    };
+    let exists = air::ast_util::mk_quantifier(Quant::Exists, &x_binders, &triggers, &inv_body);
+    let chosen = air::ast_util::mk_let(&vec![ident_binder(&x, &app)], &expr);
+    let forall = air::ast_util::mk_quantifier(
+        Quant::Forall,
+        &params,
+        &app_trigs,
+        &mk_implies(&exists, &chosen),
+    );
+    decls.push(Arc::new(DeclX::Axiom(forall)));
+    let mut choose_decls = ctx.choose_decls.borrow_mut();
+    for decl in decls {
+        choose_decls.push(Arc::new(CommandX::Global(decl)));
+    }
+    app
+}
+
 struct State {
     local_shared: Vec<Decl>, // shared between all queries for a single function
     commands: Vec<Command>,
     snapshot_count: u32,    // Used to ensure unique Idents for each snapshot
     latest_snapshot: Ident, // The ID of the closest snapshot that dominates the current position in the AST
     snap_map: Vec<(Span, SnapPos)>, // Maps each statement's span to the closest dominating snapshot's ID
+    post_condition_exps: Vec<Exp>,  // Checked at the end of the body and at each return
+    loop_infos: Vec<LoopInfo>,      // Enclosing loops, innermost last
+    var_typs: HashMap<UniqueIdent, Typ>, // Used by bit-vector queries
+    nonlinear: bool,                // All queries already run with nonlinear arithmetic enabled
+}
+
+struct LoopInfo {
+    label: Option<Ident>,
+    invs: Vec<(Span, Expr)>,
+    enss: Vec<(Span, Expr)>,
+    decrease: Option<(Span, Expr)>,
+}
+
+fn assert_with_description(description: &str, span: &Span, expr: &Expr) -> Stmt {
+    let description = Some(description.to_string());
+    let option_span = Arc::new(Some(Span { description, ..span.clone() }));
+    Arc::new(StmtX::Assert(option_span, expr.clone()))
+}
+
+// Run the commands with the SMT solver's nonlinear arithmetic enabled,
+// then restore the default (disabled by air_recommended_options)
+pub(crate) fn nonlinear_commands(mut commands: Vec<Command>) -> Vec<Command> {
+    let set_nl =
+        |value: &str| Arc::new(CommandX::SetOption(str_ident(Z3_ARITH_NL), str_ident(value)));
+    commands.insert(0, set_nl("true"));
+    commands.push(set_nl("false"));
+    commands
 }
 
 fn assume_var(span: &Span, x: &UniqueIdent, exp: &Exp) -> Stm {
@@ -345,12 +549,47 @@ fn stm_to_stmts(ctx: &Ctx, state: &mut State, stm: &Stm) -> Vec<Stmt> {
                 let option_span = Arc::new(Some(Span { description, ..stm.span.clone() }));
                 stmts.push(Arc::new(StmtX::Assert(option_span, e_req)));
             }
+            // Variables overwritten by the call: &mut arguments and the destination
+            let mut mut_vars: Vec<UniqueIdent> = Vec::new();
+            for arg in args.iter() {
+                if let ExpX::Loc(e) = &arg.x {
+                    match &e.x {
+                        ExpX::Var(x) => mut_vars.push(x.clone()),
+                        _ => panic!("internal error: Loc must contain a Var"),
+                    }
+                }
+            }
+            let mut overwritten = mut_vars.clone();
+            if let Some(Dest { var, .. }) = dest {
+                overwritten.push(var.clone());
+            }
+            // ens% receives the values of the arguments before the call ...
             let mut ens_args: Vec<Expr> = vec_map(typs, typ_to_id);
+            let mut overwrite = false;
+            for arg in args.iter() {
+                let arg_x = crate::sst_visitor::map_exp_visitor(arg, &mut |e| match &e.x {
+                    ExpX::Var(x) if overwritten.contains(x) => {
+                        overwrite = true;
+                        Spanned::new(
+                            arg.span.clone(),
+                            ExpX::Old(str_ident(SNAPSHOT_CALL), x.clone()),
+                        )
+                    }
+                    _ => e.clone(),
+                });
+                ens_args.push(exp_to_expr(ctx, &arg_x));
+            }
+            if overwrite {
+                stmts.push(Arc::new(StmtX::Snapshot(str_ident(SNAPSHOT_CALL))));
+            }
+            // ... followed by the values of the &mut arguments after the call
+            for x in mut_vars.iter() {
+                let x = suffix_local_unique_id(x);
+                stmts.push(Arc::new(StmtX::Havoc(x.clone())));
+                ens_args.push(Arc::new(ExprX::Var(x)));
+            }
             match dest {
                 None => {
-                    for arg in args.iter() {
-                        ens_args.push(exp_to_expr(ctx, arg));
-                    }
                     if ctx.debug {
                         state
                             .snap_map
@@ -359,23 +598,6 @@ fn stm_to_stmts(ctx: &Ctx, state: &mut State, stm: &Stm) -> Vec<Stmt> {
                 }
                 Some(Dest { var, is_init }) => {
                     let x = suffix_local_unique_id(&var);
-                    let mut overwrite = false;
-                    for arg in args.iter() {
-                        let arg_x = crate::sst_visitor::map_exp_visitor(arg, &mut |e| match &e.x {
-                            ExpX::Var(x) if x == var => {
-                                overwrite = true;
-                                Spanned::new(
-                                    arg.span.clone(),
-                                    ExpX::Old(str_ident(SNAPSHOT_CALL), x.0.clone()),
-                                )
-                            }
-                            _ => e.clone(),
-                        });
-                        ens_args.push(exp_to_expr(ctx, &arg_x));
-                    }
-                    if overwrite {
-                        stmts.push(Arc::new(StmtX::Snapshot(str_ident(SNAPSHOT_CALL))));
-                    }
                     ens_args.push(Arc::new(ExprX::Var(x.clone())));
                     if !*is_init {
                         let havoc = StmtX::Havoc(x.clone());
@@ -413,6 +635,78 @@ fn stm_to_stmts(ctx: &Ctx, state: &mut State, stm: &Stm) -> Vec<Stmt> {
             }
             vec![Arc::new(StmtX::Assert(option_span, air_expr))]
         }
+        StmX::AssertBV(expr) => {
+            // Prove expr in a separate bit-vector query, then assume it here
+            let asserts = vec![(stm.span.clone(), expr.clone())];
+            let query = crate::bitvector_to_air::bit_vector_query(&state.var_typs, &[], &asserts);
+            state.commands.push(query);
+            vec![Arc::new(StmtX::Assume(exp_to_expr(ctx, &expr)))]
+        }
+        StmX::AssertBy { vars: _, require, ensure, proof } => {
+            // Check the proof in a separate query that sees only the require
+            // (and the types of the local variables, including vars),
+            // so that a large proof doesn't slow down the rest of the enclosing function
+            // and nothing it assumes (including reveals) leaks out to the following statements.
+            // The caller (ast_to_sst) assumes forall vars. require ==> ensure afterwards.
+            let mut local = state.local_shared.clone();
+            for (x, typ) in state.var_typs.iter() {
+                let typ_inv = typ_invariant(ctx, typ, &ident_var(&suffix_local_unique_id(x)));
+                if let Some(expr) = typ_inv {
+                    local.push(Arc::new(DeclX::Axiom(expr)));
+                }
+            }
+            local.push(Arc::new(DeclX::Axiom(exp_to_expr(ctx, require))));
+            let mut air_body = stm_to_stmts(ctx, state, proof);
+            let option_span = Arc::new(Some(ensure.span.clone()));
+            air_body.push(Arc::new(StmtX::Assert(option_span, exp_to_expr(ctx, ensure))));
+            let assertion = Arc::new(StmtX::Block(Arc::new(air_body)));
+            let query = Arc::new(QueryX { local: Arc::new(local), assertion });
+            state.commands.push(Arc::new(CommandX::CheckValid(query)));
+            vec![]
+        }
+        StmX::AssertNonlinear { requires, ensures, proof } => {
+            // Check the proof in a separate query that sees only the requires
+            // (and the types of the local variables), with nonlinear arithmetic enabled
+            let mut local = state.local_shared.clone();
+            for (x, typ) in state.var_typs.iter() {
+                let typ_inv = typ_invariant(ctx, typ, &ident_var(&suffix_local_unique_id(x)));
+                if let Some(expr) = typ_inv {
+                    local.push(Arc::new(DeclX::Axiom(expr)));
+                }
+            }
+            for req in requires.iter() {
+                local.push(Arc::new(DeclX::Axiom(exp_to_expr(ctx, req))));
+            }
+            let mut air_body = stm_to_stmts(ctx, state, proof);
+            for ens in ensures.iter() {
+                let description = "nonlinear assertion not satisfied";
+                air_body.push(assert_with_description(
+                    description,
+                    &ens.span,
+                    &exp_to_expr(ctx, ens),
+                ));
+            }
+            let assertion = Arc::new(StmtX::Block(Arc::new(air_body)));
+            let query = Arc::new(QueryX { local: Arc::new(local), assertion });
+            let check = Arc::new(CommandX::CheckValid(query));
+            if state.nonlinear {
+                // Don't disable nonlinear arithmetic for the rest of the enclosing function
+                state.commands.push(check);
+            } else {
+                state.commands.append(&mut nonlinear_commands(vec![check]));
+            }
+
+            // At the original site, check the requires and assume the ensures
+            let mut stmts: Vec<Stmt> = Vec::new();
+            for req in requires.iter() {
+                let description = "requirement of nonlinear assertion not satisfied";
+                stmts.push(assert_with_description(description, &req.span, &exp_to_expr(ctx, req)));
+            }
+            for ens in ensures.iter() {
+                stmts.push(Arc::new(StmtX::Assume(exp_to_expr(ctx, ens))));
+            }
+            stmts
+        }
         StmX::Assume(expr) => {
             if ctx.debug {
                 state
@@ -473,13 +767,27 @@ fn stm_to_stmts(ctx: &Ctx, state: &mut State, stm: &Stm) -> Vec<Stmt> {
             }
             stmts
         }
-        StmX::While { cond, body, invs, typ_inv_vars, modified_vars } => {
-            let pos_cond = exp_to_expr(ctx, &cond);
-            let neg_cond = Arc::new(ExprX::Unary(air::ast::UnaryOp::Not, pos_cond.clone()));
-            let pos_assume = Arc::new(DeclX::Axiom(pos_cond));
-            let neg_assume = Arc::new(StmtX::Assume(neg_cond));
+        StmX::Loop {
+            label,
+            cond,
+            body,
+            invs,
+            enss,
+            has_break,
+            decrease,
+            typ_inv_vars,
+            modified_vars,
+        } => {
+            let conds = cond.as_ref().map(|cond| {
+                let pos_cond = exp_to_expr(ctx, cond);
+                let neg_cond = Arc::new(ExprX::Unary(air::ast::UnaryOp::Not, pos_cond.clone()));
+                (pos_cond, neg_cond)
+            });
             let invs: Vec<(Span, Expr)> =
                 invs.iter().map(|e| (e.span.clone(), exp_to_expr(ctx, e))).collect();
+            let enss: Vec<(Span, Expr)> =
+                enss.iter().map(|e| (e.span.clone(), exp_to_expr(ctx, e))).collect();
+            let decrease = decrease.as_ref().map(|e| (e.span.clone(), exp_to_expr(ctx, e)));
 
             let entry_snap_id = if ctx.debug {
                 // Add a snapshot to capture the start of the while loop
@@ -493,7 +801,15 @@ fn stm_to_stmts(ctx: &Ctx, state: &mut State, stm: &Stm) -> Vec<Stmt> {
                 None
             };
 
+            let loop_info = LoopInfo {
+                label: label.clone(),
+                invs: invs.clone(),
+                enss: enss.clone(),
+                decrease: decrease.clone(),
+            };
+            state.loop_infos.push(loop_info);
             let mut air_body = stm_to_stmts(ctx, state, body);
+            state.loop_infos.pop();
 
             /*
             Generate a separate SMT query for the loop body.
@@ -522,18 +838,38 @@ fn stm_to_stmts(ctx: &Ctx, state: &mut State, stm: &Stm) -> Vec<Stmt> {
             for (_, inv) in invs.iter() {
                 local.push(Arc::new(DeclX::Axiom(inv.clone())));
             }
-            local.push(pos_assume);
             for (span, inv) in invs.iter() {
-                let description = Some("invariant not satisfied at end of loop body".to_string());
-                let option_span = Arc::new(Some(Span { description, ..span.clone() }));
-                let inv_stmt = StmtX::Assert(option_span, inv.clone());
-                air_body.push(Arc::new(inv_stmt));
+                let description = "invariant not satisfied at end of loop body";
+                air_body.push(assert_with_description(description, span, inv));
+            }
+            if let Some((span, dec)) = &decrease {
+                let description = "could not prove termination";
+                air_body.push(assert_with_description(description, span, dec));
             }
             let assertion = if air_body.len() == 1 {
                 air_body[0].clone()
             } else {
                 Arc::new(StmtX::Block(Arc::new(air_body)))
             };
+            let assertion = match &conds {
+                None => assertion,
+                Some((pos_cond, _)) if enss.len() == 0 => {
+                    local.push(Arc::new(DeclX::Axiom(pos_cond.clone())));
+                    assertion
+                }
+                Some((pos_cond, neg_cond)) => {
+                    // Either run the body, or exit the loop and check the ensures
+                    let pos_assume = Arc::new(StmtX::Assume(pos_cond.clone()));
+                    let mut exit = vec![Arc::new(StmtX::Assume(neg_cond.clone()))];
+                    for (span, ens) in enss.iter() {
+                        let description = "loop ensures not satisfied at loop exit";
+                        exit.push(assert_with_description(description, span, ens));
+                    }
+                    let enter = Arc::new(StmtX::Block(Arc::new(vec![pos_assume, assertion])));
+                    let exit = Arc::new(StmtX::Block(Arc::new(exit)));
+                    Arc::new(StmtX::Switch(Arc::new(vec![enter, exit])))
+                }
+            };
 
             let assertion = if !ctx.debug {
                 assertion
@@ -550,13 +886,13 @@ fn stm_to_stmts(ctx: &Ctx, state: &mut State, stm: &Stm) -> Vec<Stmt> {
             let query = Arc::new(QueryX { local: Arc::new(local), assertion });
             state.commands.push(Arc::new(CommandX::CheckValid(query)));
 
-            // At original site of while loop, assert invariant, havoc, assume invariant + neg_cond
+            // At original site of loop, assert invariant, havoc, and assume the exit condition:
+            // - the ensures, if there are any
+            // - otherwise, the invariant, plus neg_cond if the loop can only exit via cond
             let mut stmts: Vec<Stmt> = Vec::new();
             for (span, inv) in invs.iter() {
-                let description = Some("invariant not satisfied before loop".to_string());
-                let option_span = Arc::new(Some(Span { description, ..span.clone() }));
-                let inv_stmt = StmtX::Assert(option_span, inv.clone());
-                stmts.push(Arc::new(inv_stmt));
+                let description = "invariant not satisfied before loop";
+                stmts.push(assert_with_description(description, span, inv));
             }
             for x in modified_vars.iter() {
                 stmts.push(Arc::new(StmtX::Havoc(suffix_local_unique_id(&x))));
@@ -569,11 +905,26 @@ fn stm_to_stmts(ctx: &Ctx, state: &mut State, stm: &Stm) -> Vec<Stmt> {
                     }
                 }
             }
-            for (_, inv) in invs.iter() {
-                let inv_stmt = StmtX::Assume(inv.clone());
-                stmts.push(Arc::new(inv_stmt));
+            if enss.len() == 0 {
+                for (_, inv) in invs.iter() {
+                    let inv_stmt = StmtX::Assume(inv.clone());
+                    stmts.push(Arc::new(inv_stmt));
+                }
+                match (&conds, *has_break) {
+                    (_, true) => {}
+                    (Some((_, neg_cond)), false) => {
+                        stmts.push(Arc::new(StmtX::Assume(neg_cond.clone())));
+                    }
+                    (None, false) => {
+                        // The loop never exits
+                        stmts.push(Arc::new(StmtX::Assume(mk_false())));
+                    }
+                }
+            } else {
+                for (_, ens) in enss.iter() {
+                    stmts.push(Arc::new(StmtX::Assume(ens.clone())));
+                }
             }
-            stmts.push(neg_assume);
             if ctx.debug {
                 // Add a snapshot for the state after we emerge from the while loop
                 state.snapshot_count += 1;
@@ -618,9 +969,50 @@ fn stm_to_stmts(ctx: &Ctx, state: &mut State, stm: &Stm) -> Vec<Stmt> {
             stmts
         }
         StmX::Block(stms) => stms.iter().map(|s| stm_to_stmts(ctx, state, s)).flatten().collect(),
+        StmX::Return => {
+            // Report failures at the return statement, then cut off the rest of this path
+            let mut stmts = post_condition_stmts(ctx, &state.post_condition_exps, Some(&stm.span));
+            stmts.push(Arc::new(StmtX::Assume(mk_false())));
+            stmts
+        }
+        StmX::BreakOrContinue { label, is_break } => {
+            let loop_info = state
+                .loop_infos
+                .iter()
+                .rev()
+                .find(|info| label.is_none() || info.label == *label)
+                .expect("internal error: break/continue target");
+            let mut stmts: Vec<Stmt> = Vec::new();
+            // A break must establish the loop's exit condition, a continue its invariant
+            let (description, exps) = match (*is_break, loop_info.enss.len()) {
+                (true, 0) => ("invariant not satisfied at break", &loop_info.invs),
+                (true, _) => ("loop ensures not satisfied at break", &loop_info.enss),
+                (false, _) => ("invariant not satisfied at continue", &loop_info.invs),
+            };
+            for (_, exp) in exps.iter() {
+                stmts.push(assert_with_description(description, &stm.span, exp));
+            }
+            if let (false, Some((_, dec))) = (*is_break, &loop_info.decrease) {
+                let description = "could not prove termination";
+                stmts.push(assert_with_description(description, &stm.span, dec));
+            }
+            stmts.push(Arc::new(StmtX::Assume(mk_false())));
+            stmts
+        }
     }
 }
 
+fn post_condition_stmts(ctx: &Ctx, enss: &Vec<Exp>, return_span: Option<&Span>) -> Vec<Stmt> {
+    let mut stmts: Vec<Stmt> = Vec::new();
+    for ens in enss {
+        let description = Some("postcondition not satisfied".to_string());
+        let span = return_span.unwrap_or(&ens.span);
+        let option_span = Arc::new(Some(Span { description, ..span.clone() }));
+        stmts.push(Arc::new(StmtX::Assert(option_span, exp_to_expr(ctx, ens))));
+    }
+    stmts
+}
+
 fn set_fuel(local: &mut Vec<Decl>, hidden: &Vec<Path>) {
     let fuel_expr = if hidden.len() == 0 {
         str_var(&FUEL_DEFAULTS)
@@ -654,19 +1046,20 @@ fn set_fuel(local: &mut Vec<Decl>, hidden: &Vec<Path>) {
 
 pub fn body_stm_to_air(
     ctx: &Ctx,
-    typ_params: &Idents,
+    typ_params: &TypParams,
     params: &Params,
     local_decls: &Vec<LocalDecl>,
     hidden: &Vec<Path>,
     reqs: &Vec<Exp>,
     enss: &Vec<Exp>,
     stm: &Stm,
+    nonlinear: bool,
 ) -> (Commands, Vec<(Span, SnapPos)>) {
     // Verifying a single function can generate multiple SMT queries.
     // Some declarations (local_shared) are shared among the queries.
     // Others are private to each query.
     let mut local_shared: Vec<Decl> = Vec::new();
-    for x in typ_params.iter() {
+    for (x, _) in typ_params.iter() {
         local_shared
             .push(Arc::new(DeclX::Const(suffix_typ_param_id(&x), str_typ(crate::def::TYPE))));
     }
@@ -698,6 +1091,10 @@ pub fn body_stm_to_air(
         snapshot_count: 0,
         latest_snapshot: initial_snapshot_name.clone(),
         snap_map: Vec::new(),
+        post_condition_exps: enss.clone(),
+        loop_infos: Vec::new(),
+        var_typs: declared.clone(),
+        nonlinear,
     };
 
     let stm = crate::sst_vars::stm_assign(&declared, &mut assigned, &mut HashSet::new(), stm);
@@ -712,12 +1109,7 @@ pub fn body_stm_to_air(
 
     let mut local = state.local_shared.clone();
 
-    for ens in enss {
-        let description = Some("postcondition not satisfied".to_string());
-        let option_span = Arc::new(Some(Span { description, ..ens.span.clone() }));
-        let ens_stmt = StmtX::Assert(option_span, exp_to_expr(ctx, ens));
-        stmts.push(Arc::new(ens_stmt));
-    }
+    stmts.append(&mut post_condition_stmts(ctx, enss, None));
     let assertion =
         if stmts.len() == 1 { stmts[0].clone() } else { Arc::new(StmtX::Block(Arc::new(stmts))) };
 
@@ -1,6 +1,6 @@
 use crate::ast::Typ;
 use crate::def::Spanned;
-use crate::sst::{Stm, StmX, UniqueIdent};
+use crate::sst::{ExpX, Stm, StmX, UniqueIdent};
 use std::collections::{HashMap, HashSet};
 use std::sync::Arc;
 
@@ -14,14 +14,55 @@ pub(crate) fn stm_assign(
     stm: &Stm,
 ) -> Stm {
     match &stm.x {
-        StmX::Call(_, _, _, Some(dest)) => {
-            assigned.insert(dest.var.clone());
-            if !dest.is_init {
-                modified.insert(dest.var.clone());
+        StmX::Call(_, _, args, dest) => {
+            for arg in args.iter() {
+                if let ExpX::Loc(e) = &arg.x {
+                    match &e.x {
+                        ExpX::Var(x) => {
+                            modified.insert(x.clone());
+                        }
+                        _ => panic!("internal error: Loc must contain a Var"),
+                    }
+                }
+            }
+            if let Some(dest) = dest {
+                assigned.insert(dest.var.clone());
+                if !dest.is_init {
+                    modified.insert(dest.var.clone());
+                }
             }
             stm.clone()
         }
-        StmX::Call(_, _, _, _) | StmX::Assert(_) | StmX::Assume(_) | StmX::Fuel(_, _) => {
+        StmX::Assert(_) | StmX::AssertBV(_) | StmX::Assume(_) | StmX::Fuel(_, _) => stm.clone(),
+        StmX::AssertBy { vars, require, ensure, proof } => {
+            // The proof runs in its own query, so its assignments don't count afterwards
+            let pre_assigned = assigned.clone();
+            assigned.extend(vars.iter().map(|(x, _)| x.clone()));
+            let proof = stm_assign(declared, assigned, modified, proof);
+            *assigned = pre_assigned;
+            let assert_by = StmX::AssertBy {
+                vars: vars.clone(),
+                require: require.clone(),
+                ensure: ensure.clone(),
+                proof,
+            };
+            Spanned::new(stm.span.clone(), assert_by)
+        }
+        StmX::AssertNonlinear { requires, ensures, proof } => {
+            // The proof runs in its own query, so its assignments don't count afterwards
+            let pre_assigned = assigned.clone();
+            let proof = stm_assign(declared, assigned, modified, proof);
+            *assigned = pre_assigned;
+            let assert_nonlinear = StmX::AssertNonlinear {
+                requires: requires.clone(),
+                ensures: ensures.clone(),
+                proof,
+            };
+            Spanned::new(stm.span.clone(), assert_nonlinear)
+        }
+        StmX::Return | StmX::BreakOrContinue { .. } => {
+            // Nothing after a jump is reachable, so treat every variable as assigned
+            assigned.extend(declared.keys().cloned());
             stm.clone()
         }
         StmX::Assign { lhs, rhs: _, is_init } => {
@@ -51,7 +92,17 @@ pub(crate) fn stm_assign(
             *assigned = pre_assigned;
             Spanned::new(stm.span.clone(), StmX::If(cond.clone(), lhs, rhs))
         }
-        StmX::While { cond, body, invs, typ_inv_vars, modified_vars } => {
+        StmX::Loop {
+            label,
+            cond,
+            body,
+            invs,
+            enss,
+            has_break,
+            decrease,
+            typ_inv_vars,
+            modified_vars,
+        } => {
             let pre_assigned = assigned.clone();
             let mut pre_modified = modified.clone();
             *modified = HashSet::new();
@@ -73,14 +124,18 @@ pub(crate) fn stm_assign(
             for x in assigned.iter() {
                 typ_inv_vars.push((x.clone(), declared[x].clone()));
             }
-            let while_x = StmX::While {
+            let loop_x = StmX::Loop {
+                label: label.clone(),
                 cond: cond.clone(),
                 body,
                 invs: invs.clone(),
+                enss: enss.clone(),
+                has_break: *has_break,
+                decrease: decrease.clone(),
                 typ_inv_vars: Arc::new(typ_inv_vars),
                 modified_vars: Arc::new(modified_vars),
             };
-            Spanned::new(stm.span.clone(), while_x)
+            Spanned::new(stm.span.clone(), loop_x)
         }
         StmX::Block(stms) => {
             let mut pre_assigned = assigned.clone();
@@ -1,4 +1,4 @@
-use crate::ast::{Ident, VirErr};
+use crate::ast::{Ident, Typ, VirErr};
 use crate::def::Spanned;
 use crate::sst::{BndX, Exp, ExpX, Stm, StmX, Trig, UniqueIdent};
 use crate::util::vec_map;
@@ -18,6 +18,11 @@ pub(crate) fn map_exp_visitor_bind<F>(
     match &exp.x {
         ExpX::Const(_) => f(exp, map),
         ExpX::Var(..) => f(exp, map),
+        ExpX::Loc(e1) => {
+            let expr1 = map_exp_visitor_bind(e1, map, f)?;
+            let exp = Spanned::new(exp.span.clone(), ExpX::Loc(expr1));
+            f(&exp, map)
+        }
         ExpX::Old(..) => f(exp, map),
         ExpX::Call(x, typs, es) => {
             let mut exps: Vec<Exp> = Vec::new();
@@ -55,6 +60,30 @@ pub(crate) fn map_exp_visitor_bind<F>(
             let exp = Spanned::new(exp.span.clone(), ExpX::Binary(*op, expr1, expr2));
             f(&exp, map)
         }
+        ExpX::SeqOp(op, es) => {
+            let mut exps: Vec<Exp> = Vec::new();
+            for e in es.iter() {
+                exps.push(map_exp_visitor_bind(e, map, f)?);
+            }
+            let exp = Spanned::new(exp.span.clone(), ExpX::SeqOp(*op, Arc::new(exps)));
+            f(&exp, map)
+        }
+        ExpX::SetOp(op, es) => {
+            let mut exps: Vec<Exp> = Vec::new();
+            for e in es.iter() {
+                exps.push(map_exp_visitor_bind(e, map, f)?);
+            }
+            let exp = Spanned::new(exp.span.clone(), ExpX::SetOp(*op, Arc::new(exps)));
+            f(&exp, map)
+        }
+        ExpX::MapOp(op, es) => {
+            let mut exps: Vec<Exp> = Vec::new();
+            for e in es.iter() {
+                exps.push(map_exp_visitor_bind(e, map, f)?);
+            }
+            let exp = Spanned::new(exp.span.clone(), ExpX::MapOp(*op, Arc::new(exps)));
+            f(&exp, map)
+        }
         ExpX::If(e1, e2, e3) => {
             let expr1 = map_exp_visitor_bind(e1, map, f)?;
             let expr2 = map_exp_visitor_bind(e2, map, f)?;
@@ -88,6 +117,23 @@ pub(crate) fn map_exp_visitor_bind<F>(
                     }
                     BndX::Quant(*quant, binders.clone(), Arc::new(triggers))
                 }
+                BndX::Choose(binder, ts, captured, typ_params) => {
+                    let mut triggers: Vec<Trig> = Vec::new();
+                    let mut vars: Vec<(Exp, Typ)> = Vec::new();
+                    for (e, typ) in captured.iter() {
+                        vars.push((map_exp_visitor_bind(e, map, f)?, typ.clone()));
+                    }
+                    bvars.push((binder.name.clone(), true));
+                    for t in ts.iter() {
+                        let mut exprs: Vec<Exp> = Vec::new();
+                        for exp in t.iter() {
+                            exprs.push(map_exp_visitor_bind(exp, map, f)?);
+                        }
+                        triggers.push(Arc::new(exprs));
+                    }
+                    let triggers = Arc::new(triggers);
+                    BndX::Choose(binder.clone(), triggers, Arc::new(vars), typ_params.clone())
+                }
             };
             let bnd = Spanned::new(bnd.span.clone(), bndx);
             map.push_scope(true);
@@ -127,23 +173,65 @@ pub(crate) fn map_stm_visitor<F>(stm: &Stm, f: &mut F) -> Result<Stm, VirErr>
     match &stm.x {
         StmX::Call(..) => f(stm),
         StmX::Assert(_) => f(stm),
+        StmX::AssertBV(_) => f(stm),
         StmX::Assume(_) => f(stm),
         StmX::Assign { .. } => f(stm),
         StmX::Fuel(..) => f(stm),
+        StmX::Return => f(stm),
+        StmX::BreakOrContinue { .. } => f(stm),
+        StmX::AssertBy { vars, require, ensure, proof } => {
+            let proof = map_stm_visitor(proof, f)?;
+            let stm = Spanned::new(
+                stm.span.clone(),
+                StmX::AssertBy {
+                    vars: vars.clone(),
+                    require: require.clone(),
+                    ensure: ensure.clone(),
+                    proof,
+                },
+            );
+            f(&stm)
+        }
+        StmX::AssertNonlinear { requires, ensures, proof } => {
+            let proof = map_stm_visitor(proof, f)?;
+            let stm = Spanned::new(
+                stm.span.clone(),
+                StmX::AssertNonlinear {
+                    requires: requires.clone(),
+                    ensures: ensures.clone(),
+                    proof,
+                },
+            );
+            f(&stm)
+        }
         StmX::If(cond, lhs, rhs) => {
             let lhs = map_stm_visitor(lhs, f)?;
             let rhs = rhs.as_ref().map(|rhs| map_stm_visitor(rhs, f)).transpose()?;
             let stm = Spanned::new(stm.span.clone(), StmX::If(cond.clone(), lhs, rhs));
             f(&stm)
         }
-        StmX::While { cond, body, invs, typ_inv_vars, modified_vars } => {
+        StmX::Loop {
+            label,
+            cond,
+            body,
+            invs,
+            enss,
+            has_break,
+            decrease,
+            typ_inv_vars,
+            modified_vars,
+        } => {
             let body = map_stm_visitor(body, f)?;
             let stm = Spanned::new(
                 stm.span.clone(),
-                StmX::While {
+                StmX::Loop {
+                    label: label.clone(),
                     cond: cond.clone(),
                     body,
                     invs: invs.clone(),
+                    enss: enss.clone(),
+                    has_break: *has_break,
+                    decrease: decrease.clone(),
                     typ_inv_vars: typ_inv_vars.clone(),
                     modified_vars: modified_vars.clone(),
                 },
@@ -173,25 +261,61 @@ pub(crate) fn map_stm_exp_visitor<F>(stm: &Stm, f: &F) -> Result<Stm, VirErr>
                 Spanned::new(span, StmX::Call(path.clone(), typs.clone(), exps, (*dest).clone()))
             }
             StmX::Assert(exp) => Spanned::new(span, StmX::Assert(f(exp))),
+            StmX::AssertBV(exp) => Spanned::new(span, StmX::AssertBV(f(exp))),
             StmX::Assume(exp) => Spanned::new(span, StmX::Assume(f(exp))),
+            StmX::AssertBy { vars, require, ensure, proof } => Spanned::new(
+                span,
+                StmX::AssertBy {
+                    vars: vars.clone(),
+                    require: f(require),
+                    ensure: f(ensure),
+                    proof: proof.clone(),
+                },
+            ),
+            StmX::AssertNonlinear { requires, ensures, proof } => Spanned::new(
+                span,
+                StmX::AssertNonlinear {
+                    requires: Arc::new(vec_map(requires, f)),
+                    ensures: Arc::new(vec_map(ensures, f)),
+                    proof: proof.clone(),
+                },
+            ),
             StmX::Assign { lhs, rhs, is_init } => {
                 let rhs = f(rhs);
                 Spanned::new(span, StmX::Assign { lhs: lhs.clone(), rhs, is_init: *is_init })
             }
             StmX::Fuel(..) => stm.clone(),
+            StmX::Return => stm.clone(),
+            StmX::BreakOrContinue { .. } => stm.clone(),
             StmX::If(exp, s1, s2) => {
                 let exp = f(exp);
                 Spanned::new(span, StmX::If(exp, s1.clone(), s2.clone()))
             }
-            StmX::While { cond, body, invs, typ_inv_vars, modified_vars } => {
-                let cond = f(cond);
+            StmX::Loop {
+                label,
+                cond,
+                body,
+                invs,
+                enss,
+                has_break,
+                decrease,
+                typ_inv_vars,
+                modified_vars,
+            } => {
+                let cond = cond.as_ref().map(f);
                 let invs = Arc::new(vec_map(invs, f));
+                let enss = Arc::new(vec_map(enss, f));
+                let decrease = decrease.as_ref().map(f);
                 Spanned::new(
                     span,
-                    StmX::While {
+                    StmX::Loop {
+                        label: label.clone(),
                         cond,
                         body: body.clone(),
                         invs,
+                        enss,
+                        has_break: *has_break,
+                        decrease,
                         typ_inv_vars: typ_inv_vars.clone(),
                         modified_vars: modified_vars.clone(),
                     },
@@ -0,0 +1,117 @@
+use crate::ast::{
+    Expr, ExprX, Function, FunctionKind, FunctionX, Ident, Krate, KrateX, Path, SpannedTyped, Typ,
+    TypX, UnaryOpr, VirErr,
+};
+use crate::ast_util::err_str;
+use crate::ast_visitor::{map_expr_visitor, map_typ_visitor_env};
+use crate::def::{Spanned, TRAIT_SELF_TYPE_PARAM};
+use crate::util::vec_map_result;
+use std::collections::HashMap;
+use std::sync::Arc;
+
+fn is_self_typ(typ: &Typ) -> bool {
+    match &**typ {
+        TypX::TypParam(x) => **x == TRAIT_SELF_TYPE_PARAM,
+        _ => false,
+    }
+}
+
+fn subst_self_typ(typ: &Typ, self_typ: &Typ) -> Result<Typ, VirErr> {
+    map_typ_visitor_env(typ, &mut (), &|_, t: &Typ| {
+        if is_self_typ(t) {
+            Ok(self_typ.clone())
+        } else {
+            Ok(t.clone())
+        }
+    })
+}
+
+fn mentions_self_typ(typ: &Typ) -> bool {
+    let mut found = false;
+    let _ = map_typ_visitor_env(typ, &mut found, &|found: &mut bool, t: &Typ| {
+        *found = *found || is_self_typ(t);
+        Ok(t.clone())
+    });
+    found
+}
+
+// Rewrite a requires/ensures expression of the trait method declaration `decl`
+// so that it refers to the parameters of the trait method implementation `function`.
+// Values of type Self are boxed in the declaration, so the implementation's values are boxed
+// wherever the declaration expects a Self.
+fn rename_decl_expr(
+    renames: &HashMap<Ident, (Ident, Typ, bool)>,
+    self_typ: &Typ,
+    expr: &Expr,
+) -> Result<Expr, VirErr> {
+    map_expr_visitor(expr, &mut |expr: &Expr| {
+        let rename = |x: &Ident, mk: &dyn Fn(Ident) -> ExprX| match renames.get(x) {
+            None => expr.clone(),
+            Some((y, impl_typ, is_self)) => {
+                let var = expr.new_x(mk(y.clone()));
+                if *is_self {
+                    let op = UnaryOpr::Box(impl_typ.clone());
+                    SpannedTyped::new(&expr.span, &expr.typ, ExprX::UnaryOpr(op, var))
+                } else {
+                    var
+                }
+            }
+        };
+        match &expr.x {
+            ExprX::Var(x) => Ok(rename(x, &|y| ExprX::Var(y))),
+            ExprX::VarAt(x, at) => Ok(rename(x, &|y| ExprX::VarAt(y, *at))),
+            ExprX::Call(path, typs, args) => {
+                let typs = vec_map_result(&**typs, |t| subst_self_typ(t, self_typ))?;
+                Ok(expr.new_x(ExprX::Call(path.clone(), Arc::new(typs), args.clone())))
+            }
+            ExprX::Quant(_, binders, _) if binders.iter().any(|b| mentions_self_typ(&b.a)) => {
+                err_str(&expr.span, "not yet supported: quantifier over Self in trait method spec")
+            }
+            ExprX::Choose(binder, _) if mentions_self_typ(&binder.a) => {
+                err_str(&expr.span, "not yet supported: choose over Self in trait method spec")
+            }
+            _ => Ok(expr.clone()),
+        }
+    })
+}
+
+fn inherit_specs(decl: &Function, function: &Function) -> Result<Function, VirErr> {
+    let self_typ = &function.x.params[0].x.typ;
+    let mut renames: HashMap<Ident, (Ident, Typ, bool)> = HashMap::new();
+    for (decl_param, param) in decl.x.params.iter().zip(function.x.params.iter()) {
+        let is_self = is_self_typ(&decl_param.x.typ);
+        renames.insert(
+            decl_param.x.name.clone(),
+            (param.x.name.clone(), param.x.typ.clone(), is_self),
+        );
+    }
+    let (decl_ret, ret) = (&decl.x.ret.x, &function.x.ret.x);
+    renames.insert(
+        decl_ret.name.clone(),
+        (ret.name.clone(), ret.typ.clone(), is_self_typ(&decl_ret.typ)),
+    );
+    let require = vec_map_result(&*decl.x.require, |e| rename_decl_expr(&renames, self_typ, e))?;
+    let ensure = vec_map_result(&*decl.x.ensure, |e| rename_decl_expr(&renames, self_typ, e))?;
+    let functionx = FunctionX {
+        require: Arc::new(require),
+        ensure: Arc::new(ensure),
+        custom_req_err: decl.x.custom_req_err.clone(),
+        ..function.x.clone()
+    };
+    Ok(Spanned::new(function.span.clone(), functionx))
+}
+
+/// Each trait method implementation inherits the requires/ensures of its trait method declaration
+pub(crate) fn inherit_trait_specs(krate: &Krate) -> Result<Krate, VirErr> {
+    let decls: HashMap<Path, Function> = krate
+        .functions
+        .iter()
+        .filter(|f| matches!(f.x.kind, FunctionKind::TraitMethodDecl { .. }))
+        .map(|f| (f.x.path.clone(), f.clone()))
+        .collect();
+    let functions = vec_map_result(&krate.functions, |function| match &function.x.kind {
+        FunctionKind::TraitMethodImpl { method, .. } => inherit_specs(&decls[method], function),
+        _ => Ok(function.clone()),
+    })?;
+    Ok(Arc::new(KrateX { functions, ..(**krate).clone() }))
+}
@@ -1,4 +1,4 @@
-use crate::ast::{BinaryOp, Ident, UnaryOp, UnaryOpr, VirErr};
+use crate::ast::{BinaryOp, Ident, MapOp, SeqOp, SetOp, UnaryOp, UnaryOpr, VirErr};
 use crate::ast_util::{err_str, err_string};
 use crate::context::Ctx;
 use crate::sst::{BndX, Exp, ExpX, Trig, Trigs};
@@ -27,6 +27,12 @@ fn check_trigger_expr(exp: &Exp, free_vars: &mut HashSet<Ident>) -> Result<(), V
     match &exp.x {
         ExpX::Call(..)
         | ExpX::UnaryOpr(UnaryOpr::Field { .. }, _)
+        | ExpX::SeqOp(
+            SeqOp::Push | SeqOp::Len | SeqOp::Index | SeqOp::Update | SeqOp::Subrange,
+            _,
+        )
+        | ExpX::SetOp(SetOp::Contains | SetOp::Insert | SetOp::Remove, _)
+        | ExpX::MapOp(MapOp::Dom | MapOp::Index | MapOp::Insert | MapOp::Remove, _)
         | ExpX::Unary(UnaryOp::Trigger(_), _) => {}
         // REVIEW: Z3 allows some arithmetic, but it's not clear we want to allow it
         _ => {
@@ -40,9 +46,10 @@ fn check_trigger_expr(exp: &Exp, free_vars: &mut HashSet<Ident>) -> Result<(), V
             Ok(exp.clone())
         }
         ExpX::Var((_, Some(_))) => Ok(exp.clone()),
+        ExpX::Loc(_) => panic!("internal error: Loc"),
         ExpX::Old(_, _) => panic!("internal error: Old"),
         ExpX::Unary(op, _) => match op {
-            UnaryOp::Trigger(_) | UnaryOp::Clip(_) => Ok(exp.clone()),
+            UnaryOp::Trigger(_) | UnaryOp::Clip { .. } | UnaryOp::BitNot => Ok(exp.clone()),
             UnaryOp::Not => err_str(&exp.span, "triggers cannot contain boolean operators"),
         },
         ExpX::UnaryOpr(op, _) => match op {
@@ -59,9 +66,13 @@ fn check_trigger_expr(exp: &Exp, free_vars: &mut HashSet<Ident>) -> Result<(), V
                     err_str(&exp.span, "triggers cannot contain boolean operators")
                 }
                 Le | Ge | Lt | Gt => Ok(exp.clone()),
-                Add | Sub | Mul | EuclideanDiv | EuclideanMod => Ok(exp.clone()),
+                Add | Sub | Mul | EuclideanDiv | EuclideanMod | TruncDiv | TruncMod => {
+                    Ok(exp.clone())
+                }
+                BitAnd | BitOr | BitXor | Shr | Shl => Ok(exp.clone()),
             }
         }
+        ExpX::SeqOp(..) | ExpX::SetOp(..) | ExpX::MapOp(..) => Ok(exp.clone()),
         ExpX::If(_, _, _) => err_str(&exp.span, "triggers cannot contain if/else"),
         ExpX::Bind(_, _) => err_str(&exp.span, "triggers cannot contain let/forall/exists"),
     };
@@ -104,6 +115,7 @@ fn get_manual_triggers(state: &mut State, exp: &Exp) -> Result<(), VirErr> {
             let bvars: Vec<Ident> = match &bnd.x {
                 BndX::Let(binders) => binders.iter().map(|b| b.name.clone()).collect(),
                 BndX::Quant(_, binders, _) => binders.iter().map(|b| b.name.clone()).collect(),
+                BndX::Choose(binder, _, _, _) => vec![binder.name.clone()],
             };
             for x in bvars {
                 if map.contains_key(&x) {
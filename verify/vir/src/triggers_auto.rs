@@ -1,4 +1,4 @@
-use crate::ast::{BinaryOp, Constant, Ident, Path, UnaryOp, UnaryOpr, VirErr};
+use crate::ast::{BinaryOp, Constant, Ident, MapOp, Path, SeqOp, SetOp, UnaryOp, UnaryOpr, VirErr};
 use crate::ast_util::err_str;
 use crate::context::Ctx;
 use crate::sst::{Exp, ExpX, Trig, Trigs, UniqueIdent};
@@ -33,7 +33,10 @@ enum App {
     Field(Path, Ident, Ident),
     Call(Path),
     Ctor(Path, Ident), // datatype constructor: (Path, Variant)
-    Other(u64),        // u64 is an id, assigned via a simple counter
+    SeqOp(SeqOp),
+    SetOp(SetOp),
+    MapOp(MapOp),
+    Other(u64), // u64 is an id, assigned via a simple counter
 }
 
 type Term = Arc<TermX>;
@@ -50,9 +53,16 @@ fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> Result<(), std::fmt::Error> {
             TermX::Var(x) => write!(f, "{:?}", x),
             TermX::App(App::Const(c), _) => write!(f, "{:?}", c),
             TermX::App(App::Field(_, x, y), es) => write!(f, "{:?}.{}/{}", es[0], x, y),
-            TermX::App(c @ (App::Call(_) | App::Ctor(_, _)), es) => {
+            TermX::App(
+                c
+                @ (App::Call(_) | App::Ctor(_, _) | App::SeqOp(_) | App::SetOp(_) | App::MapOp(_)),
+                es,
+            ) => {
                 match c {
                     App::Call(x) => write!(f, "{:?}(", x)?,
+                    App::SeqOp(op) => write!(f, "{:?}(", op)?,
+                    App::SetOp(op) => write!(f, "Set{:?}(", op)?,
+                    App::MapOp(op) => write!(f, "Map{:?}(", op)?,
                     App::Ctor(path, variant) => {
                         write!(f, "{}(", crate::def::variant_ident(path, variant))?
                     }
@@ -180,6 +190,7 @@ fn gather_terms(ctxt: &mut Ctxt, ctx: &Ctx, exp: &Exp, depth: u64) -> (bool, Ter
         ExpX::Var(x) => {
             return (true, Arc::new(TermX::Var(x.clone())));
         }
+        ExpX::Loc(_) => panic!("internal error: Loc"),
         ExpX::Old(_, _) => panic!("internal error: Old"),
         ExpX::Call(x, _, args) => {
             let (is_pures, terms): (Vec<bool>, Vec<Term>) =
@@ -198,7 +209,7 @@ fn gather_terms(ctxt: &mut Ctxt, ctx: &Ctx, exp: &Exp, depth: u64) -> (bool, Ter
         ExpX::Unary(op, e1) => {
             let depth = match op {
                 UnaryOp::Not => 0,
-                UnaryOp::Trigger(_) | UnaryOp::Clip(_) => 1,
+                UnaryOp::Trigger(_) | UnaryOp::Clip { .. } | UnaryOp::BitNot => 1,
             };
             let (_, term1) = gather_terms(ctxt, ctx, e1, depth);
             ctxt.next_id += 1;
@@ -230,13 +241,36 @@ fn gather_terms(ctxt: &mut Ctxt, ctx: &Ctx, exp: &Exp, depth: u64) -> (bool, Ter
             use BinaryOp::*;
             let depth = match op {
                 And | Or | Implies | Eq(_) => 0,
-                Ne | Le | Ge | Lt | Gt | Add | Sub | Mul | EuclideanDiv | EuclideanMod => 1,
+                Ne | Le | Ge | Lt | Gt | Add | Sub | Mul | EuclideanDiv | EuclideanMod
+                | TruncDiv | TruncMod | BitAnd | BitOr | BitXor | Shr | Shl => 1,
             };
             let (_, term1) = gather_terms(ctxt, ctx, e1, depth);
             let (_, term2) = gather_terms(ctxt, ctx, e2, depth);
             ctxt.next_id += 1;
             (false, Arc::new(TermX::App(App::Other(ctxt.next_id), Arc::new(vec![term1, term2]))))
         }
+        // Arrays and slices are already represented as sequences
+        ExpX::SeqOp(SeqOp::View, es) => gather_terms(ctxt, ctx, &es[0], depth),
+        // Like equality, extensional equality is not a trigger
+        ExpX::SeqOp(SeqOp::ExtEqual, es)
+        | ExpX::SetOp(SetOp::ExtEqual, es)
+        | ExpX::MapOp(MapOp::ExtEqual, es) => {
+            let terms: Vec<Term> = es.iter().map(|e| gather_terms(ctxt, ctx, e, 0).1).collect();
+            ctxt.next_id += 1;
+            (false, Arc::new(TermX::App(App::Other(ctxt.next_id), Arc::new(terms))))
+        }
+        ExpX::SeqOp(_, es) | ExpX::SetOp(_, es) | ExpX::MapOp(_, es) => {
+            let app = match &exp.x {
+                ExpX::SeqOp(op, _) => App::SeqOp(*op),
+                ExpX::SetOp(op, _) => App::SetOp(*op),
+                ExpX::MapOp(op, _) => App::MapOp(*op),
+                _ => unreachable!(),
+            };
+            let (is_pures, terms): (Vec<bool>, Vec<Term>) =
+                es.iter().map(|e| gather_terms(ctxt, ctx, e, depth + 1)).unzip();
+            let is_pure = is_pures.into_iter().all(|b| b);
+            (is_pure, Arc::new(TermX::App(app, Arc::new(terms))))
+        }
         ExpX::If(e1, e2, e3) => {
             let depth = 1;
             let (_, term1) = gather_terms(ctxt, ctx, e1, depth);
@@ -1,7 +1,11 @@
-use crate::ast::{Datatype, Expr, ExprX, Function, Krate, Mode, Path, UnaryOpr, VirErr};
-use crate::ast_util::err_string;
+use crate::ast::{
+    Datatype, Expr, ExprX, Function, FunctionKind, FunctionX, GenericBoundX, Krate, Mode, Path,
+    Typ, TypX, UnaryOpr, VarAt, VirErr,
+};
+use crate::ast_util::{err_str, err_string, path_as_rust_name};
 use crate::ast_visitor::map_expr_visitor;
 use crate::datatype_to_air::is_datatype_transparent;
+use air::ast::Span;
 use std::collections::HashMap;
 
 struct Ctxt {
@@ -9,9 +13,153 @@ struct Ctxt {
     pub(crate) dts: HashMap<Path, Datatype>,
 }
 
+fn check_trait_method_impl(ctxt: &Ctxt, function: &Function, method: &Path) -> Result<(), VirErr> {
+    let decl = match ctxt.funs.get(method) {
+        Some(decl) => decl,
+        None => return err_str(&function.span, "could not find trait method declaration"),
+    };
+    if function.x.mode != decl.x.mode {
+        return err_string(
+            &function.span,
+            format!("trait method implementation must have mode {}", decl.x.mode),
+        );
+    }
+    if function.x.params.len() != decl.x.params.len() {
+        return err_str(
+            &function.span,
+            "trait method implementation has wrong number of parameters",
+        );
+    }
+    for (param, decl_param) in function.x.params.iter().zip(decl.x.params.iter()) {
+        if param.x.mode != decl_param.x.mode {
+            return err_string(
+                &param.span,
+                format!("parameter must have mode {}, as in the trait", decl_param.x.mode),
+            );
+        }
+    }
+    if function.x.require.len() > 0 || function.x.ensure.len() > 0 {
+        return err_str(
+            &function.span,
+            "trait method implementation cannot declare requires/ensures; these are inherited from the trait",
+        );
+    }
+    Ok(())
+}
+
+// A call through a trait method declaration, with a type parameter as Self,
+// needs the type parameter to be bounded by the trait
+fn check_trait_bound(
+    function: &Function,
+    span: &Span,
+    trait_path: &Path,
+    self_typ: &Typ,
+) -> Result<(), VirErr> {
+    if let TypX::TypParam(t) = &**self_typ {
+        let bounded = function.x.typ_params.iter().any(|(x, bound)| {
+            x == t
+                && match &**bound {
+                    GenericBoundX::Traits(traits) => traits.contains(trait_path),
+                }
+        });
+        if !bounded {
+            return err_string(
+                span,
+                format!(
+                    "type parameter {} must be bounded by trait {}",
+                    t,
+                    path_as_rust_name(trait_path)
+                ),
+            );
+        }
+    }
+    Ok(())
+}
+
 fn check_function(ctxt: &Ctxt, function: &Function) -> Result<(), VirErr> {
+    if let FunctionKind::TraitMethodImpl { method, .. } = &function.x.kind {
+        check_trait_method_impl(ctxt, function, method)?;
+    }
+    for param in function.x.params.iter() {
+        if param.x.is_mut && function.x.mode == Mode::Spec {
+            return err_string(
+                &param.span,
+                format!("spec function cannot have &mut parameter {}", param.x.name),
+            );
+        }
+    }
+    if function.x.bit_vector {
+        if function.x.mode != Mode::Proof {
+            return err_str(
+                &function.span,
+                "#[verifier(bit_vector)] is only allowed on proof functions",
+            );
+        }
+        if function.x.params.iter().any(|p| p.x.is_mut) {
+            return err_str(
+                &function.span,
+                "#[verifier(bit_vector)] functions cannot have &mut parameters",
+            );
+        }
+    }
+    if function.x.nonlinear && function.x.mode != Mode::Proof {
+        return err_str(
+            &function.span,
+            "#[verifier(nonlinear)] is only allowed on proof functions",
+        );
+    }
+    if function.x.broadcast {
+        if function.x.mode != Mode::Proof {
+            return err_str(
+                &function.span,
+                "#[verifier(broadcast)] is only allowed on proof functions",
+            );
+        }
+        if function.x.params.iter().any(|p| p.x.is_mut) {
+            return err_str(
+                &function.span,
+                "#[verifier(broadcast)] functions cannot have &mut parameters",
+            );
+        }
+        if function.x.has_return() {
+            return err_str(
+                &function.span,
+                "#[verifier(broadcast)] functions cannot have a return value",
+            );
+        }
+        if function.x.params.len() == 0 || function.x.ensure.len() == 0 {
+            return err_str(
+                &function.span,
+                "#[verifier(broadcast)] functions must have parameters and ensures",
+            );
+        }
+    }
+    let check_common = |expr: &Expr| match &expr.x {
+        ExprX::VarAt(x, VarAt::Pre) => {
+            if function.x.mode == Mode::Spec {
+                err_str(&expr.span, "old is not allowed in spec functions")
+            } else if !function.x.params.iter().any(|p| p.x.name == *x) {
+                err_string(&expr.span, format!("old can only be applied to a parameter, not {}", x))
+            } else {
+                Ok(expr.clone())
+            }
+        }
+        ExprX::Call(x, typs, _) => {
+            if let Some(FunctionX { kind: FunctionKind::TraitMethodDecl { trait_path }, .. }) =
+                ctxt.funs.get(x).map(|f| &f.x)
+            {
+                check_trait_bound(function, &expr.span, trait_path, &typs[0])?;
+            }
+            Ok(expr.clone())
+        }
+        _ => Ok(expr.clone()),
+    };
+    for e in function.x.require.iter().chain(function.x.ensure.iter()) {
+        map_expr_visitor(e, &mut |expr: &Expr| check_common(expr))?;
+    }
     if let Some(body) = &function.x.body {
         map_expr_visitor(body, &mut |expr: &Expr| {
+            check_common(expr)?;
             match &expr.x {
                 ExprX::Call(x, _, _) => {
                     // Check that public, non-abstract spec function bodies don't refer to private items
@@ -44,6 +192,11 @@ fn check_function(ctxt: &Ctxt, function: &Function) -> Result<(), VirErr> {
                         panic!("constructor of undefined datatype");
                     }
                 }
+                ExprX::Fuel(x, _) if ctxt.funs.get(x).map_or(false, |f| f.x.broadcast) => {
+                    // Broadcast functions are visible by default; revealing them could bring
+                    // them back into the functions that must be verified without them
+                    return err_str(&expr.span, "cannot reveal a #[verifier(broadcast)] function");
+                }
                 // TODO: disallow private fields, unless function is marked #[verifier(pub_abstract)]
                 ExprX::UnaryOpr(UnaryOpr::Field { datatype: path, .. }, _) => {
                     if let Some(dt) = ctxt.dts.get(path) {